@@ -0,0 +1,416 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use solana_sdk::{bs58, pubkey::Pubkey};
+use yellowstone_grpc_proto::prelude::subscribe_update::UpdateOneof;
+
+use crate::{
+    curve::Curve,
+    monitor::{convert_to_encoded_tx, parse_events, ComputeBudget, TargetEvent, TradeEvent},
+    pnl::LAMPORTS_PER_SIGNATURE,
+    replay::{replay_stream, Playback},
+    store::{Store, Stored},
+    utils::should_sell,
+};
+
+/// 回测的一组策略参数：每个 mint 的第一笔成交时开仓，按实盘的退出规则平仓
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StrategyConfig {
+    pub name: String,
+    /// 每个 mint 买入花费的 SOL（lamports，含 pump.fun 手续费）
+    pub buy_lamports: u64,
+    /// 同实盘 `MIN_PROFIT`：扣除成本和手续费后的利润达到成本加该值（lamports）时卖出
+    pub min_profit: u64,
+    pub slippage_bps: u64,
+    /// 从发出信号到交易落地的 slot 数
+    pub delay_slots: u64,
+    /// 每笔交易的网络手续费和 tip
+    pub tx_cost: u64,
+}
+
+impl Default for StrategyConfig {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            buy_lamports: 100_000_000,
+            min_profit: 0,
+            slippage_bps: 1500,
+            delay_slots: 1,
+            tx_cost: LAMPORTS_PER_SIGNATURE + ComputeBudget::default().priority_fee(),
+        }
+    }
+}
+
+/// 一次完整的买入和卖出
+#[derive(Debug, Clone)]
+pub struct RoundTrip {
+    pub mint: Pubkey,
+    pub entry_slot: u64,
+    pub exit_slot: u64,
+    pub tokens: u64,
+    /// 买入花费，含手续费和失败交易的成本
+    pub cost: u64,
+    /// 卖出收到的 SOL，已扣除手续费
+    pub proceeds: u64,
+    /// 数据结束时仍未卖出，按最后的曲线状态估值
+    pub forced: bool,
+}
+
+impl RoundTrip {
+    pub fn pnl(&self) -> i64 {
+        self.proceeds as i64 - self.cost as i64
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BacktestReport {
+    pub name: String,
+    pub mints: usize,
+    /// 按卖出 slot 排序
+    pub round_trips: Vec<RoundTrip>,
+    /// 因滑点失败的交易数
+    pub failed_fills: usize,
+    /// 买入失败、没有开仓的 mint 的损失
+    pub missed_cost: u64,
+}
+
+impl BacktestReport {
+    pub fn pnl(&self) -> i64 {
+        self.round_trips.iter().map(RoundTrip::pnl).sum::<i64>() - self.missed_cost as i64
+    }
+
+    pub fn wins(&self) -> usize {
+        self.round_trips.iter().filter(|trip| trip.pnl() > 0).count()
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        if self.round_trips.is_empty() {
+            return 0.0;
+        }
+        self.wins() as f64 / self.round_trips.len() as f64
+    }
+
+    /// 按卖出顺序累计盈亏的最大回撤（lamports）
+    pub fn max_drawdown(&self) -> u64 {
+        let mut equity = 0i64;
+        let mut peak = 0i64;
+        let mut drawdown = 0u64;
+        for trip in &self.round_trips {
+            equity += trip.pnl();
+            peak = peak.max(equity);
+            drawdown = drawdown.max((peak - equity) as u64);
+        }
+        drawdown
+    }
+}
+
+enum Position {
+    Flat,
+    Buying {
+        min_tokens: u64,
+        land_slot: u64,
+    },
+    Holding {
+        tokens: u64,
+        cost: u64,
+        entry_slot: u64,
+    },
+    Selling {
+        tokens: u64,
+        cost: u64,
+        entry_slot: u64,
+        min_sol: u64,
+        land_slot: u64,
+    },
+    Done,
+}
+
+fn min_out(amount: u64, slippage_bps: u64) -> u64 {
+    (amount as u128 * 10_000u128.saturating_sub(slippage_bps as u128) / 10_000) as u64
+}
+
+struct Simulation<'a> {
+    config: &'a StrategyConfig,
+    mint: Pubkey,
+    position: Position,
+    failed_fills: usize,
+    /// 失败交易的成本，计入下一次成交
+    wasted: u64,
+    round_trip: Option<RoundTrip>,
+}
+
+impl Simulation<'_> {
+    /// 挂单在 `curve`（落地 slot 结束时的状态）上成交
+    fn settle(&mut self, curve: &Curve) {
+        let config = self.config;
+        self.position = match std::mem::replace(&mut self.position, Position::Done) {
+            Position::Buying { min_tokens, land_slot } => {
+                let quote = curve.buy_with_sol(config.buy_lamports);
+                if quote.tokens == 0 || quote.tokens < min_tokens {
+                    self.failed_fills += 1;
+                    self.wasted += config.tx_cost;
+                    Position::Done
+                } else {
+                    let cost = quote.sol_in() + config.tx_cost + std::mem::take(&mut self.wasted);
+                    Position::Holding {
+                        tokens: quote.tokens,
+                        cost,
+                        entry_slot: land_slot,
+                    }
+                }
+            }
+            Position::Selling {
+                tokens,
+                cost,
+                entry_slot,
+                min_sol,
+                land_slot,
+            } => {
+                let quote = curve.sell(tokens);
+                if quote.sol_out() < min_sol {
+                    self.failed_fills += 1;
+                    Position::Holding {
+                        tokens,
+                        cost: cost + config.tx_cost,
+                        entry_slot,
+                    }
+                } else {
+                    self.round_trip = Some(RoundTrip {
+                        mint: self.mint,
+                        entry_slot,
+                        exit_slot: land_slot,
+                        tokens,
+                        cost: cost + config.tx_cost,
+                        proceeds: quote.sol_out(),
+                        forced: false,
+                    });
+                    Position::Done
+                }
+            }
+            other => other,
+        };
+    }
+
+    /// 根据成交后的曲线状态决定是否发出信号
+    fn signal(&mut self, slot: u64, curve: &Curve) {
+        let config = self.config;
+        self.position = match std::mem::replace(&mut self.position, Position::Done) {
+            Position::Flat => Position::Buying {
+                min_tokens: min_out(curve.buy_with_sol(config.buy_lamports).tokens, config.slippage_bps),
+                land_slot: slot + config.delay_slots,
+            },
+            Position::Holding {
+                tokens,
+                cost,
+                entry_slot,
+            } => {
+                let sol_out = curve.sell(tokens).sol_out();
+                let pnl = sol_out as i64 - config.tx_cost as i64 - cost as i64;
+                if should_sell(pnl as f32, cost.saturating_add(config.min_profit)) {
+                    Position::Selling {
+                        tokens,
+                        cost,
+                        entry_slot,
+                        min_sol: min_out(sol_out, config.slippage_bps),
+                        land_slot: slot + config.delay_slots,
+                    }
+                } else {
+                    Position::Holding {
+                        tokens,
+                        cost,
+                        entry_slot,
+                    }
+                }
+            }
+            other => other,
+        };
+    }
+
+    fn land_slot(&self) -> Option<u64> {
+        match self.position {
+            Position::Buying { land_slot, .. } | Position::Selling { land_slot, .. } => Some(land_slot),
+            _ => None,
+        }
+    }
+
+    /// 数据结束：挂单按最后状态成交，仍持仓的按最后状态估值
+    fn finish(&mut self, slot: u64, curve: &Curve) {
+        if self.land_slot().is_some() {
+            self.settle(curve);
+        }
+        if let Position::Holding {
+            tokens,
+            cost,
+            entry_slot,
+        } = self.position
+        {
+            self.round_trip = Some(RoundTrip {
+                mint: self.mint,
+                entry_slot,
+                exit_slot: slot,
+                tokens,
+                cost: cost + self.config.tx_cost,
+                proceeds: curve.sell(tokens).sol_out(),
+                forced: true,
+            });
+            self.position = Position::Done;
+        }
+    }
+}
+
+/// 按 mint 回放录制的成交。订单按落地 slot 末的曲线状态成交，自己的价格影响不改变录制的成交
+#[derive(Debug, Default)]
+pub struct Backtester {
+    history: HashMap<Pubkey, Vec<Stored<TradeEvent>>>,
+}
+
+impl Backtester {
+    pub fn new(history: HashMap<Pubkey, Vec<Stored<TradeEvent>>>) -> Self {
+        Self { history }
+    }
+
+    pub fn from_store(store: &Store) -> Result<Self> {
+        let mut history = HashMap::new();
+        for mint in store.traded_mints()? {
+            history.insert(mint, store.trades(&mint)?);
+        }
+        Ok(Self::new(history))
+    }
+
+    /// 从 `replay` 录制的 gRPC 流中解析成交记录
+    pub async fn from_recording(path: impl AsRef<Path>) -> Result<Self> {
+        let mut history: HashMap<Pubkey, Vec<Stored<TradeEvent>>> = HashMap::new();
+        let mut stream = replay_stream(path, Playback::AsFastAsPossible).await?;
+        while let Some(update) = stream.next().await {
+            let Some(UpdateOneof::Transaction(sub_tx)) = update?.update_oneof else {
+                continue;
+            };
+            let Some(tx_info) = sub_tx.transaction else {
+                continue;
+            };
+            let signature = bs58::encode(&tx_info.signature).into_string();
+            for event in parse_events(&convert_to_encoded_tx(tx_info)?) {
+                if let TargetEvent::PumpfunBuy(trade) | TargetEvent::PumpfunSell(trade) = event {
+                    history.entry(trade.mint).or_default().push(Stored {
+                        slot: sub_tx.slot,
+                        signature: signature.clone(),
+                        event: trade,
+                    });
+                }
+            }
+        }
+        for trades in history.values_mut() {
+            trades.sort_by_key(|trade| trade.slot);
+        }
+        Ok(Self::new(history))
+    }
+
+    pub fn mints(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn run(&self, config: &StrategyConfig) -> BacktestReport {
+        let mut report = BacktestReport {
+            name: config.name.clone(),
+            mints: self.history.len(),
+            ..Default::default()
+        };
+        for (mint, trades) in &self.history {
+            let mut sim = Simulation {
+                config,
+                mint: *mint,
+                position: Position::Flat,
+                failed_fills: 0,
+                wasted: 0,
+                round_trip: None,
+            };
+            let mut curve: Option<Curve> = None;
+            let mut last_slot = 0;
+            for trade in trades {
+                if let (Some(land_slot), Some(curve)) = (sim.land_slot(), &curve) {
+                    if trade.slot > land_slot {
+                        sim.settle(curve);
+                    }
+                }
+                let next = Curve::from(&trade.event);
+                curve = Some(next);
+                last_slot = trade.slot;
+                if next.is_complete() {
+                    break;
+                }
+                sim.signal(trade.slot, &next);
+            }
+            if let Some(curve) = &curve {
+                sim.finish(last_slot, curve);
+            }
+            report.failed_fills += sim.failed_fills;
+            report.missed_cost += sim.wasted;
+            report.round_trips.extend(sim.round_trip);
+        }
+        report.round_trips.sort_by_key(|trip| trip.exit_slot);
+        report
+    }
+}
+
+#[test]
+fn test_backtest_min_profit_and_slippage() {
+    use crate::monitor::{INIT_SOL_REVERSES, INIT_TOKEN_REVERSES};
+
+    let mint = Pubkey::new_unique();
+    let mut curve = Curve {
+        virtual_sol_reserves: INIT_SOL_REVERSES,
+        virtual_token_reserves: INIT_TOKEN_REVERSES,
+        real_sol_reserves: 0,
+        real_token_reserves: 793_100_000_000_000,
+    };
+    // 每个 slot 有人买入 1 SOL，价格持续上涨
+    let trades: Vec<_> = (0..20u64)
+        .map(|slot| {
+            let quote = curve.buy_with_sol(1_010_000_000);
+            curve.apply_buy(&quote);
+            Stored {
+                slot,
+                signature: String::new(),
+                event: TradeEvent {
+                    mint,
+                    sol_amount: quote.sol,
+                    token_amount: quote.tokens,
+                    is_buy: true,
+                    user: Pubkey::new_unique(),
+                    timestamp: slot as i64,
+                    virtual_sol_reserves: curve.virtual_sol_reserves,
+                    virtual_token_reserves: curve.virtual_token_reserves,
+                    real_sol_reserves: curve.real_sol_reserves,
+                    real_token_reserves: curve.real_token_reserves,
+                },
+            }
+        })
+        .collect();
+    let backtester = Backtester::new(HashMap::from([(mint, trades)]));
+
+    let report = backtester.run(&StrategyConfig {
+        min_profit: 10_000_000,
+        ..Default::default()
+    });
+    assert_eq!(report.round_trips.len(), 1);
+    let trip = &report.round_trips[0];
+    assert!(!trip.forced);
+    assert_eq!(trip.entry_slot, 1);
+    // 同实盘：利润达到成本加 `min_profit` 时卖出，成交价因滑点略低
+    assert!(trip.pnl() as f64 >= (trip.cost + 10_000_000) as f64 * 0.8);
+    assert_eq!(report.win_rate(), 1.0);
+    assert_eq!(report.max_drawdown(), 0);
+
+    // 延迟 3 个 slot、滑点 1% 时买入失败
+    let report = backtester.run(&StrategyConfig {
+        slippage_bps: 100,
+        delay_slots: 3,
+        ..Default::default()
+    });
+    assert!(report.round_trips.is_empty());
+    assert_eq!(report.failed_fills, 1);
+    assert!(report.pnl() < 0);
+}
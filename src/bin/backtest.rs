@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use solana_pump::{
+    backtest::{Backtester, StrategyConfig},
+    store::Store,
+};
+
+/// 用录制的 pump.fun 成交回测卖出策略
+#[derive(Parser)]
+struct Args {
+    /// `STORE_PATH` 写入的 SQLite 数据库
+    #[arg(long)]
+    store: Option<PathBuf>,
+    /// `RECORD_PATH` 录制的 gRPC 流
+    #[arg(long)]
+    recording: Option<PathBuf>,
+    /// 策略配置，JSON 数组，每项是一组 `StrategyConfig`；不指定时使用默认配置
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let backtester = match (&args.store, &args.recording) {
+        (Some(path), None) => Backtester::from_store(&Store::open(path)?)?,
+        (None, Some(path)) => Backtester::from_recording(path).await?,
+        _ => return Err(anyhow!("specify exactly one of --store or --recording")),
+    };
+    let configs: Vec<StrategyConfig> = match &args.config {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => vec![StrategyConfig::default()],
+    };
+    println!("回测 {} 个 mint", backtester.mints());
+
+    for config in &configs {
+        let report = backtester.run(config);
+        println!(
+            "{}: 交易 {} 次，胜率 {:.1}%，盈亏 {} SOL，最大回撤 {} SOL，失败 {} 笔，未平仓估值 {} 次",
+            report.name,
+            report.round_trips.len(),
+            report.win_rate() * 100.0,
+            report.pnl() as f64 / 1e9,
+            report.max_drawdown() as f64 / 1e9,
+            report.failed_fills,
+            report.round_trips.iter().filter(|trip| trip.forced).count(),
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use clap::Parser;
+use dotenv::dotenv;
+use solana_pump::{
+    config::{parse_keypair, parse_sol, LogFormat, LogSettings},
+    jito::{JitoPool, Selection, TipAccounts},
+    remote_signer::{SignPolicy, SigningDaemon},
+};
+use solana_sdk::signer::Signer;
+use tokio::net::UnixListener;
+use tracing::info;
+
+/// 签名进程：持有私钥，只为交易进程签 pump.fun 买卖和 Jito tip 交易
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+    /// 监听的 Unix socket
+    #[arg(long, env = "SIGNER_SOCKET")]
+    socket: PathBuf,
+    /// base58 私钥或 keypair 文件路径，加密的 keystore 从 PK_PASSPHRASE 读取口令或交互输入
+    #[arg(long, env = "PK", hide_env_values = true)]
+    pk: String,
+    /// 一笔交易中买入最多花费的 SOL
+    #[arg(long, value_parser = parse_sol)]
+    max_buy_sol: u64,
+    /// 一笔交易中卖出的 token 数量上限，不设置时不限制
+    #[arg(long)]
+    max_sell_tokens: Option<u64>,
+    /// 一笔交易中 Jito tip 之和上限（lamports）
+    #[arg(long, default_value_t = 1_000_000)]
+    max_tip: u64,
+    /// 优先费上限（lamports）
+    #[arg(long, default_value_t = 100_000)]
+    max_priority_fee: u64,
+    /// Jito block engine 地址，逗号分隔；设置时和交易进程一样定时刷新 tip 账户，否则只允许内置的 tip 账户
+    #[arg(long, env = "JITO")]
+    jito: Option<String>,
+    /// 日志级别，`tracing_subscriber::EnvFilter` 语法
+    #[arg(long, env = "LOG_LEVEL", default_value = "info")]
+    log_level: String,
+    #[arg(long, env = "LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+    let args = Args::parse();
+    LogSettings {
+        level: args.log_level.clone(),
+        format: args.log_format,
+    }
+    .init()?;
+    let keypair = parse_keypair(&args.pk)?;
+    // 和交易进程使用同一份 tip 账户列表，否则刷新后的 tip 账户会被拒绝
+    let tip_accounts = match &args.jito {
+        Some(urls) => {
+            let pool = Arc::new(JitoPool::from_env_list(urls, Selection::default()));
+            let tip_accounts = TipAccounts::fetch(&pool).await;
+            tip_accounts.spawn_refresh(pool, Duration::from_secs(300));
+            tip_accounts
+        }
+        None => TipAccounts::default(),
+    };
+    let policy = SignPolicy {
+        max_buy_lamports: args.max_buy_sol,
+        max_sell_tokens: args.max_sell_tokens,
+        max_tip_lamports: args.max_tip,
+        max_priority_fee: args.max_priority_fee,
+        tip_accounts,
+    };
+
+    // 清理上次退出时留下的 socket 文件
+    let _ = std::fs::remove_file(&args.socket);
+    let listener = UnixListener::bind(&args.socket)?;
+    // 只有同一用户可以连接
+    std::fs::set_permissions(&args.socket, std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    info!(wallet = %keypair.pubkey(), socket = %args.socket.display(), ?policy, "签名服务已启动");
+    Arc::new(SigningDaemon::new(keypair, policy)).serve(listener).await
+}
@@ -0,0 +1,533 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use solana_sdk::{bs58, pubkey::Pubkey, signature::Keypair};
+use tokio::sync::watch;
+use toml::{Table, Value};
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
+
+use crate::{
+    jito::Selection,
+    keystore::read_keypair,
+    monitor::{ComputeBudget, UNIT_LIMIT, UNIT_PRICE},
+    price::PriceFeed,
+    replay::Playback,
+    risk::RiskLimits,
+};
+
+/// 单笔交易的计算单元上限
+const MAX_UNIT_LIMIT: u32 = 1_400_000;
+
+/// 策略阈值，修改配置文件后热加载
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrategySettings {
+    /// 买入成本（lamports）
+    pub launch_cost: Option<u64>,
+    /// 扣除成本和 tip 后的最小利润（lamports）
+    pub min_profit: Option<u64>,
+    /// 自动卖出的滑点（bps）
+    pub slippage_bps: f64,
+    /// 手动清仓的滑点（bps）
+    pub exit_slippage_bps: f64,
+}
+
+impl Default for StrategySettings {
+    fn default() -> Self {
+        Self {
+            launch_cost: None,
+            min_profit: None,
+            slippage_bps: 1500.0,
+            exit_slippage_bps: 5000.0,
+        }
+    }
+}
+
+/// 手续费设置，修改配置文件后热加载
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeSettings {
+    pub unit_limit: u32,
+    /// 优先费单价（micro-lamports）
+    pub unit_price: u64,
+    /// Jito tip（lamports），配置了 tip floor 时作为最小值，0 表示不走 Jito
+    pub tip: u64,
+    /// 动态 tip 不超过预期利润的比例
+    pub tip_max_profit_fraction: Option<f64>,
+}
+
+impl Default for FeeSettings {
+    fn default() -> Self {
+        Self {
+            unit_limit: UNIT_LIMIT,
+            unit_price: UNIT_PRICE,
+            tip: 0,
+            tip_max_profit_fraction: None,
+        }
+    }
+}
+
+impl FeeSettings {
+    pub fn compute_budget(&self) -> ComputeBudget {
+        ComputeBudget {
+            unit_limit: self.unit_limit,
+            unit_price: self.unit_price,
+        }
+    }
+}
+
+/// 发送通道，修改后需要重启
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SendSettings {
+    /// 除 `rpc_url` 外同时发送的 RPC 节点
+    pub rpc_urls: Vec<String>,
+    /// 设置后通过 QUIC 直接发送给 leader 的 TPU
+    pub ws_url: Option<String>,
+    /// Jito 区域选择，默认发送给所有区域
+    pub jito_selection: Option<Selection>,
+}
+
+/// SOL/USD 价格源，没有设置的字段使用 `PriceOracle` 的默认值，修改后需要重启
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PriceSettings {
+    /// Pyth 价格账户，`v2:` 前缀为 `PriceUpdateV2`
+    pub feeds: Vec<String>,
+    /// 价格发布时间距今的最大秒数
+    pub max_age: Option<i64>,
+    /// conf / price 的最大比例
+    pub max_conf_ratio: Option<f64>,
+    /// 备用 HTTP 价格源
+    pub fallback_url: Option<String>,
+    /// 备用价格源响应中价格的 JSON pointer，默认 `/price`
+    pub fallback_pointer: Option<String>,
+}
+
+/// Geyser 流的录制和回放，修改后需要重启
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StreamSettings {
+    /// 回放录制文件代替 Geyser，只能和 `--paper` 一起使用
+    pub replay_path: Option<PathBuf>,
+    /// realtime、max 或倍速，默认 realtime
+    pub replay_speed: Option<String>,
+    /// 连接 Geyser 时同时录制到这个文件
+    pub record_path: Option<PathBuf>,
+}
+
+/// 日志输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象，包含所在 span 的字段
+    Json,
+}
+
+/// 日志设置，修改后需要重启
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogSettings {
+    /// `tracing_subscriber::EnvFilter` 语法，例如 `info` 或 `info,solana_pump::confirm=debug`
+    pub level: String,
+    pub format: LogFormat,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::Text,
+        }
+    }
+}
+
+impl LogSettings {
+    /// 安装全局的 tracing subscriber，日志写到 stdout。JSON 格式带上当前 span 和所有上层 span 的字段，
+    /// 可以按 signature 或 mint 追踪一笔交易从收到事件到落地的过程
+    pub fn init(&self) -> Result<()> {
+        let filter = EnvFilter::try_new(&self.level).map_err(|e| anyhow!("invalid log level {:?}: {}", self.level, e))?;
+        let builder = tracing_subscriber::fmt().with_env_filter(filter);
+        let result = match self.format {
+            LogFormat::Text => builder.try_init(),
+            LogFormat::Json => builder.json().with_current_span(true).with_span_list(true).try_init(),
+        };
+        result.map_err(|e| anyhow!("failed to install logger: {}", e))
+    }
+}
+
+/// TOML 配置。文件顶层是默认值，`[profiles.<name>]` 覆盖其中的部分字段，
+/// 顶层的 `profile` 指定默认使用的 profile：
+///
+/// ```toml
+/// profile = "mainnet-aggressive"
+/// rpc_url = "https://api.mainnet-beta.solana.com"
+///
+/// [strategy]
+/// min_profit = 10000000
+///
+/// [profiles.devnet]
+/// rpc_url = "https://api.devnet.solana.com"
+///
+/// [profiles.mainnet-aggressive.fees]
+/// unit_price = 200000
+/// tip = 100000
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub rpc_url: Option<String>,
+    pub grpc_url: Option<String>,
+    /// base58 私钥或 keypair 文件（keystore 或 `solana-keygen` JSON）路径
+    pub pk: Option<String>,
+    pub mint: Option<String>,
+    /// Jito block engine 地址，逗号分隔
+    pub jito: Option<String>,
+    /// 签名进程的 Unix socket，设置后私钥不进入交易进程
+    pub signer_socket: Option<PathBuf>,
+    /// 钱包池：多个私钥或 keypair 文件，设置后替代 `pk`
+    pub wallets: Vec<String>,
+    /// 事件、订单和成交写入的 SQLite 文件
+    pub store_path: Option<PathBuf>,
+    /// tip floor 地址，设置后按 tip floor 动态调整 tip
+    pub tip_floor_url: Option<String>,
+    pub strategy: StrategySettings,
+    pub fees: FeeSettings,
+    pub risk: RiskLimits,
+    pub log: LogSettings,
+    pub send: SendSettings,
+    pub price: PriceSettings,
+    pub stream: StreamSettings,
+}
+
+impl Config {
+    /// 读取配置文件，`profile` 为 None 时使用文件中的 `profile`
+    pub fn load(path: impl AsRef<Path>, profile: Option<&str>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        Self::parse(&text, profile).with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    pub fn parse(text: &str, profile: Option<&str>) -> Result<Self> {
+        let mut table: Table = toml::from_str(text)?;
+        let default_profile = match table.remove("profile") {
+            Some(Value::String(name)) => Some(name),
+            Some(other) => bail!("profile must be a string, got {}", other),
+            None => None,
+        };
+        let profiles = match table.remove("profiles") {
+            Some(Value::Table(profiles)) => profiles,
+            Some(other) => bail!("profiles must be a table, got {}", other),
+            None => Table::new(),
+        };
+
+        if let Some(name) = profile.map(str::to_string).or(default_profile) {
+            let overlay = match profiles.get(&name) {
+                Some(Value::Table(overlay)) => overlay,
+                Some(_) => bail!("profiles.{} must be a table", name),
+                None => bail!(
+                    "unknown profile {:?}, available: {}",
+                    name,
+                    profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                ),
+            };
+            merge(&mut table, overlay);
+        }
+
+        let config: Config = Value::Table(table).try_into()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let urls = [
+            ("rpc_url", self.rpc_url.as_ref()),
+            ("grpc_url", self.grpc_url.as_ref()),
+            ("tip_floor_url", self.tip_floor_url.as_ref()),
+            ("price.fallback_url", self.price.fallback_url.as_ref()),
+        ];
+        let send_urls = self.send.rpc_urls.iter().map(|url| ("send.rpc_urls", Some(url)));
+        for (name, url) in urls.into_iter().chain(send_urls) {
+            if let Some(url) = url {
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    bail!("{} must be an http(s) URL, got {:?}", name, url);
+                }
+            }
+        }
+        for feed in &self.price.feeds {
+            feed.parse::<PriceFeed>().with_context(|| format!("invalid price.feeds entry {:?}", feed))?;
+        }
+        if let Some(speed) = &self.stream.replay_speed {
+            speed.parse::<Playback>().with_context(|| format!("invalid stream.replay_speed {:?}", speed))?;
+        }
+        if let Some(mint) = &self.mint {
+            parse_mint(mint)?;
+        }
+        EnvFilter::try_new(&self.log.level).map_err(|e| anyhow!("invalid log.level {:?}: {}", self.log.level, e))?;
+        for (name, bps) in [
+            ("strategy.slippage_bps", self.strategy.slippage_bps),
+            ("strategy.exit_slippage_bps", self.strategy.exit_slippage_bps),
+            ("risk.max_slippage_bps", self.risk.max_slippage_bps),
+        ] {
+            if !(0.0..10_000.0).contains(&bps) {
+                bail!("{} must be in [0, 10000), got {}", name, bps);
+            }
+        }
+        // 否则风控会拒绝策略自己的卖出
+        for (name, bps) in [
+            ("strategy.slippage_bps", self.strategy.slippage_bps),
+            ("strategy.exit_slippage_bps", self.strategy.exit_slippage_bps),
+        ] {
+            if bps > self.risk.max_slippage_bps {
+                bail!("{} {} exceeds risk.max_slippage_bps {}", name, bps, self.risk.max_slippage_bps);
+            }
+        }
+        if self.risk.max_trades_per_minute == 0 {
+            bail!("risk.max_trades_per_minute must be at least 1");
+        }
+        if self.fees.unit_limit == 0 || self.fees.unit_limit > MAX_UNIT_LIMIT {
+            bail!("fees.unit_limit must be in [1, {}], got {}", MAX_UNIT_LIMIT, self.fees.unit_limit);
+        }
+        if let Some(fraction) = self.fees.tip_max_profit_fraction {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                bail!("fees.tip_max_profit_fraction must be in (0, 1], got {}", fraction);
+            }
+        }
+        Ok(())
+    }
+
+    /// 热加载只替换策略阈值、手续费和风控限制，连接、钱包、mint、日志等其他修改需要重启
+    pub fn reload(&self, new: Config) -> Config {
+        let restart = Config {
+            strategy: self.strategy.clone(),
+            fees: self.fees.clone(),
+            risk: self.risk.clone(),
+            ..new.clone()
+        } != *self;
+        if restart {
+            warn!("连接、钱包、mint 和日志配置的修改重启后生效");
+        }
+        Config {
+            strategy: new.strategy,
+            fees: new.fees,
+            risk: new.risk,
+            ..self.clone()
+        }
+    }
+}
+
+/// profile 中的表逐层合并，其他值直接覆盖
+fn merge(base: &mut Table, overlay: &Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 定时检查配置文件的修改时间，变化后重新加载并通过 watch 通知；加载或校验失败时保留旧配置
+pub fn watch_config(
+    path: PathBuf,
+    profile: Option<String>,
+    config: Config,
+    interval: Duration,
+) -> watch::Receiver<Config> {
+    let (tx, rx) = watch::channel(config);
+    tokio::spawn(async move {
+        let mut last_modified = modified(&path);
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if tx.is_closed() {
+                return;
+            }
+            let current = modified(&path);
+            if current == last_modified {
+                continue;
+            }
+            last_modified = current;
+            match Config::load(&path, profile.as_deref()) {
+                Ok(new) => {
+                    let reloaded = tx.borrow().reload(new);
+                    let changed = tx.send_if_modified(|config| {
+                        let changed = *config != reloaded;
+                        *config = reloaded;
+                        changed
+                    });
+                    if changed {
+                        info!(path = %path.display(), "配置已重新加载");
+                    }
+                }
+                Err(e) => warn!("重新加载配置失败，继续使用旧配置: {:?}", e),
+            }
+        }
+    });
+    rx
+}
+
+/// 取出必填配置，缺失时提示三种设置方式
+pub fn require<T>(value: Option<T>, flag: &str, env: &str, key: &str) -> Result<T> {
+    value.ok_or_else(|| {
+        anyhow!(
+            "missing {}: pass --{}, set {} or add `{}` to the config file",
+            flag,
+            flag,
+            env,
+            key
+        )
+    })
+}
+
+/// 配置文件中的 mint 地址
+pub fn parse_mint(value: &str) -> Result<Pubkey> {
+    value
+        .parse()
+        .map_err(|e| anyhow!("invalid token mint {:?}: {}", value, e))
+}
+
+/// base58 私钥，或者 keypair 文件（加密的 keystore 或 `solana-keygen` 生成的 JSON）
+pub fn parse_keypair(value: &str) -> Result<Keypair> {
+    if Path::new(value).is_file() {
+        return read_keypair(value);
+    }
+    let bytes = bs58::decode(value)
+        .into_vec()
+        .map_err(|_| anyhow!("invalid private key: expected a base58 keypair or a keypair file path"))?;
+    Keypair::from_bytes(&bytes).map_err(|_| anyhow!("invalid private key: expected 64 bytes, got {}", bytes.len()))
+}
+
+/// 滑点，单位 bps，必须小于 10000
+pub fn parse_slippage_bps(value: &str) -> Result<f64> {
+    let bps: f64 = value.parse()?;
+    if !(0.0..10_000.0).contains(&bps) {
+        return Err(anyhow!("slippage must be in [0, 10000) bps, got {}", bps));
+    }
+    Ok(bps)
+}
+
+/// 正的 SOL 数量，返回 lamports
+pub fn parse_sol(value: &str) -> Result<u64> {
+    let sol: f64 = value.parse()?;
+    if !sol.is_finite() || sol <= 0.0 {
+        return Err(anyhow!("SOL amount must be positive, got {}", value));
+    }
+    Ok((sol * 1e9).round() as u64)
+}
+
+#[test]
+fn test_config_profiles_and_validation() {
+    let text = r#"
+        profile = "devnet"
+        rpc_url = "https://api.mainnet-beta.solana.com"
+
+        [strategy]
+        min_profit = 10000000
+
+        [fees]
+        tip = 1000
+
+        [profiles.devnet]
+        rpc_url = "https://api.devnet.solana.com"
+
+        [profiles.mainnet-aggressive.fees]
+        unit_price = 200000
+    "#;
+    let devnet = Config::parse(text, None).unwrap();
+    assert_eq!(devnet.rpc_url.as_deref(), Some("https://api.devnet.solana.com"));
+    assert_eq!(devnet.strategy.min_profit, Some(10_000_000));
+
+    // profile 中的表逐字段覆盖，没写的字段保留顶层的值
+    let aggressive = Config::parse(text, Some("mainnet-aggressive")).unwrap();
+    assert_eq!(aggressive.rpc_url.as_deref(), Some("https://api.mainnet-beta.solana.com"));
+    assert_eq!(aggressive.fees.unit_price, 200_000);
+    assert_eq!(aggressive.fees.tip, 1000);
+    assert_eq!(aggressive.fees.unit_limit, UNIT_LIMIT);
+
+    let err = Config::parse(text, Some("testnet")).unwrap_err().to_string();
+    assert!(err.contains("devnet") && err.contains("mainnet-aggressive"));
+    // 拼错的字段和超出范围的值在加载时报错
+    assert!(Config::parse("[strategy]\nslipage_bps = 100", None).is_err());
+    assert!(Config::parse("[strategy]\nslippage_bps = 10000", None).is_err());
+    assert!(Config::parse("[fees]\nunit_limit = 0", None).is_err());
+    // 策略的滑点不能超过风控允许的滑点
+    assert!(Config::parse("[strategy]\nexit_slippage_bps = 6000", None).is_err());
+    assert!(Config::parse("[strategy]\nexit_slippage_bps = 6000\n[risk]\nmax_slippage_bps = 7000", None).is_ok());
+    assert!(Config::parse("mint = \"xyz\"", None).is_err());
+    let config = Config::parse(
+        r#"
+        store_path = "pump.db"
+        [send]
+        rpc_urls = ["https://send.example"]
+        jito_selection = "lowest-latency"
+        [price]
+        feeds = ["v2:7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE"]
+        max_age = 30
+        [stream]
+        replay_speed = "max"
+        "#,
+        None,
+    )
+    .unwrap();
+    assert_eq!(config.store_path, Some(PathBuf::from("pump.db")));
+    assert_eq!(config.send.jito_selection, Some(Selection::LowestLatency));
+    assert_eq!(config.price.max_age, Some(30));
+    assert!(Config::parse("[send]\nrpc_urls = [\"send.example\"]", None).is_err());
+    assert!(Config::parse("[send]\njito_selection = \"random\"", None).is_err());
+    assert!(Config::parse("[price]\nfeeds = [\"v2:xyz\"]", None).is_err());
+    assert!(Config::parse("[stream]\nreplay_speed = \"0\"", None).is_err());
+    let log = Config::parse("[log]\nlevel = \"warn,solana_pump::confirm=debug\"\nformat = \"json\"", None).unwrap().log;
+    assert_eq!(log.format, LogFormat::Json);
+    assert!(Config::parse("[log]\nlevel = \"solana_pump=loud\"", None).is_err());
+
+    let err = require(None::<String>, "rpc-url", "RPC_URL", "rpc_url").unwrap_err().to_string();
+    assert!(err.contains("--rpc-url") && err.contains("RPC_URL") && err.contains("`rpc_url`"));
+
+    let keypair = Keypair::new();
+    assert_eq!(parse_keypair(&keypair.to_base58_string()).unwrap(), keypair);
+    assert!(parse_keypair("not-a-key").is_err());
+    assert_eq!(parse_sol("0.25").unwrap(), 250_000_000);
+    assert!(parse_sol("-1").is_err());
+    assert!(parse_slippage_bps("10000").is_err());
+}
+
+#[tokio::test]
+async fn test_config_hot_reload() {
+    let path = std::env::temp_dir().join(format!("solana_pump_config_{}.toml", std::process::id()));
+    fs::write(&path, "rpc_url = \"https://a.example\"\n[fees]\ntip = 1000\n").unwrap();
+    let config = Config::load(&path, None).unwrap();
+    let mut rx = watch_config(path.clone(), None, config, Duration::from_millis(10));
+
+    // 修改时间的精度可能是秒，等待后再写
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    fs::write(&path, "rpc_url = \"https://b.example\"\n[fees]\ntip = 2000\n").unwrap();
+    tokio::time::timeout(Duration::from_secs(5), rx.changed()).await.unwrap().unwrap();
+    let reloaded = rx.borrow_and_update().clone();
+    assert_eq!(reloaded.fees.tip, 2000);
+    // 连接配置不热加载
+    assert_eq!(reloaded.rpc_url.as_deref(), Some("https://a.example"));
+
+    // 无效的配置不替换当前配置
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    fs::write(&path, "[fees]\nunit_limit = 0\n").unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!rx.has_changed().unwrap());
+    assert_eq!(rx.borrow().fees.tip, 2000);
+
+    let _ = fs::remove_file(&path);
+}
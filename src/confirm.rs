@@ -0,0 +1,303 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+    transaction::TransactionError,
+};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use tokio::sync::oneshot;
+use tracing::warn;
+use yellowstone_grpc_proto::{convert_from::create_tx_error, prelude::SubscribeUpdateTransaction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmSource {
+    Geyser,
+    Rpc,
+}
+
+/// 交易落地结果
+#[derive(Debug, Clone)]
+pub struct Confirmation {
+    pub signature: Signature,
+    pub slot: u64,
+    pub err: Option<TransactionError>,
+    /// 钱包 SOL 余额变化 (post - pre)，已扣除手续费；取不到交易详情时为 None
+    pub sol_change: Option<i64>,
+    pub source: ConfirmSource,
+    /// 从开始跟踪到确认的耗时
+    pub elapsed: Duration,
+}
+
+impl Confirmation {
+    pub fn is_success(&self) -> bool {
+        self.err.is_none()
+    }
+}
+
+struct Pending {
+    wallet: Pubkey,
+    tracked_at: Instant,
+    sender: oneshot::Sender<Confirmation>,
+}
+
+/// 从 Geyser 交易流确认自己的交易，流中漏掉的用 `getSignatureStatuses` 补查
+#[derive(Default)]
+pub struct ConfirmationTracker {
+    pending: DashMap<Signature, Pending>,
+}
+
+impl ConfirmationTracker {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// 开始跟踪签名，交易落地后 receiver 收到结果；超时后 sender 被丢弃
+    pub fn track(&self, signature: Signature, wallet: Pubkey) -> oneshot::Receiver<Confirmation> {
+        let (sender, receiver) = oneshot::channel();
+        self.pending.insert(
+            signature,
+            Pending {
+                wallet,
+                tracked_at: Instant::now(),
+                sender,
+            },
+        );
+        receiver
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 处理 Geyser 推送的交易，命中跟踪中的签名时返回 true
+    pub fn on_transaction(&self, update: &SubscribeUpdateTransaction) -> bool {
+        let Some(info) = &update.transaction else {
+            return false;
+        };
+        let Ok(signature) = Signature::try_from(info.signature.as_slice()) else {
+            return false;
+        };
+        let Some((_, pending)) = self.pending.remove(&signature) else {
+            return false;
+        };
+
+        let meta = info.meta.as_ref();
+        let err = meta.and_then(|meta| create_tx_error(meta.err.as_ref()).ok().flatten());
+        let sol_change = meta.and_then(|meta| {
+            let keys = &info.transaction.as_ref()?.message.as_ref()?.account_keys;
+            let index = keys.iter().position(|key| key.as_slice() == pending.wallet.as_ref())?;
+            balance_change(&meta.pre_balances, &meta.post_balances, index)
+        });
+
+        pending
+            .sender
+            .send(Confirmation {
+                signature,
+                slot: update.slot,
+                err,
+                sol_change,
+                source: ConfirmSource::Geyser,
+                elapsed: pending.tracked_at.elapsed(),
+            })
+            .ok();
+        true
+    }
+
+    /// 定时查询超过 `after` 仍未在流中出现的签名，超过 `expire` 放弃跟踪
+    pub fn spawn_rpc_fallback(
+        self: &Arc<Self>,
+        rpc: Arc<RpcClient>,
+        after: Duration,
+        expire: Duration,
+        interval: Duration,
+    ) {
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                tracker.poll_rpc(&rpc, after, expire).await;
+            }
+        });
+    }
+
+    async fn poll_rpc(&self, rpc: &RpcClient, after: Duration, expire: Duration) {
+        self.pending
+            .retain(|_, pending| pending.tracked_at.elapsed() < expire);
+        let signatures: Vec<Signature> = self
+            .pending
+            .iter()
+            .filter(|e| e.tracked_at.elapsed() >= after)
+            .map(|e| *e.key())
+            .collect();
+        // getSignatureStatuses 每次最多 256 个
+        for chunk in signatures.chunks(256) {
+            let statuses = match rpc.get_signature_statuses(chunk).await {
+                Ok(resp) => resp.value,
+                Err(e) => {
+                    warn!("getSignatureStatuses 请求失败: {:?}", e);
+                    return;
+                }
+            };
+            for (signature, status) in chunk.iter().zip(statuses) {
+                let Some(status) = status else {
+                    continue;
+                };
+                let Some((_, pending)) = self.pending.remove(signature) else {
+                    continue;
+                };
+                let sol_change = fetch_sol_change(rpc, signature, &pending.wallet).await;
+                pending
+                    .sender
+                    .send(Confirmation {
+                        signature: *signature,
+                        slot: status.slot,
+                        err: status.err,
+                        sol_change,
+                        source: ConfirmSource::Rpc,
+                        elapsed: pending.tracked_at.elapsed(),
+                    })
+                    .ok();
+            }
+        }
+    }
+}
+
+fn balance_change(pre: &[u64], post: &[u64], index: usize) -> Option<i64> {
+    Some(*post.get(index)? as i64 - *pre.get(index)? as i64)
+}
+
+/// 通过 getTransaction 取钱包的余额变化
+async fn fetch_sol_change(rpc: &RpcClient, signature: &Signature, wallet: &Pubkey) -> Option<i64> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let tx = rpc.get_transaction_with_config(signature, config).await.ok()?;
+    wallet_sol_change(&tx, wallet)
+}
+
+pub fn wallet_sol_change(
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+    wallet: &Pubkey,
+) -> Option<i64> {
+    let meta = tx.transaction.meta.as_ref()?;
+    let decoded = tx.transaction.transaction.decode()?;
+    let index = decoded
+        .message
+        .static_account_keys()
+        .iter()
+        .position(|key| key == wallet)?;
+    balance_change(&meta.pre_balances, &meta.post_balances, index)
+}
+
+#[cfg(test)]
+fn geyser_update(signature: &Signature, wallet: &Pubkey, err: Option<TransactionError>, pre: u64, post: u64) -> SubscribeUpdateTransaction {
+    use yellowstone_grpc_proto::{
+        convert_to::create_transaction_error,
+        prelude::{Message, SubscribeUpdateTransactionInfo, Transaction, TransactionStatusMeta},
+    };
+
+    let status = err.map_or(Ok(()), Err);
+    SubscribeUpdateTransaction {
+        transaction: Some(SubscribeUpdateTransactionInfo {
+            signature: signature.as_ref().to_vec(),
+            transaction: Some(Transaction {
+                signatures: vec![signature.as_ref().to_vec()],
+                message: Some(Message {
+                    account_keys: vec![Pubkey::new_unique().to_bytes().to_vec(), wallet.to_bytes().to_vec()],
+                    ..Default::default()
+                }),
+            }),
+            meta: Some(TransactionStatusMeta {
+                err: create_transaction_error(&status),
+                pre_balances: vec![5_000_000, pre],
+                post_balances: vec![5_000_000, post],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        slot: 42,
+    }
+}
+
+#[test]
+fn test_confirm_from_geyser() {
+    use solana_sdk::instruction::InstructionError;
+
+    let tracker = ConfirmationTracker::new();
+    let wallet = Pubkey::new_unique();
+    let (sold, failed) = (Signature::new_unique(), Signature::new_unique());
+    let mut sold_rx = tracker.track(sold, wallet);
+    let mut failed_rx = tracker.track(failed, wallet);
+
+    // 不是自己的签名
+    assert!(!tracker.on_transaction(&geyser_update(&Signature::new_unique(), &wallet, None, 0, 1)));
+    assert_eq!(tracker.pending_len(), 2);
+
+    // 卖出后钱包收到 0.5 SOL
+    assert!(tracker.on_transaction(&geyser_update(&sold, &wallet, None, 1_000_000_000, 1_500_000_000)));
+    let confirmation = sold_rx.try_recv().unwrap();
+    assert!(confirmation.is_success());
+    assert_eq!((confirmation.slot, confirmation.source), (42, ConfirmSource::Geyser));
+    assert_eq!(confirmation.sol_change, Some(500_000_000));
+    // 同一个签名只确认一次
+    assert!(!tracker.on_transaction(&geyser_update(&sold, &wallet, None, 0, 1)));
+
+    let err = TransactionError::InstructionError(2, InstructionError::Custom(6003));
+    assert!(tracker.on_transaction(&geyser_update(&failed, &wallet, Some(err.clone()), 1_000_000_000, 999_995_000)));
+    let confirmation = failed_rx.try_recv().unwrap();
+    assert_eq!(confirmation.err, Some(err));
+    assert_eq!(confirmation.sol_change, Some(-5_000));
+    assert_eq!(tracker.pending_len(), 0);
+
+    assert_eq!(balance_change(&[10, 20], &[15, 5], 1), Some(-15));
+    assert_eq!(balance_change(&[10], &[15], 1), None);
+}
+
+#[tokio::test]
+async fn test_confirm_rpc_fallback_and_expiry() {
+    use std::collections::HashMap;
+
+    use solana_client::{
+        rpc_request::RpcRequest,
+        rpc_response::{Response, RpcResponseContext},
+    };
+    use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+
+    let status = TransactionStatus {
+        slot: 7,
+        confirmations: None,
+        status: Ok(()),
+        err: None,
+        confirmation_status: Some(TransactionConfirmationStatus::Confirmed),
+    };
+    let response = Response { context: RpcResponseContext { slot: 7, api_version: None }, value: vec![Some(status)] };
+    let mocks = HashMap::from([(RpcRequest::GetSignatureStatuses, serde_json::to_value(response).unwrap())]);
+    let rpc = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+
+    let tracker = ConfirmationTracker::new();
+    let wallet = Pubkey::new_unique();
+    let landed = Signature::new_unique();
+    let receiver = tracker.track(landed, wallet);
+    // 还没到补查时间
+    tracker.poll_rpc(&rpc, Duration::from_secs(60), Duration::from_secs(90)).await;
+    assert_eq!(tracker.pending_len(), 1);
+    tracker.poll_rpc(&rpc, Duration::ZERO, Duration::from_secs(90)).await;
+    let confirmation = receiver.await.unwrap();
+    assert_eq!(confirmation.signature, landed);
+    assert_eq!((confirmation.slot, confirmation.source), (7, ConfirmSource::Rpc));
+    assert!(confirmation.is_success());
+
+    // 过期后放弃跟踪，receiver 收到错误
+    let receiver = tracker.track(Signature::new_unique(), wallet);
+    tracker.poll_rpc(&rpc, Duration::ZERO, Duration::ZERO).await;
+    assert!(receiver.await.is_err());
+    assert_eq!(tracker.pending_len(), 0);
+}
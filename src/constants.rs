@@ -1,40 +1,4 @@
-// use std::env;
-
-// use once_cell::sync::Lazy;
-// use solana_program::pubkey;
-// use solana_sdk::pubkey::Pubkey;
-
-// pub static GRPC: Lazy<String> = Lazy::new(|| env::var("GRPC_URL").unwrap());
-// pub static RPC: Lazy<String> = Lazy::new(|| env::var("RPC_URL").unwrap());
-// pub static WS: Lazy<String> = Lazy::new(|| env::var("WS_URL").unwrap());
-// pub static JITO: Lazy<String> = Lazy::new(|| env::var("JITO_BLOCK_ENGINE_URL").unwrap());
-// pub static UNIT_PRICE: Lazy<u64> = Lazy::new(|| {
-//     env::var("UNIT_PRICE")
-//         .unwrap()
-//         .parse::<u64>()
-//         .unwrap_or(20000)
-// });
-
-// pub static UNIT_LIMIT: Lazy<u32> = Lazy::new(|| {
-//     env::var("UNIT_LIMIT")
-//         .unwrap()
-//         .parse::<u32>()
-//         .unwrap_or(2000000)
-// });
-// pub static PRIVATE_KEY: Lazy<String> = Lazy::new(|| env::var("PRIVATE_KEY").unwrap());
-// pub static REDIS_URL: Lazy<String> = Lazy::new(|| env::var("REDIS_URL").unwrap());
-// pub static PRICE_INCREASE_BUY_LOWER: Lazy<f32> = Lazy::new(|| {
-//     env::var("PRICE_INCREASE_BUY_LOWER")
-//         .unwrap()
-//         .parse::<f32>()
-//         .unwrap_or(0.1)
-// });
-// pub static PRICE_INCREASE_BUY_UPPER: Lazy<f32> = Lazy::new(|| {
-//     env::var("PRICE_INCREASE_BUY_UPPER")
-//         .unwrap()
-//         .parse::<f32>()
-//         .unwrap_or(0.2)
-// });
+// 运行时配置（RPC、费用、策略阈值）见 config.rs
 
 // // program相关
 // pub const SYSTEM_PROGRAM_ID: Pubkey = pubkey!("11111111111111111111111111111111");
@@ -0,0 +1,343 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use anyhow::{bail, Result};
+use axum::{
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{
+    net::TcpListener,
+    sync::{mpsc, oneshot},
+};
+use tracing::error;
+
+use crate::{latency::LatencyReport, pnl::SessionPnl};
+
+/// 调整运行中的策略参数，没有给出的字段保持不变
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StrategyUpdate {
+    pub launch_cost: Option<u64>,
+    pub min_profit: Option<u64>,
+    pub slippage_bps: Option<f64>,
+    pub exit_slippage_bps: Option<f64>,
+}
+
+#[derive(Debug)]
+pub enum ControlCommand {
+    Status,
+    /// 按最新价格卖出持仓的 `percent`%，`exit` 为 true 时卖出后退出
+    Sell { percent: f64, exit: bool },
+    /// 花费 `lamports` 买入，钱包池中拆分到多个钱包
+    Buy { lamports: u64 },
+    /// 暂停和恢复自动卖出，手动命令不受影响
+    Pause,
+    Resume,
+    UpdateStrategy(StrategyUpdate),
+    Shutdown,
+}
+
+pub type Reply = Result<Value, String>;
+
+/// 控制命令和回复通道，由交易主循环处理
+#[derive(Debug)]
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    pub reply: oneshot::Sender<Reply>,
+}
+
+impl ControlRequest {
+    pub fn new(command: ControlCommand) -> (Self, oneshot::Receiver<Reply>) {
+        let (reply, rx) = oneshot::channel();
+        (Self { command, reply }, rx)
+    }
+
+    /// 回复结果，请求方已经断开时忽略
+    pub fn respond(self, reply: Reply) {
+        self.reply.send(reply).ok();
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionStatus {
+    pub wallet: String,
+    pub sol: u64,
+    pub tokens: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PnlStatus {
+    pub fills: usize,
+    pub failed: usize,
+    pub sol_change: i64,
+    pub fees: u64,
+    pub tips: u64,
+    pub rent_reclaimed: u64,
+    pub usd_change: f64,
+    /// 风控统计的当天已实现盈亏
+    pub daily_pnl: i64,
+}
+
+impl PnlStatus {
+    pub fn new(session: &SessionPnl, daily_pnl: i64) -> Self {
+        Self {
+            fills: session.fills,
+            failed: session.failed,
+            sol_change: session.sol_change,
+            fees: session.fees,
+            tips: session.tips,
+            rent_reclaimed: session.rent_reclaimed,
+            usd_change: session.usd_change,
+            daily_pnl,
+        }
+    }
+}
+
+/// `GET /status` 的返回值，`/positions`、`/pnl`、`/price` 和 `/latency` 返回其中的一部分
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub mint: String,
+    pub price: f32,
+    pub paused: bool,
+    pub kill_switch: Option<String>,
+    pub positions: Vec<PositionStatus>,
+    pub pnl: PnlStatus,
+    /// 各阶段延迟的 p50/p99 和落地 slot
+    pub latency: LatencyReport,
+    /// 当前的策略阈值
+    pub strategy: Value,
+}
+
+#[derive(Clone)]
+struct ControlState {
+    token: Arc<str>,
+    commands: mpsc::Sender<ControlRequest>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SellBody {
+    percent: f64,
+    #[serde(default)]
+    exit: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BuyBody {
+    sol: f64,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+/// 比较 token 时不因为提前返回泄露匹配的长度
+fn token_matches(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len() && given.iter().zip(expected).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+async fn auth(State(state): State<ControlState>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| token_matches(token.as_bytes(), state.token.as_bytes()));
+    if !authorized {
+        return error(StatusCode::UNAUTHORIZED, "missing or invalid bearer token");
+    }
+    next.run(request).await
+}
+
+async fn dispatch(state: &ControlState, command: ControlCommand) -> Result<Value, Response> {
+    let (request, reply) = ControlRequest::new(command);
+    let unavailable = || error(StatusCode::SERVICE_UNAVAILABLE, "bot is not running");
+    state.commands.send(request).await.map_err(|_| unavailable())?;
+    match reply.await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(error(StatusCode::BAD_REQUEST, &e)),
+        Err(_) => Err(unavailable()),
+    }
+}
+
+async fn respond(state: &ControlState, command: ControlCommand) -> Response {
+    match dispatch(state, command).await {
+        Ok(value) => Json(value).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// 返回状态中的一个字段
+async fn status_field(state: &ControlState, field: &str) -> Response {
+    match dispatch(state, ControlCommand::Status).await {
+        Ok(mut status) => Json(status[field].take()).into_response(),
+        Err(response) => response,
+    }
+}
+
+async fn status(State(state): State<ControlState>) -> Response {
+    respond(&state, ControlCommand::Status).await
+}
+
+async fn positions(State(state): State<ControlState>) -> Response {
+    status_field(&state, "positions").await
+}
+
+async fn pnl(State(state): State<ControlState>) -> Response {
+    status_field(&state, "pnl").await
+}
+
+async fn price(State(state): State<ControlState>) -> Response {
+    status_field(&state, "price").await
+}
+
+async fn latency(State(state): State<ControlState>) -> Response {
+    status_field(&state, "latency").await
+}
+
+async fn sell(State(state): State<ControlState>, Json(body): Json<SellBody>) -> Response {
+    if !(body.percent > 0.0 && body.percent <= 100.0) {
+        return error(StatusCode::BAD_REQUEST, "percent must be in (0, 100]");
+    }
+    respond(&state, ControlCommand::Sell { percent: body.percent, exit: body.exit }).await
+}
+
+async fn buy(State(state): State<ControlState>, Json(body): Json<BuyBody>) -> Response {
+    if !(body.sol.is_finite() && body.sol > 0.0) {
+        return error(StatusCode::BAD_REQUEST, "sol must be positive");
+    }
+    let lamports = (body.sol * 1e9).round() as u64;
+    respond(&state, ControlCommand::Buy { lamports }).await
+}
+
+async fn pause(State(state): State<ControlState>) -> Response {
+    respond(&state, ControlCommand::Pause).await
+}
+
+async fn resume(State(state): State<ControlState>) -> Response {
+    respond(&state, ControlCommand::Resume).await
+}
+
+async fn strategy(State(state): State<ControlState>, Json(update): Json<StrategyUpdate>) -> Response {
+    respond(&state, ControlCommand::UpdateStrategy(update)).await
+}
+
+async fn shutdown(State(state): State<ControlState>) -> Response {
+    respond(&state, ControlCommand::Shutdown).await
+}
+
+/// 在本机地址上启动控制 API，请求转发到 `commands`，返回实际监听的地址。
+/// 所有请求都需要 `Authorization: Bearer <token>`
+pub async fn spawn_control_api(
+    addr: SocketAddr,
+    token: String,
+    commands: mpsc::Sender<ControlRequest>,
+) -> Result<SocketAddr> {
+    if !addr.ip().is_loopback() {
+        bail!("control API must listen on a loopback address, got {}", addr);
+    }
+    if token.is_empty() {
+        bail!("control API token must not be empty");
+    }
+    let state = ControlState {
+        token: token.into(),
+        commands,
+    };
+    let app = Router::new()
+        .route("/status", get(status))
+        .route("/positions", get(positions))
+        .route("/pnl", get(pnl))
+        .route("/price", get(price))
+        .route("/latency", get(latency))
+        .route("/sell", post(sell))
+        .route("/buy", post(buy))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .route("/strategy", post(strategy))
+        .route("/shutdown", post(shutdown))
+        .layer(middleware::from_fn_with_state(state.clone(), auth))
+        .with_state(state);
+    let listener = TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            error!("控制 API 已停止: {:?}", e);
+        }
+    });
+    Ok(local_addr)
+}
+
+#[tokio::test]
+async fn test_control_api() {
+    let (tx, mut rx) = mpsc::channel::<ControlRequest>(8);
+    assert!(spawn_control_api("0.0.0.0:0".parse().unwrap(), "secret".into(), tx.clone())
+        .await
+        .is_err());
+    let addr = spawn_control_api("127.0.0.1:0".parse().unwrap(), "secret".into(), tx)
+        .await
+        .unwrap();
+
+    // 模拟交易主循环
+    tokio::spawn(async move {
+        while let Some(request) = rx.recv().await {
+            let reply = match &request.command {
+                ControlCommand::Status => Ok(json!({ "price": 0.5, "positions": [] })),
+                ControlCommand::Sell { percent, exit } => Ok(json!({ "percent": percent, "exit": exit })),
+                ControlCommand::Buy { lamports } => Ok(json!({ "lamports": lamports })),
+                ControlCommand::Pause => Err("already paused".to_string()),
+                _ => Ok(json!({})),
+            };
+            request.respond(reply);
+        }
+    });
+
+    let client = reqwest::Client::new();
+    let url = |path: &str| format!("http://{}{}", addr, path);
+    let response = client.get(url("/status")).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    let response = client.get(url("/status")).bearer_auth("wrong").send().await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    let price: Value = client.get(url("/price")).bearer_auth("secret").send().await.unwrap().json().await.unwrap();
+    assert_eq!(price, json!(0.5));
+    let sold: Value = client
+        .post(url("/sell"))
+        .bearer_auth("secret")
+        .json(&json!({ "percent": 50 }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(sold, json!({ "percent": 50.0, "exit": false }));
+    let response = client
+        .post(url("/sell"))
+        .bearer_auth("secret")
+        .json(&json!({ "percent": 150 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let bought: Value = client
+        .post(url("/buy"))
+        .bearer_auth("secret")
+        .json(&json!({ "sol": 0.25 }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(bought, json!({ "lamports": 250_000_000 }));
+    // 主循环返回的错误
+    let response = client.post(url("/pause")).bearer_auth("secret").send().await.unwrap();
+    assert_eq!(response.status(), 400);
+}
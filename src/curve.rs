@@ -0,0 +1,194 @@
+use crate::monitor::{
+    cal_pumpfun_price, BondingCurveAccount, TradeEvent, INIT_REAL_TOKEN_RESERVES, INIT_SOL_REVERSES,
+    INIT_TOKEN_REVERSES, PUMPFUN_TOTAL_SUPPLY,
+};
+
+/// pump.fun 交易手续费，买卖各收 1%
+pub const PUMPFUN_FEE_BPS: u64 = 100;
+
+/// 一次买入或卖出的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Quote {
+    /// 进出曲线的 SOL（不含手续费）
+    pub sol: u64,
+    pub tokens: u64,
+    pub fee: u64,
+}
+
+impl Quote {
+    /// 买入时钱包支付的 SOL
+    pub fn sol_in(&self) -> u64 {
+        self.sol + self.fee
+    }
+
+    /// 卖出时钱包收到的 SOL
+    pub fn sol_out(&self) -> u64 {
+        self.sol - self.fee
+    }
+}
+
+/// bonding curve 储备，整数运算和 pump.fun 程序一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Curve {
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub real_token_reserves: u64,
+}
+
+impl From<&TradeEvent> for Curve {
+    /// 成交后的曲线状态
+    fn from(trade: &TradeEvent) -> Self {
+        Self {
+            virtual_sol_reserves: trade.virtual_sol_reserves,
+            virtual_token_reserves: trade.virtual_token_reserves,
+            real_sol_reserves: trade.real_sol_reserves,
+            real_token_reserves: trade.real_token_reserves,
+        }
+    }
+}
+
+impl From<&BondingCurveAccount> for Curve {
+    fn from(account: &BondingCurveAccount) -> Self {
+        Self {
+            virtual_sol_reserves: account.virtual_sol_reserves,
+            virtual_token_reserves: account.virtual_token_reserves,
+            real_sol_reserves: account.real_sol_reserves,
+            real_token_reserves: account.real_token_reserves,
+        }
+    }
+}
+
+fn fee(sol: u64) -> u64 {
+    (sol as u128 * PUMPFUN_FEE_BPS as u128 / 10_000) as u64
+}
+
+impl Curve {
+    /// 新创建代币的曲线状态
+    pub fn initial() -> Self {
+        Self {
+            virtual_sol_reserves: INIT_SOL_REVERSES,
+            virtual_token_reserves: INIT_TOKEN_REVERSES,
+            real_sol_reserves: 0,
+            real_token_reserves: INIT_REAL_TOKEN_RESERVES,
+        }
+    }
+
+    pub fn price(&self) -> f32 {
+        cal_pumpfun_price(self.virtual_sol_reserves, self.virtual_token_reserves)
+    }
+
+    /// 按当前价格计算的总市值（lamports）
+    pub fn market_cap(&self) -> u64 {
+        if self.virtual_token_reserves == 0 {
+            return 0;
+        }
+        (self.virtual_sol_reserves as u128 * PUMPFUN_TOTAL_SUPPLY as u128 / self.virtual_token_reserves as u128) as u64
+    }
+
+    /// 曲线进度：已卖出的可交易 token 占比，0 到 1，1 时迁移
+    pub fn progress(&self) -> f64 {
+        let sold = INIT_REAL_TOKEN_RESERVES.saturating_sub(self.real_token_reserves);
+        sold as f64 / INIT_REAL_TOKEN_RESERVES as f64
+    }
+
+    /// 曲线已完成，不能再交易
+    pub fn is_complete(&self) -> bool {
+        self.real_token_reserves == 0
+    }
+
+    /// 买入 `tokens` 需要的 SOL，不超过剩余可买数量
+    pub fn buy_exact_tokens(&self, tokens: u64) -> Quote {
+        let tokens = tokens.min(self.real_token_reserves);
+        if tokens == 0 {
+            return Quote::default();
+        }
+        let vsr = self.virtual_sol_reserves as u128;
+        let vtr = self.virtual_token_reserves as u128;
+        let sol = (vsr * vtr / (vtr - tokens as u128) + 1 - vsr) as u64;
+        Quote {
+            sol,
+            tokens,
+            fee: fee(sol),
+        }
+    }
+
+    /// 花费 `sol_in`（含手续费）能买到的数量
+    pub fn buy_with_sol(&self, sol_in: u64) -> Quote {
+        let sol = (sol_in as u128 * 10_000 / (10_000 + PUMPFUN_FEE_BPS) as u128) as u64;
+        if sol == 0 {
+            return Quote::default();
+        }
+        let vsr = self.virtual_sol_reserves as u128;
+        let vtr = self.virtual_token_reserves as u128;
+        let tokens = (vtr - (vsr * vtr / (vsr + sol as u128) + 1)) as u64;
+        if tokens > self.real_token_reserves {
+            return self.buy_exact_tokens(self.real_token_reserves);
+        }
+        Quote {
+            sol,
+            tokens,
+            fee: fee(sol),
+        }
+    }
+
+    /// 卖出 `tokens` 能拿到的 SOL
+    pub fn sell(&self, tokens: u64) -> Quote {
+        if tokens == 0 {
+            return Quote::default();
+        }
+        let vsr = self.virtual_sol_reserves as u128;
+        let vtr = self.virtual_token_reserves as u128;
+        let sol = ((tokens as u128 * vsr / (vtr + tokens as u128)) as u64).min(self.real_sol_reserves);
+        Quote {
+            sol,
+            tokens,
+            fee: fee(sol),
+        }
+    }
+
+    pub fn apply_buy(&mut self, quote: &Quote) {
+        self.virtual_sol_reserves += quote.sol;
+        self.real_sol_reserves += quote.sol;
+        self.virtual_token_reserves -= quote.tokens;
+        self.real_token_reserves -= quote.tokens;
+    }
+
+    pub fn apply_sell(&mut self, quote: &Quote) {
+        self.virtual_sol_reserves -= quote.sol;
+        self.real_sol_reserves -= quote.sol;
+        self.virtual_token_reserves += quote.tokens;
+        self.real_token_reserves += quote.tokens;
+    }
+}
+
+#[test]
+fn test_curve_round_trip_pays_fees() {
+    let mut curve = Curve::initial();
+    let buy = curve.buy_with_sol(1_010_000_000);
+    assert_eq!(buy.sol, 1_000_000_000);
+    assert_eq!(buy.fee, 10_000_000);
+    // 按数量买入同样多的 token 花费基本一致
+    let exact = curve.buy_exact_tokens(buy.tokens);
+    assert!(exact.sol.abs_diff(buy.sol) <= 1);
+
+    curve.apply_buy(&buy);
+    let sell = curve.sell(buy.tokens);
+    assert!(sell.sol <= buy.sol);
+    assert!(buy.sol - sell.sol <= 1);
+    assert_eq!(sell.sol_out(), sell.sol - sell.sol / 100);
+    curve.apply_sell(&sell);
+    assert_eq!(curve.virtual_token_reserves, INIT_TOKEN_REVERSES);
+
+    // 初始市值约 28 SOL，来回交易后 SOL 储备可能多出 1 lamport；卖完可交易 token 时进度为 1
+    assert_eq!(curve.progress(), 0.0);
+    let initial = INIT_SOL_REVERSES as u128 * PUMPFUN_TOTAL_SUPPLY as u128 / INIT_TOKEN_REVERSES as u128;
+    assert_eq!(Curve::initial().market_cap(), initial as u64);
+    let expected = curve.virtual_sol_reserves as u128 * PUMPFUN_TOTAL_SUPPLY as u128 / curve.virtual_token_reserves as u128;
+    assert_eq!(curve.market_cap(), expected as u64);
+    assert!(curve.market_cap().abs_diff(Curve::initial().market_cap()) <= 1);
+    let all = curve.buy_exact_tokens(INIT_REAL_TOKEN_RESERVES);
+    curve.apply_buy(&all);
+    assert_eq!(curve.progress(), 1.0);
+    assert!(curve.is_complete());
+}
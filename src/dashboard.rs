@@ -0,0 +1,461 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, Stderr},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEventKind},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Layout},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tokio::{runtime::Handle, sync::mpsc};
+use tracing::error;
+
+use crate::{
+    control::{ControlCommand, ControlRequest},
+    curve::Curve,
+    monitor::TradeEvent,
+    pnl::SessionPnl,
+};
+
+/// 成交流和日志保留的行数
+const TAPE_LEN: usize = 200;
+const LOG_LEN: usize = 50;
+/// 交易列表保留的数量，已落地的先移除
+const TX_LEN: usize = 50;
+
+/// 交易主循环推送给面板的更新
+#[derive(Debug, Clone)]
+pub enum DashboardEvent {
+    /// 流中的成交，曲线状态为成交后的状态
+    Trade { slot: u64, trade: TradeEvent },
+    /// 钱包池在 `mint` 上的总持仓
+    Position { mint: Pubkey, tokens: u64 },
+    /// 本次运行的盈亏和每个代币的已实现盈亏
+    Pnl { session: SessionPnl, realized: Vec<(Pubkey, i64)> },
+    Paused(bool),
+    TxSent { signature: Signature, wallet: Pubkey, side: &'static str },
+    TxLanded { signature: Signature, slot: u64, success: bool },
+    TxExpired(Signature),
+    Log(String),
+    /// 恢复终端并结束面板线程
+    Quit,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MintView {
+    curve: Option<Curve>,
+    position: u64,
+    realized: i64,
+}
+
+#[derive(Debug, Clone)]
+struct TapeEntry {
+    slot: u64,
+    mint: Pubkey,
+    trade: TradeEvent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TxState {
+    Pending,
+    Landed(u64),
+    Failed(u64),
+    Expired,
+}
+
+#[derive(Debug, Clone)]
+struct TxView {
+    signature: Signature,
+    wallet: Pubkey,
+    side: &'static str,
+    sent: Instant,
+    state: TxState,
+}
+
+/// 终端面板的状态，由 [`DashboardEvent`] 更新
+#[derive(Debug, Default)]
+pub struct Dashboard {
+    mints: BTreeMap<Pubkey, MintView>,
+    tape: VecDeque<TapeEntry>,
+    txs: VecDeque<TxView>,
+    pnl: SessionPnl,
+    paused: bool,
+    logs: VecDeque<String>,
+}
+
+impl Dashboard {
+    /// 只显示 `mints` 的成交和持仓
+    pub fn new(mints: &[Pubkey]) -> Self {
+        Self {
+            mints: mints.iter().map(|mint| (*mint, MintView::default())).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn apply(&mut self, event: DashboardEvent) {
+        match event {
+            DashboardEvent::Trade { slot, trade } => {
+                let Some(view) = self.mints.get_mut(&trade.mint) else {
+                    return;
+                };
+                view.curve = Some(Curve::from(&trade));
+                push_capped(&mut self.tape, TapeEntry { slot, mint: trade.mint, trade }, TAPE_LEN);
+            }
+            DashboardEvent::Position { mint, tokens } => {
+                if let Some(view) = self.mints.get_mut(&mint) {
+                    view.position = tokens;
+                }
+            }
+            DashboardEvent::Pnl { session, realized } => {
+                self.pnl = session;
+                for (mint, pnl) in realized {
+                    if let Some(view) = self.mints.get_mut(&mint) {
+                        view.realized = pnl;
+                    }
+                }
+            }
+            DashboardEvent::Paused(paused) => self.paused = paused,
+            DashboardEvent::TxSent { signature, wallet, side } => {
+                if self.txs.len() >= TX_LEN {
+                    // 优先移除最早的已结束交易
+                    let index = self.txs.iter().position(|tx| tx.state != TxState::Pending).unwrap_or(0);
+                    self.txs.remove(index);
+                }
+                self.txs.push_back(TxView {
+                    signature,
+                    wallet,
+                    side,
+                    sent: Instant::now(),
+                    state: TxState::Pending,
+                });
+            }
+            DashboardEvent::TxLanded { signature, slot, success } => {
+                self.set_tx_state(&signature, if success { TxState::Landed(slot) } else { TxState::Failed(slot) });
+            }
+            DashboardEvent::TxExpired(signature) => self.set_tx_state(&signature, TxState::Expired),
+            DashboardEvent::Log(line) => push_capped(&mut self.logs, line, LOG_LEN),
+            DashboardEvent::Quit => {}
+        }
+    }
+
+    fn set_tx_state(&mut self, signature: &Signature, state: TxState) {
+        if let Some(tx) = self.txs.iter_mut().find(|tx| tx.signature == *signature) {
+            tx.state = state;
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let [header, mints, middle, logs] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(self.mints.len() as u16 + 3),
+            Constraint::Min(8),
+            Constraint::Length(8),
+        ])
+        .areas(frame.area());
+        let [tape, txs] = Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(middle);
+
+        let status = match self.paused {
+            true => "自动卖出已暂停",
+            false => "自动卖出运行中",
+        };
+        let summary = format!(
+            "{} | 成交 {} 失败 {} | SOL 变化 {} | 手续费 {} tip {} | {:+.2} USD",
+            status,
+            self.pnl.fills,
+            self.pnl.failed,
+            sol(self.pnl.sol_change),
+            sol(self.pnl.fees as i64),
+            sol(self.pnl.tips as i64),
+            self.pnl.usd_change,
+        );
+        frame.render_widget(
+            Paragraph::new(summary).block(Block::default().borders(Borders::ALL).title("pump 狙击")),
+            header,
+        );
+
+        let rows = self.mints.iter().map(|(mint, view)| {
+            let (price, market_cap, progress, value) = match &view.curve {
+                Some(curve) => (
+                    format!("{:.10}", curve.price()),
+                    sol(curve.market_cap() as i64),
+                    format!("{:.1}%", curve.progress() * 100.0),
+                    sol(curve.sell(view.position).sol_out() as i64),
+                ),
+                None => ("-".into(), "-".into(), "-".into(), "-".into()),
+            };
+            Row::new(vec![
+                short(&mint.to_string()),
+                price,
+                market_cap,
+                progress,
+                view.position.to_string(),
+                value,
+                sol(view.realized),
+            ])
+        });
+        let widths = [
+            Constraint::Length(12),
+            Constraint::Length(14),
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Length(18),
+            Constraint::Length(12),
+            Constraint::Length(12),
+        ];
+        frame.render_widget(
+            Table::new(rows, widths)
+                .header(Row::new(vec!["代币", "价格", "市值 SOL", "进度", "持仓", "持仓价值", "已实现"]))
+                .block(Block::default().borders(Borders::ALL).title("代币")),
+            mints,
+        );
+
+        let tape_items: Vec<ListItem> = self
+            .tape
+            .iter()
+            .rev()
+            .take(tape.height as usize)
+            .map(|entry| {
+                let (side, color) = match entry.trade.is_buy {
+                    true => ("买", Color::Green),
+                    false => ("卖", Color::Red),
+                };
+                ListItem::new(Line::from(format!(
+                    "{} {} {} {} SOL {} 个 {}",
+                    entry.slot,
+                    short(&entry.mint.to_string()),
+                    side,
+                    sol(entry.trade.sol_amount as i64),
+                    entry.trade.token_amount,
+                    short(&entry.trade.user.to_string()),
+                )))
+                .style(Style::default().fg(color))
+            })
+            .collect();
+        frame.render_widget(
+            List::new(tape_items).block(Block::default().borders(Borders::ALL).title("成交")),
+            tape,
+        );
+
+        let tx_rows = self.txs.iter().rev().map(|tx| {
+            let (state, color) = match tx.state {
+                TxState::Pending => (format!("等待 {:.1}s", tx.sent.elapsed().as_secs_f32()), Color::Yellow),
+                TxState::Landed(slot) => (format!("落地 {}", slot), Color::Green),
+                TxState::Failed(slot) => (format!("失败 {}", slot), Color::Red),
+                TxState::Expired => ("过期".to_string(), Color::DarkGray),
+            };
+            Row::new(vec![short(&tx.signature.to_string()), tx.side.to_string(), short(&tx.wallet.to_string()), state])
+                .style(Style::default().fg(color))
+        });
+        let tx_widths = [Constraint::Length(12), Constraint::Length(5), Constraint::Length(12), Constraint::Min(10)];
+        frame.render_widget(
+            Table::new(tx_rows, tx_widths)
+                .header(Row::new(vec!["签名", "方向", "钱包", "状态"]))
+                .block(Block::default().borders(Borders::ALL).title("交易")),
+            txs,
+        );
+
+        let log_items: Vec<ListItem> = self
+            .logs
+            .iter()
+            .rev()
+            .take(logs.height.saturating_sub(2) as usize)
+            .rev()
+            .map(|line| ListItem::new(line.as_str()))
+            .collect();
+        frame.render_widget(
+            List::new(log_items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("1/2/3 卖出 25%/50%/100%  p 暂停/恢复  q 清仓退出  x 退出"),
+            ),
+            logs,
+        );
+    }
+}
+
+fn push_capped<T>(queue: &mut VecDeque<T>, item: T, cap: usize) {
+    if queue.len() >= cap {
+        queue.pop_front();
+    }
+    queue.push_back(item);
+}
+
+/// lamports 显示为 SOL
+fn sol(lamports: i64) -> String {
+    format!("{:.4}", lamports as f64 / 1e9)
+}
+
+/// 地址和签名只显示开头
+fn short(value: &str) -> String {
+    value.chars().take(8).collect::<String>() + "…"
+}
+
+/// 向面板推送更新，面板已经退出时忽略
+#[derive(Debug, Clone)]
+pub struct DashboardHandle {
+    tx: mpsc::UnboundedSender<DashboardEvent>,
+}
+
+impl DashboardHandle {
+    pub fn send(&self, event: DashboardEvent) {
+        self.tx.send(event).ok();
+    }
+}
+
+/// 面板线程，drop 时结束线程并恢复终端（包括交易主循环出错返回时）
+pub struct DashboardThread {
+    handle: DashboardHandle,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl Drop for DashboardThread {
+    fn drop(&mut self) {
+        self.handle.send(DashboardEvent::Quit);
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(Err(e))) => error!("面板出错: {:?}", e),
+            Some(Err(_)) => error!("面板线程 panic"),
+            _ => {}
+        }
+    }
+}
+
+/// 在 stderr 上启动终端面板，stdout 的日志应该重定向到文件。
+/// 快捷键通过 `commands` 发送控制命令，必须在 tokio 运行时内调用
+pub fn spawn_dashboard(mints: &[Pubkey], commands: mpsc::Sender<ControlRequest>) -> Result<(DashboardHandle, DashboardThread)> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let handle = DashboardHandle { tx };
+    let runtime = Handle::current();
+    let mut dashboard = Dashboard::new(mints);
+    let mut terminal = init_terminal()?;
+    let replies = handle.clone();
+    let thread = thread::spawn(move || {
+        let result = (|| -> Result<()> {
+            loop {
+                while let Ok(event) = rx.try_recv() {
+                    if let DashboardEvent::Quit = event {
+                        return Ok(());
+                    }
+                    dashboard.apply(event);
+                }
+                terminal.draw(|frame| dashboard.render(frame))?;
+                if !event::poll(Duration::from_millis(100))? {
+                    continue;
+                }
+                let Event::Key(key) = event::read()? else {
+                    continue;
+                };
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                let command = match key.code {
+                    KeyCode::Char('1') => ControlCommand::Sell { percent: 25.0, exit: false },
+                    KeyCode::Char('2') => ControlCommand::Sell { percent: 50.0, exit: false },
+                    KeyCode::Char('3') => ControlCommand::Sell { percent: 100.0, exit: false },
+                    KeyCode::Char('q') => ControlCommand::Sell { percent: 100.0, exit: true },
+                    KeyCode::Char('p') if dashboard.paused => ControlCommand::Resume,
+                    KeyCode::Char('p') => ControlCommand::Pause,
+                    KeyCode::Char('x') => ControlCommand::Shutdown,
+                    _ => continue,
+                };
+                dashboard.apply(DashboardEvent::Log(format!("发送 {:?}", command)));
+                let (request, reply) = ControlRequest::new(command);
+                if commands.blocking_send(request).is_err() {
+                    dashboard.apply(DashboardEvent::Log("交易主循环已退出".into()));
+                    continue;
+                }
+                // 不阻塞绘制，回复到达后写入日志
+                let replies = replies.clone();
+                runtime.spawn(async move {
+                    let line = match reply.await {
+                        Ok(Ok(value)) => format!("完成 {}", value),
+                        Ok(Err(e)) => format!("失败 {}", e),
+                        Err(_) => "没有回复".to_string(),
+                    };
+                    replies.send(DashboardEvent::Log(line));
+                });
+            }
+        })();
+        restore_terminal(&mut terminal);
+        result
+    });
+    Ok((handle.clone(), DashboardThread { handle, thread: Some(thread) }))
+}
+
+fn init_terminal() -> Result<Terminal<CrosstermBackend<Stderr>>> {
+    enable_raw_mode()?;
+    execute!(io::stderr(), EnterAlternateScreen)?;
+    // panic 时也要恢复终端，否则 panic 信息不可见
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        disable_raw_mode().ok();
+        execute!(io::stderr(), LeaveAlternateScreen).ok();
+        hook(info);
+    }));
+    Ok(Terminal::new(CrosstermBackend::new(io::stderr()))?)
+}
+
+fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stderr>>) {
+    disable_raw_mode().ok();
+    execute!(terminal.backend_mut(), LeaveAlternateScreen).ok();
+    terminal.show_cursor().ok();
+}
+
+#[test]
+fn test_dashboard_tracks_trades_and_txs() {
+    use ratatui::backend::TestBackend;
+
+    let mint = Pubkey::new_unique();
+    let mut dashboard = Dashboard::new(&[mint]);
+    let mut curve = Curve::initial();
+    let quote = curve.buy_with_sol(1_000_000_000);
+    curve.apply_buy(&quote);
+    let trade = |mint| TradeEvent {
+        mint,
+        sol_amount: quote.sol,
+        token_amount: quote.tokens,
+        is_buy: true,
+        user: Pubkey::new_unique(),
+        timestamp: 0,
+        virtual_sol_reserves: curve.virtual_sol_reserves,
+        virtual_token_reserves: curve.virtual_token_reserves,
+        real_sol_reserves: curve.real_sol_reserves,
+        real_token_reserves: curve.real_token_reserves,
+    };
+    // 没有跟踪的代币不显示
+    dashboard.apply(DashboardEvent::Trade { slot: 1, trade: trade(Pubkey::new_unique()) });
+    assert!(dashboard.tape.is_empty());
+    for slot in 0..TAPE_LEN as u64 + 5 {
+        dashboard.apply(DashboardEvent::Trade { slot, trade: trade(mint) });
+    }
+    assert_eq!(dashboard.tape.len(), TAPE_LEN);
+    assert_eq!(dashboard.tape.front().unwrap().slot, 5);
+    assert_eq!(dashboard.mints[&mint].curve, Some(curve));
+    dashboard.apply(DashboardEvent::Position { mint, tokens: quote.tokens });
+    assert_eq!(dashboard.mints[&mint].position, quote.tokens);
+
+    let landed = Signature::new_unique();
+    let expired = Signature::new_unique();
+    for signature in [landed, expired] {
+        dashboard.apply(DashboardEvent::TxSent { signature, wallet: Pubkey::new_unique(), side: "sell" });
+    }
+    dashboard.apply(DashboardEvent::TxLanded { signature: landed, slot: 9, success: true });
+    dashboard.apply(DashboardEvent::TxExpired(expired));
+    assert_eq!(dashboard.txs[0].state, TxState::Landed(9));
+    assert_eq!(dashboard.txs[1].state, TxState::Expired);
+
+    let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+    terminal.draw(|frame| dashboard.render(frame)).unwrap();
+}
@@ -1,19 +1,63 @@
-use anyhow::{anyhow, Ok, Result};
-use futures_util::Stream;
+use anyhow::{anyhow, Result};
+use futures::channel::mpsc;
+use futures_util::{stream, Sink, SinkExt, Stream, StreamExt};
 use solana_program::pubkey;
 use solana_sdk::pubkey::Pubkey;
-use std::env;
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, pin::Pin, time::Duration};
+use tracing::{error, warn};
 use yellowstone_grpc_client::{ClientTlsConfig, GeyserGrpcClient, Interceptor};
 use yellowstone_grpc_proto::{
     geyser::{
-        CommitmentLevel, SubscribeRequest, SubscribeRequestFilterTransactions, SubscribeUpdate,
+        subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+        SubscribeRequestFilterAccounts, SubscribeRequestFilterTransactions, SubscribeRequestPing,
+        SubscribeUpdate,
     },
     tonic::Status,
 };
 
+use crate::{metrics, replay::UpdateStream};
+
 type TransactionsFilterMap = HashMap<String, SubscribeRequestFilterTransactions>;
+type AccountsFilterMap = HashMap<String, SubscribeRequestFilterAccounts>;
+type SubscribeSink = Pin<Box<dyn Sink<SubscribeRequest, Error = mpsc::SendError> + Send>>;
 pub const PUMPFUN_PROGRAM_ID: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+
+/// 断线重连的退避设置
+#[derive(Debug, Clone)]
+pub struct Reconnect {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// 连续失败次数上限，超过后流结束；None 为一直重试
+    pub max_attempts: Option<usize>,
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+impl Reconnect {
+    fn backoff(&self, failures: usize) -> Duration {
+        let exp = failures.saturating_sub(1).min(16) as u32;
+        (self.initial_backoff * 2u32.pow(exp)).min(self.max_backoff)
+    }
+}
+
+struct ReconnectState {
+    client: GrpcClient,
+    request: SubscribeRequest,
+    reconnect: Reconnect,
+    /// 连续失败次数，收到消息后清零
+    failures: usize,
+    conn: Option<(SubscribeSink, UpdateStream)>,
+}
+
+#[derive(Clone)]
 pub struct GrpcClient {
     endpoint: String,
 }
@@ -77,19 +121,111 @@ impl GrpcClient {
 
         Ok(stream)
     }
-}
 
-pub async fn get_pumpfun_stream() -> Result<impl Stream<Item = Result<SubscribeUpdate, Status>>> {
-    let mut client = GeyserGrpcClient::build_from_shared(env::var("GRPC_URL")?)?
-        .tls_config(ClientTlsConfig::new().with_native_roots())?
-        .connect_timeout(Duration::from_secs(10))
-        .timeout(Duration::from_secs(60))
-        .connect()
-        .await
-        .map_err(|e| anyhow!("{:?}", e.to_string()))?;
+    /// 发送订阅请求，返回后续请求的发送端和更新流
+    pub async fn subscribe(&self, request: SubscribeRequest) -> Result<(SubscribeSink, UpdateStream)> {
+        let mut client = self.get_client().await?;
+        let (sink, stream) = client.subscribe_with_request(Some(request)).await?;
+        Ok((Box::pin(sink), Box::pin(stream)))
+    }
+
+    /// 订阅 `request`，流结束或出错后按指数退避重新订阅；错误仍然返回给调用方，服务端 ping 自动回复
+    pub fn subscribe_with_reconnect(self, request: SubscribeRequest, reconnect: Reconnect) -> UpdateStream {
+        let state = ReconnectState {
+            client: self,
+            request,
+            reconnect,
+            failures: 0,
+            conn: None,
+        };
+        Box::pin(stream::unfold(state, |mut state| async move {
+            loop {
+                if state.conn.is_none() {
+                    if state.failures > 0 {
+                        if state.reconnect.max_attempts.is_some_and(|max| state.failures > max) {
+                            error!(failures = state.failures, "Geyser 订阅多次失败，放弃重连");
+                            return None;
+                        }
+                        tokio::time::sleep(state.reconnect.backoff(state.failures)).await;
+                        metrics::STREAM_RECONNECTS.inc();
+                    }
+                    match state.client.subscribe(state.request.clone()).await {
+                        Ok(conn) => state.conn = Some(conn),
+                        Err(e) => {
+                            state.failures += 1;
+                            warn!(failures = state.failures, "Geyser 订阅失败: {:?}", e);
+                            continue;
+                        }
+                    }
+                }
+                let Some((sink, stream)) = state.conn.as_mut() else {
+                    continue;
+                };
+                match stream.next().await {
+                    Some(Ok(update)) => {
+                        state.failures = 0;
+                        if let Some(UpdateOneof::Ping(_)) = update.update_oneof {
+                            let ping = SubscribeRequest {
+                                ping: Some(SubscribeRequestPing { id: 1 }),
+                                ..Default::default()
+                            };
+                            sink.send(ping).await.ok();
+                        }
+                        return Some((Ok(update), state));
+                    }
+                    Some(Err(status)) => {
+                        state.conn = None;
+                        state.failures += 1;
+                        return Some((Err(status), state));
+                    }
+                    None => {
+                        warn!("Geyser 流已关闭，重新订阅");
+                        state.conn = None;
+                        state.failures += 1;
+                    }
+                }
+            }
+        }))
+    }
+
+    /// 订阅指定账户的数据更新
+    pub async fn subscribe_account(
+        &self,
+        account: Vec<String>,
+        commitment: CommitmentLevel,
+    ) -> Result<impl Stream<Item = Result<SubscribeUpdate, Status>>> {
+        let mut client = self.get_client().await?;
+
+        // 过滤规则
+        let mut accounts: AccountsFilterMap = HashMap::new();
+        accounts.insert(
+            "client".to_string(),
+            SubscribeRequestFilterAccounts {
+                account,
+                owner: vec![],
+                filters: vec![],
+                nonempty_txn_signature: None,
+            },
+        );
 
-    // 过滤规则
+        // request
+        let subscribe_request = SubscribeRequest {
+            accounts,
+            commitment: Some(commitment.into()),
+            ..Default::default()
+        };
 
+        // 返回流
+        let (_, stream) = client
+            .subscribe_with_request(Some(subscribe_request))
+            .await?;
+
+        Ok(stream)
+    }
+}
+
+/// 必须包含 pump.fun 程序的交易，processed 级别
+pub fn pumpfun_request() -> SubscribeRequest {
     let mut transactions: TransactionsFilterMap = HashMap::new();
     transactions.insert(
         "client".to_string(),
@@ -103,16 +239,15 @@ pub async fn get_pumpfun_stream() -> Result<impl Stream<Item = Result<SubscribeU
         },
     );
 
-    // request
-    let subscribe_request = SubscribeRequest {
+    SubscribeRequest {
         transactions,
         commitment: Some(CommitmentLevel::Processed.into()),
         ..Default::default()
-    };
+    }
+}
 
-    // 返回流
-    let (_, stream) = client
-        .subscribe_with_request(Some(subscribe_request))
-        .await?;
-    Ok(stream)
+/// 订阅 `grpc_url` 上的 pump.fun 交易，断线后自动重连
+pub async fn get_pumpfun_stream(grpc_url: &str) -> Result<UpdateStream> {
+    let client = GrpcClient::new(grpc_url.to_string());
+    Ok(client.subscribe_with_reconnect(pumpfun_request(), Reconnect::default()))
 }
@@ -0,0 +1,456 @@
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use futures::future::join_all;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_client::rpc_client::SerializableTransaction;
+use rand::{rng, seq::IndexedRandom};
+use solana_sdk::{bs58, pubkey::Pubkey};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::metrics;
+
+/// Jito 返回的限流错误码
+const RATE_LIMIT_CODE: i64 = -32097;
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+const BACKOFF_MAX: Duration = Duration::from_secs(10);
+
+/// `getTipAccounts` 不可用时使用的 tip 账户
+pub const FALLBACK_TIP_ACCOUNTS: [&str; 8] = [
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+];
+
+#[derive(Debug, Error)]
+pub enum JitoError {
+    #[error("{0}: rate limited")]
+    RateLimited(String),
+    #[error("{url}: rpc error {code}: {message}")]
+    Rpc {
+        url: String,
+        code: i64,
+        message: String,
+    },
+    #[error("{0}: request failed: {1}")]
+    Request(String, String),
+    #[error("{0}: unexpected response: {1}")]
+    InvalidResponse(String, Value),
+    #[error("no block engine available, all rate limited")]
+    Unavailable,
+}
+
+impl JitoError {
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self, Self::RateLimited(_))
+    }
+}
+
+/// 把交易编码成 `sendBundle` 需要的 base58 数组
+pub fn encode_bundle(bundle: Vec<impl SerializableTransaction>) -> Result<Value> {
+    let mut params = vec![];
+    for tx in bundle {
+        params.push(bs58::encode(bincode::serialize(&tx)?).into_string());
+    }
+    Ok(json!(params))
+}
+
+/// 解析 block engine 的 JSON-RPC 响应，返回 `result` 字段
+pub fn parse_response(url: &str, resp: Value) -> Result<Value, JitoError> {
+    if let Some(error) = resp.get("error") {
+        let code = error.get("code").and_then(Value::as_i64).unwrap_or_default();
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        if code == RATE_LIMIT_CODE {
+            return Err(JitoError::RateLimited(url.to_string()));
+        }
+        return Err(JitoError::Rpc {
+            url: url.to_string(),
+            code,
+            message,
+        });
+    }
+    match resp.get("result") {
+        Some(result) => Ok(result.clone()),
+        None => Err(JitoError::InvalidResponse(url.to_string(), resp)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Selection {
+    RoundRobin,
+    LowestLatency,
+    /// 同时发送给所有可用区域
+    #[default]
+    Broadcast,
+}
+
+impl FromStr for Selection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "round-robin" => Ok(Self::RoundRobin),
+            "lowest-latency" => Ok(Self::LowestLatency),
+            "broadcast" => Ok(Self::Broadcast),
+            _ => Err(anyhow!("unknown jito selection: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EngineStats {
+    pub sent: u64,
+    pub failed: u64,
+    pub rate_limited: u64,
+    /// 成功请求延迟的指数移动平均
+    pub latency: Option<Duration>,
+    consecutive_limits: u32,
+    backoff_until: Option<Instant>,
+}
+
+impl EngineStats {
+    pub fn is_backing_off(&self, now: Instant) -> bool {
+        self.backoff_until.is_some_and(|until| until > now)
+    }
+}
+
+pub struct BlockEngine {
+    pub url: String,
+    client: Client,
+    stats: Mutex<EngineStats>,
+}
+
+impl BlockEngine {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            client: Client::new(),
+            stats: Mutex::new(EngineStats::default()),
+        }
+    }
+
+    /// 向 `{url}/bundles` 发送 JSON-RPC 请求，HTTP 429 和限流错误码都按限流处理
+    async fn request(&self, method: &str, params: Value) -> Result<Value, JitoError> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response = self
+            .client
+            .post(format!("{}/bundles", self.url))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| JitoError::Request(self.url.clone(), e.to_string()))?;
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(JitoError::RateLimited(self.url.clone()));
+        }
+        match response.json::<Value>().await {
+            Ok(resp) => parse_response(&self.url, resp),
+            Err(_) if !status.is_success() => Err(JitoError::Request(self.url.clone(), format!("http status {}", status))),
+            Err(e) => Err(JitoError::Request(self.url.clone(), e.to_string())),
+        }
+    }
+
+    pub fn stats(&self) -> EngineStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    fn record<T>(&self, elapsed: Duration, result: &Result<T, JitoError>) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.sent += 1;
+        match result {
+            Ok(_) => {
+                stats.consecutive_limits = 0;
+                stats.backoff_until = None;
+                stats.latency = Some(match stats.latency {
+                    Some(avg) => avg.mul_f64(0.8) + elapsed.mul_f64(0.2),
+                    None => elapsed,
+                });
+            }
+            Err(e) if e.is_rate_limited() => {
+                stats.failed += 1;
+                stats.rate_limited += 1;
+                let backoff = BACKOFF_BASE
+                    .saturating_mul(1 << stats.consecutive_limits.min(5))
+                    .min(BACKOFF_MAX);
+                stats.consecutive_limits += 1;
+                stats.backoff_until = Some(Instant::now() + backoff);
+            }
+            Err(_) => stats.failed += 1,
+        }
+    }
+
+    pub async fn send_bundle(&self, bundle: Value) -> Result<String, JitoError> {
+        let start = Instant::now();
+        let result = self.request("sendBundle", json!([bundle])).await.and_then(|result| {
+            result
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| JitoError::InvalidResponse(self.url.clone(), result))
+        });
+        self.record(start.elapsed(), &result);
+        let label = match &result {
+            Ok(_) => "ok",
+            Err(e) if e.is_rate_limited() => "rate_limited",
+            Err(_) => "error",
+        };
+        metrics::JITO_REQUESTS.with_label_values(&[&self.url, label]).inc();
+        result
+    }
+
+    pub async fn get_tip_accounts(&self) -> Result<Value, JitoError> {
+        let start = Instant::now();
+        let result = self.request("getTipAccounts", json!([])).await;
+        self.record(start.elapsed(), &result);
+        result
+    }
+}
+
+/// 多个 block engine 区域，被限流的区域在退避期内跳过；默认 [`Selection::Broadcast`] 发往所有可用区域
+pub struct JitoPool {
+    engines: Vec<BlockEngine>,
+    selection: Selection,
+    next: AtomicUsize,
+}
+
+impl JitoPool {
+    pub fn new(urls: &[String], selection: Selection) -> Self {
+        Self {
+            engines: urls.iter().map(|url| BlockEngine::new(url)).collect(),
+            selection,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// 逗号分隔的 block engine 地址
+    pub fn from_env_list(urls: &str, selection: Selection) -> Self {
+        let urls: Vec<String> = urls
+            .split(',')
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .map(str::to_string)
+            .collect();
+        Self::new(&urls, selection)
+    }
+
+    pub fn engines(&self) -> &[BlockEngine] {
+        &self.engines
+    }
+
+    /// 按选择策略排序的可用区域，限流中的排除在外
+    fn ordered(&self) -> Vec<&BlockEngine> {
+        let now = Instant::now();
+        let mut available: Vec<(usize, &BlockEngine)> = self
+            .engines
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.stats.lock().unwrap().is_backing_off(now))
+            .collect();
+        match self.selection {
+            Selection::RoundRobin | Selection::Broadcast => {
+                if !available.is_empty() {
+                    let start = self.next.fetch_add(1, Ordering::Relaxed) % available.len();
+                    available.rotate_left(start);
+                }
+            }
+            Selection::LowestLatency => {
+                // 没有延迟数据的区域优先探测
+                available.sort_by_key(|(_, e)| e.stats().latency.unwrap_or(Duration::ZERO));
+            }
+        }
+        available.into_iter().map(|(_, e)| e).collect()
+    }
+
+    /// 发送 bundle，限流时切换到下一个区域，全部失败时返回最后一个错误
+    pub async fn send_bundle(&self, bundle: Vec<impl SerializableTransaction>) -> Result<String> {
+        let bundle = encode_bundle(bundle)?;
+        let engines = self.ordered();
+        if engines.is_empty() {
+            return Err(JitoError::Unavailable.into());
+        }
+
+        if self.selection == Selection::Broadcast {
+            let results = join_all(engines.iter().map(|e| e.send_bundle(bundle.clone()))).await;
+            let mut last_err = JitoError::Unavailable;
+            for result in results {
+                match result {
+                    Ok(bundle_id) => return Ok(bundle_id),
+                    Err(e) => last_err = e,
+                }
+            }
+            return Err(last_err.into());
+        }
+
+        let mut last_err = JitoError::Unavailable;
+        for engine in engines {
+            match engine.send_bundle(bundle.clone()).await {
+                Ok(bundle_id) => return Ok(bundle_id),
+                Err(e) if e.is_rate_limited() => {
+                    warn!("Jito 区域被限流: {}", e);
+                    last_err = e;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(last_err.into())
+    }
+
+    /// 依次向可用区域请求 tip 账户
+    pub async fn get_tip_accounts(&self) -> Result<Vec<Pubkey>> {
+        let mut last_err = JitoError::Unavailable;
+        for engine in self.ordered() {
+            match engine.get_tip_accounts().await {
+                Ok(result) => return parse_tip_accounts(&engine.url, result),
+                Err(e) => last_err = e,
+            }
+        }
+        Err(last_err.into())
+    }
+}
+
+fn parse_tip_accounts(url: &str, result: Value) -> Result<Vec<Pubkey>> {
+    let accounts = result
+        .as_array()
+        .ok_or_else(|| JitoError::InvalidResponse(url.to_string(), result.clone()))?
+        .iter()
+        .filter_map(Value::as_str)
+        .map(Pubkey::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    if accounts.is_empty() {
+        return Err(anyhow!("jito: {} returned no tip accounts", url));
+    }
+    Ok(accounts)
+}
+
+fn fallback_tip_accounts() -> Vec<Pubkey> {
+    FALLBACK_TIP_ACCOUNTS
+        .iter()
+        .map(|acc| Pubkey::from_str(acc).unwrap())
+        .collect()
+}
+
+/// 从 block engine 获取的 tip 账户，获取失败时使用内置列表
+#[derive(Debug, Clone)]
+pub struct TipAccounts {
+    accounts: Arc<RwLock<Vec<Pubkey>>>,
+}
+
+impl Default for TipAccounts {
+    fn default() -> Self {
+        Self {
+            accounts: Arc::new(RwLock::new(fallback_tip_accounts())),
+        }
+    }
+}
+
+impl TipAccounts {
+    /// 启动时拉取一次，失败则使用内置列表
+    pub async fn fetch(pool: &JitoPool) -> Self {
+        let tip_accounts = Self::default();
+        tip_accounts.refresh(pool).await;
+        tip_accounts
+    }
+
+    pub async fn refresh(&self, pool: &JitoPool) {
+        match pool.get_tip_accounts().await {
+            Ok(accounts) => *self.accounts.write().unwrap() = accounts,
+            Err(e) => warn!("刷新 tip 账户失败，继续使用当前列表: {:?}", e),
+        }
+    }
+
+    pub fn spawn_refresh(&self, pool: Arc<JitoPool>, interval: Duration) {
+        let tip_accounts = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // 第一次 tick 立即返回，启动时已经拉取过
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                tip_accounts.refresh(&pool).await;
+            }
+        });
+    }
+
+    pub fn accounts(&self) -> Vec<Pubkey> {
+        self.accounts.read().unwrap().clone()
+    }
+
+    pub fn contains(&self, account: &Pubkey) -> bool {
+        self.accounts.read().unwrap().contains(account)
+    }
+
+    pub fn random(&self) -> Result<Pubkey> {
+        self.accounts
+            .read()
+            .unwrap()
+            .choose(&mut rng())
+            .copied()
+            .ok_or_else(|| anyhow!("jito: no tip accounts available"))
+    }
+}
+
+/// 从内置列表随机选择一个 tip 账户
+pub fn get_tip_account() -> Result<Pubkey> {
+    fallback_tip_accounts()
+        .choose(&mut rng())
+        .copied()
+        .ok_or_else(|| anyhow!("jito: no tip accounts available"))
+}
+
+#[test]
+fn test_parse_response_rate_limit() {
+    let resp = json!({
+        "jsonrpc": "2.0",
+        "error": {"code": -32097, "message": "Rate limit exceeded. Limit: 1 per second for txn requests"},
+        "id": 1
+    });
+    assert!(parse_response("ny", resp).unwrap_err().is_rate_limited());
+
+    let resp = json!({"jsonrpc": "2.0", "result": "abc", "id": 1});
+    assert_eq!(parse_response("ny", resp).unwrap(), json!("abc"));
+}
+
+#[tokio::test]
+async fn test_block_engine_http_rate_limit() {
+    use axum::{http::StatusCode as HttpStatus, routing::post, Json, Router};
+
+    async fn bundles(Json(body): Json<Value>) -> (HttpStatus, Json<Value>) {
+        match body["method"].as_str() {
+            Some("getTipAccounts") => (HttpStatus::OK, Json(json!({ "jsonrpc": "2.0", "result": FALLBACK_TIP_ACCOUNTS, "id": 1 }))),
+            _ => (HttpStatus::TOO_MANY_REQUESTS, Json(json!({ "message": "slow down" }))),
+        }
+    }
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api/v1", listener.local_addr().unwrap());
+    let app = Router::new().route("/api/v1/bundles", post(bundles));
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let pool = JitoPool::new(&[url], Selection::default());
+    assert_eq!(pool.get_tip_accounts().await.unwrap(), fallback_tip_accounts());
+    // HTTP 429 按状态码识别为限流，区域进入退避
+    let err = pool.engines()[0].send_bundle(json!([])).await.unwrap_err();
+    assert!(err.is_rate_limited());
+    let stats = pool.engines()[0].stats();
+    assert_eq!((stats.sent, stats.rate_limited), (2, 1));
+    assert!(stats.is_backing_off(Instant::now()));
+    assert!(pool.send_bundle(Vec::<solana_sdk::transaction::Transaction>::new()).await.is_err());
+}
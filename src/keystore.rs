@@ -0,0 +1,196 @@
+use std::{fs, io::Write, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    bs58,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+    signer::Signer,
+};
+
+/// 当前格式版本
+const VERSION: u32 = 1;
+/// scrypt 默认参数：N = 2^15，约 32MB 内存
+pub const DEFAULT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+/// 没有设置时交互输入口令
+pub const PASSPHRASE_ENV: &str = "PK_PASSPHRASE";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    /// base58
+    pub salt: String,
+}
+
+/// 口令加密的 keypair 文件：scrypt 派生密钥，XChaCha20-Poly1305 加密，公钥明文保存并作为关联数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    pub pubkey: String,
+    pub scrypt: ScryptParams,
+    /// base58
+    pub nonce: String,
+    /// base58
+    pub ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, params: &ScryptParams) -> Result<Key> {
+    let salt = bs58::decode(&params.salt).into_vec()?;
+    let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+        .map_err(|e| anyhow!("keystore: invalid scrypt params: {}", e))?;
+    let mut key = Key::default();
+    scrypt::scrypt(passphrase.as_bytes(), &salt, &scrypt_params, &mut key)
+        .map_err(|e| anyhow!("keystore: scrypt failed: {}", e))?;
+    Ok(key)
+}
+
+impl Keystore {
+    pub fn encrypt(keypair: &Keypair, passphrase: &str, log_n: u8) -> Result<Self> {
+        if passphrase.is_empty() {
+            bail!("keystore: passphrase must not be empty");
+        }
+        let scrypt = ScryptParams {
+            log_n,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: bs58::encode(rand::random::<[u8; 32]>()).into_string(),
+        };
+        let nonce: [u8; 24] = rand::random();
+        let pubkey = keypair.pubkey();
+        let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &scrypt)?);
+        let ciphertext = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &keypair.to_bytes(),
+                    aad: pubkey.as_ref(),
+                },
+            )
+            .map_err(|_| anyhow!("keystore: encryption failed"))?;
+        Ok(Self {
+            version: VERSION,
+            pubkey: pubkey.to_string(),
+            scrypt,
+            nonce: bs58::encode(nonce).into_string(),
+            ciphertext: bs58::encode(ciphertext).into_string(),
+        })
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> Result<Keypair> {
+        if self.version != VERSION {
+            bail!("keystore: unsupported version {}", self.version);
+        }
+        let pubkey: Pubkey = self.pubkey.parse()?;
+        let nonce = bs58::decode(&self.nonce).into_vec()?;
+        if nonce.len() != 24 {
+            bail!("keystore: nonce must be 24 bytes, got {}", nonce.len());
+        }
+        let ciphertext = bs58::decode(&self.ciphertext).into_vec()?;
+        let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &self.scrypt)?);
+        let bytes = cipher
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: pubkey.as_ref(),
+                },
+            )
+            .map_err(|_| anyhow!("keystore: wrong passphrase or corrupted file"))?;
+        let keypair = Keypair::from_bytes(&bytes).map_err(|e| anyhow!("keystore: {}", e))?;
+        if keypair.pubkey() != pubkey {
+            bail!("keystore: decrypted key does not match {}", pubkey);
+        }
+        Ok(keypair)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&data).with_context(|| format!("{} is not a keystore", path.display()))
+    }
+
+    /// 写入文件，unix 上只有所有者可读写
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options
+            .open(path.as_ref())
+            .with_context(|| format!("failed to create {}", path.as_ref().display()))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// 口令：优先读取 `PK_PASSPHRASE`，否则在终端输入（不回显）
+pub fn read_passphrase(prompt: &str) -> Result<String> {
+    match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => Ok(passphrase),
+        Err(_) => Ok(rpassword::prompt_password(prompt)?),
+    }
+}
+
+/// 新建 keystore 的口令，交互输入时需要确认一次
+pub fn read_new_passphrase() -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    let passphrase = rpassword::prompt_password("new passphrase: ")?;
+    if rpassword::prompt_password("confirm passphrase: ")? != passphrase {
+        bail!("passphrases do not match");
+    }
+    Ok(passphrase)
+}
+
+/// 读取 keypair 文件：加密的 keystore 或 `solana-keygen` 生成的 JSON 数组
+pub fn read_keypair(path: impl AsRef<Path>) -> Result<Keypair> {
+    read_keypair_with(path, read_passphrase)
+}
+
+/// 同 `read_keypair`，keystore 的口令由 `passphrase` 按提示语给出
+pub fn read_keypair_with(path: impl AsRef<Path>, passphrase: impl FnOnce(&str) -> Result<String>) -> Result<Keypair> {
+    let path = path.as_ref();
+    let data = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    if data.trim_start().starts_with('{') {
+        let keystore: Keystore =
+            serde_json::from_str(&data).with_context(|| format!("{} is not a keystore", path.display()))?;
+        let passphrase = passphrase(&format!("passphrase for {} ({}): ", path.display(), keystore.pubkey))?;
+        return keystore.decrypt(&passphrase);
+    }
+    read_keypair_file(path).map_err(|e| anyhow!("invalid keypair file {}: {}", path.display(), e))
+}
+
+#[test]
+fn test_keystore_round_trip() {
+    let keypair = Keypair::new();
+    // 测试用较小的 N
+    let keystore = Keystore::encrypt(&keypair, "correct horse", 4).unwrap();
+    assert_eq!(keystore.pubkey, keypair.pubkey().to_string());
+    assert!(!keystore.ciphertext.contains(&keypair.to_base58_string()));
+    assert_eq!(keystore.decrypt("correct horse").unwrap(), keypair);
+    assert!(keystore.decrypt("wrong").is_err());
+
+    // 公钥作为关联数据，被替换后无法解密
+    let mut tampered = keystore.clone();
+    tampered.pubkey = Pubkey::new_unique().to_string();
+    assert!(tampered.decrypt("correct horse").is_err());
+
+    let path = std::env::temp_dir().join(format!("solana_pump_keystore_{}.json", std::process::id()));
+    let _ = fs::remove_file(&path);
+    keystore.save(&path).unwrap();
+    // 不覆盖已有的文件
+    assert!(keystore.save(&path).is_err());
+    assert_eq!(read_keypair_with(&path, |_| Ok("correct horse".to_string())).unwrap(), keypair);
+    assert!(read_keypair_with(&path, |_| Ok("wrong".to_string())).is_err());
+    let _ = fs::remove_file(&path);
+}
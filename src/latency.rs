@@ -0,0 +1,309 @@
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, HashMap, VecDeque},
+    time::{Duration, Instant, SystemTime},
+};
+
+use serde::Serialize;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{Signer, SignerError},
+};
+use yellowstone_grpc_proto::prost_types::Timestamp;
+
+use crate::metrics;
+
+/// 每个阶段保留的最近样本数
+const MAX_SAMPLES: usize = 10_000;
+
+/// 收到 Geyser 更新时打的时间戳，更新中的事件和由它触发的交易都带着它
+#[derive(Debug, Clone, Copy)]
+pub struct EventStamp {
+    pub slot: u64,
+    pub received: Instant,
+    /// 本机收到的时间
+    pub received_at: SystemTime,
+    /// Geyser 节点生成这条更新的时间，回放和旧版本节点没有
+    pub created_at: Option<SystemTime>,
+}
+
+impl EventStamp {
+    /// `received` 为收到更新的时间，`created_at` 取自更新本身
+    pub fn new(slot: u64, received: Instant, created_at: Option<Timestamp>) -> Self {
+        Self {
+            slot,
+            received,
+            received_at: SystemTime::now() - received.elapsed(),
+            created_at: created_at.and_then(|ts| SystemTime::try_from(ts).ok()),
+        }
+    }
+
+    /// Geyser 节点生成到本机收到的耗时，两边时钟不同步时可能取不到
+    pub fn geyser_delay(&self) -> Option<Duration> {
+        self.received_at.duration_since(self.created_at?).ok()
+    }
+}
+
+/// 从收到更新到交易发出的各个阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    /// Geyser 节点生成更新到本机收到
+    Geyser,
+    /// 收到到解析出事件
+    Decode,
+    /// 解析出事件到交易构造完成，包括计算利润和获取 blockhash
+    Build,
+    Sign,
+    /// 签名完成到最先返回成功的发送通道确认
+    Send,
+    /// 收到更新到交易发出
+    Total,
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Geyser => "geyser",
+            Stage::Decode => "decode",
+            Stage::Build => "build",
+            Stage::Sign => "sign",
+            Stage::Send => "send",
+            Stage::Total => "total",
+        }
+    }
+}
+
+/// 一笔交易从触发事件到发出的时间戳
+#[derive(Debug, Clone, Copy)]
+pub struct OrderTiming {
+    pub trigger: EventStamp,
+    pub decoded: Instant,
+    pub built: Instant,
+    pub signed: Instant,
+    pub sent: Instant,
+}
+
+impl OrderTiming {
+    pub fn stages(&self) -> [(Stage, Duration); 4] {
+        [
+            (Stage::Build, self.built.saturating_duration_since(self.decoded)),
+            (Stage::Sign, self.signed.saturating_duration_since(self.built)),
+            (Stage::Send, self.sent.saturating_duration_since(self.signed)),
+            (Stage::Total, self.sent.saturating_duration_since(self.trigger.received)),
+        ]
+    }
+}
+
+/// 统计签名耗时的 Signer 包装。交易在构造函数内部签名，用它把签名和构造的耗时分开
+pub struct TimedSigner<'a> {
+    inner: &'a dyn Signer,
+    elapsed: Cell<Duration>,
+}
+
+impl<'a> TimedSigner<'a> {
+    pub fn new(inner: &'a dyn Signer) -> Self {
+        Self {
+            inner,
+            elapsed: Cell::new(Duration::ZERO),
+        }
+    }
+
+    /// 累计的签名耗时
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed.get()
+    }
+}
+
+impl Signer for TimedSigner<'_> {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        self.inner.try_pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let start = Instant::now();
+        let signature = self.inner.try_sign_message(message);
+        self.elapsed.set(self.elapsed.get() + start.elapsed());
+        signature
+    }
+
+    fn is_interactive(&self) -> bool {
+        self.inner.is_interactive()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageSummary {
+    pub count: usize,
+    pub p50_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+/// 落地 slot 减去触发事件的 slot
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlotSummary {
+    pub count: usize,
+    pub p50: u64,
+    pub p99: u64,
+    pub max: u64,
+    /// 和触发事件在同一个 slot 落地的交易数
+    pub same_slot: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencyReport {
+    pub stages: BTreeMap<Stage, StageSummary>,
+    pub landing_slots: Option<SlotSummary>,
+}
+
+/// 最近的延迟样本和待落地交易的触发 slot
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    samples: HashMap<Stage, VecDeque<Duration>>,
+    /// 已发出的交易的触发 slot，成交后移除
+    pending: HashMap<Signature, u64>,
+    slot_deltas: VecDeque<u64>,
+}
+
+impl LatencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, stage: Stage, elapsed: Duration) {
+        metrics::STAGE_LATENCY.with_label_values(&[stage.name()]).observe(elapsed.as_secs_f64());
+        push_bounded(self.samples.entry(stage).or_default(), elapsed);
+    }
+
+    /// 每条更新解析完成后调用
+    pub fn record_event(&mut self, stamp: &EventStamp, decoded: Instant) {
+        if let Some(delay) = stamp.geyser_delay() {
+            self.record(Stage::Geyser, delay);
+        }
+        self.record(Stage::Decode, decoded.saturating_duration_since(stamp.received));
+    }
+
+    /// 交易发出后调用，记录各阶段耗时，并等待成交比较落地 slot
+    pub fn record_order(&mut self, signature: Signature, timing: &OrderTiming) {
+        for (stage, elapsed) in timing.stages() {
+            self.record(stage, elapsed);
+        }
+        self.pending.insert(signature, timing.trigger.slot);
+    }
+
+    /// 按确认的交易数据记录落地 slot，返回比触发事件晚的 slot 数；不是触发的交易时返回 None
+    pub fn landed(&mut self, signature: &Signature, slot: u64) -> Option<u64> {
+        let trigger = self.pending.remove(signature)?;
+        let delta = slot.saturating_sub(trigger);
+        metrics::LANDING_SLOTS.observe(delta as f64);
+        push_bounded(&mut self.slot_deltas, delta);
+        Some(delta)
+    }
+
+    /// 交易过期或取不到成交时调用
+    pub fn forget(&mut self, signature: &Signature) {
+        self.pending.remove(signature);
+    }
+
+    pub fn report(&self) -> LatencyReport {
+        let stages = self
+            .samples
+            .iter()
+            .filter(|(_, samples)| !samples.is_empty())
+            .map(|(stage, samples)| {
+                let mut micros: Vec<u64> = samples.iter().map(|d| d.as_micros() as u64).collect();
+                micros.sort_unstable();
+                let summary = StageSummary {
+                    count: micros.len(),
+                    p50_us: percentile(&micros, 50.0),
+                    p99_us: percentile(&micros, 99.0),
+                    max_us: *micros.last().unwrap(),
+                };
+                (*stage, summary)
+            })
+            .collect();
+        let landing_slots = (!self.slot_deltas.is_empty()).then(|| {
+            let mut deltas: Vec<u64> = self.slot_deltas.iter().copied().collect();
+            deltas.sort_unstable();
+            SlotSummary {
+                count: deltas.len(),
+                p50: percentile(&deltas, 50.0),
+                p99: percentile(&deltas, 99.0),
+                max: *deltas.last().unwrap(),
+                same_slot: deltas.iter().filter(|d| **d == 0).count(),
+            }
+        });
+        LatencyReport { stages, landing_slots }
+    }
+}
+
+fn push_bounded<T>(samples: &mut VecDeque<T>, value: T) {
+    if samples.len() == MAX_SAMPLES {
+        samples.pop_front();
+    }
+    samples.push_back(value);
+}
+
+/// 最近秩法的百分位数，`sorted` 不能为空
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[test]
+fn test_latency_report() {
+    use solana_sdk::signature::Keypair;
+
+    let mut tracker = LatencyTracker::new();
+    let trigger = EventStamp::new(100, Instant::now(), None);
+    assert_eq!(trigger.geyser_delay(), None);
+    let ms = Duration::from_millis;
+    for i in 1..=100 {
+        tracker.record(Stage::Decode, Duration::from_micros(i));
+    }
+    let timing = OrderTiming {
+        trigger,
+        decoded: trigger.received + ms(1),
+        built: trigger.received + ms(3),
+        signed: trigger.received + ms(4),
+        sent: trigger.received + ms(10),
+    };
+    let signatures: Vec<Signature> = (0..3).map(|_| Keypair::new().sign_message(b"order")).collect();
+    for signature in &signatures {
+        tracker.record_order(*signature, &timing);
+    }
+    assert_eq!(tracker.landed(&signatures[0], 100), Some(0));
+    assert_eq!(tracker.landed(&signatures[1], 102), Some(2));
+    assert_eq!(tracker.landed(&signatures[1], 102), None);
+    tracker.forget(&signatures[2]);
+    assert_eq!(tracker.landed(&signatures[2], 101), None);
+
+    let report = tracker.report();
+    assert_eq!(
+        report.stages[&Stage::Decode],
+        StageSummary { count: 100, p50_us: 50, p99_us: 99, max_us: 100 }
+    );
+    assert_eq!(report.stages[&Stage::Build].p50_us, 2_000);
+    assert_eq!(report.stages[&Stage::Send].p99_us, 6_000);
+    assert_eq!(report.stages[&Stage::Total].max_us, 10_000);
+    assert!(!report.stages.contains_key(&Stage::Geyser));
+    assert_eq!(
+        report.landing_slots,
+        Some(SlotSummary { count: 2, p50: 0, p99: 2, max: 2, same_slot: 1 })
+    );
+}
+
+#[test]
+fn test_timed_signer() {
+    use solana_sdk::signature::Keypair;
+
+    let keypair = Keypair::new();
+    let signer = TimedSigner::new(&keypair);
+    assert_eq!(signer.elapsed(), Duration::ZERO);
+    let signature = signer.sign_message(b"message");
+    assert!(signature.verify(keypair.pubkey().as_ref(), b"message"));
+    assert_eq!(signer.pubkey(), keypair.pubkey());
+    assert!(signer.elapsed() > Duration::ZERO);
+}
@@ -1,4 +1,24 @@
+pub mod backtest;
+pub mod config;
+pub mod confirm;
 pub mod constants;
+pub mod control;
+pub mod curve;
+pub mod dashboard;
 pub mod grpc;
+pub mod jito;
+pub mod keystore;
+pub mod latency;
+pub mod metrics;
 pub mod monitor;
+pub mod paper;
+pub mod pnl;
+pub mod price;
+pub mod remote_signer;
+pub mod replay;
+pub mod risk;
+pub mod store;
+pub mod submit;
+pub mod tip;
 pub mod utils;
+pub mod wallet;
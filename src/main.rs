@@ -1,230 +1,1171 @@
-use anyhow::Result;
-use jito_sdk_rust::JitoJsonRpcSDK;
+use anyhow::{anyhow, bail, Result};
+use clap::{Args, Parser, Subcommand};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_pump::{
-    grpc::get_pumpfun_stream,
-    monitor::{ 
-        cal_pumpfun_price, create_sell_transaction, find_bonding_curve, get_pumpfun_reserve, process_tx_with_meta, send_bundle, transfer_tx
-    }, utils::{check_sol_change, get_sol_price},
+    config::{
+        parse_keypair, parse_mint, parse_slippage_bps, parse_sol, require, watch_config, Config, LogFormat, LogSettings,
+        PriceSettings, SendSettings, StreamSettings,
+    },
+    confirm::ConfirmationTracker,
+    control::{spawn_control_api, ControlCommand, ControlRequest, PnlStatus, PositionStatus, Status},
+    curve::Curve,
+    dashboard::{spawn_dashboard, DashboardEvent, DashboardHandle},
+    grpc::{get_pumpfun_stream, GrpcClient},
+    jito::{JitoPool, Selection, TipAccounts},
+    keystore::{read_new_passphrase, Keystore, DEFAULT_LOG_N},
+    latency::{EventStamp, LatencyTracker, OrderTiming, TimedSigner},
+    metrics::{self, spawn_metrics_server},
+    monitor::{
+        cal_pumpfun_price, convert_to_encoded_tx, create_buy_transaction, create_partial_sell_transaction,
+        create_sell_transaction, create_token_transaction, find_bonding_curve, get_pumpfun_reserve, instruction_programs, min_sol_out, parse_events,
+        process_tx_with_meta, transfer_tx, ComputeBudget, CreateArgs, SellParams, TargetEvent,
+    },
+    paper::{PaperOrder, PaperSide, PaperTrader},
+    pnl::{fetch_fill, Fill, PnlLedger},
+    price::{HttpPrice, PriceFeed, PriceOracle, SolUsdTracker},
+    remote_signer::RemoteSigner,
+    risk::{RiskError, RiskGuard},
+    replay::{record_stream, replay_stream, Playback, StreamRecorder, UpdateStream},
+    store::{unix_now, FillRecord, OrderRecord, Store, StoreRecord},
+    submit::{SubmitPath, Submitter},
+    tip::{TipFloorCache, TipFloorSource, TipPolicy},
+    utils::{check_sol_change, should_sell},
+    wallet::{buy_fees, get_token_balance, WalletPool},
 };
 use solana_sdk::{
-    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Keypair, signer::Signer
+    bs58, hash::Hash, pubkey::Pubkey, signature::{Keypair, Signature}, signer::Signer, transaction::Transaction
 };
 
-use spl_associated_token_account::get_associated_token_address_with_program_id;
-use tokio::sync::{mpsc, Mutex, Notify};
-use std::{io, str::FromStr, sync::Arc};
+use tokio::{sync::{mpsc, watch}, task::JoinHandle};
+use std::{collections::HashMap, net::SocketAddr, path::PathBuf, sync::{Arc, Mutex as StdMutex}, time::{Duration, Instant}};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
 use yellowstone_grpc_proto::prelude::subscribe_update::UpdateOneof;
 
 use dotenv::dotenv;
+use futures::future::{join_all, try_join_all, BoxFuture};
+use futures::FutureExt;
 use futures_util::stream::StreamExt;
-use std::env;
+use serde_json::json;
 
-// 定义一个命令枚举
-enum Command {
-    Sell(f32), // 包含价格的卖出命令
+/// pump.fun 狙击和交易工具。
+/// 配置优先级：命令行参数 > 环境变量（支持 .env）> `--config` 指定的 TOML 配置文件
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// TOML 配置文件，`[strategy]` 和 `[fees]` 修改后自动重新加载
+    #[arg(long, global = true, env = "PUMP_CONFIG")]
+    config: Option<PathBuf>,
+    /// 使用配置文件中的 `[profiles.<name>]`
+    #[arg(long, global = true, env = "PUMP_PROFILE")]
+    profile: Option<String>,
+    #[arg(long, global = true, env = "RPC_URL")]
+    rpc_url: Option<String>,
+    #[arg(long, global = true, env = "GRPC_URL")]
+    grpc_url: Option<String>,
+    /// base58 私钥或 keypair 文件路径，加密的 keystore 从 PK_PASSPHRASE 读取口令或交互输入
+    #[arg(long, global = true, env = "PK", hide_env_values = true)]
+    pk: Option<String>,
+    /// Jito block engine 地址，逗号分隔
+    #[arg(long, global = true, env = "JITO")]
+    jito: Option<String>,
+    /// 签名进程（`signer`）的 Unix socket，设置后不再读取 --pk
+    #[arg(long, global = true, env = "SIGNER_SOCKET")]
+    signer_socket: Option<PathBuf>,
+    /// 钱包池：逗号分隔的多个私钥或 keypair 文件，设置后替代 --pk
+    #[arg(long, global = true, env = "WALLETS", value_delimiter = ',', hide_env_values = true)]
+    wallets: Vec<String>,
+    /// Jito tip（lamports），0 表示不走 Jito，设置后不再从配置文件热加载
+    #[arg(long, global = true, env = "TIP")]
+    tip: Option<u64>,
+    /// 日志级别，`tracing_subscriber::EnvFilter` 语法，例如 `info,solana_pump::confirm=debug`
+    #[arg(long, global = true, env = "LOG_LEVEL")]
+    log_level: Option<String>,
+    /// 日志格式，json 每行一个对象，带上事件和交易 span 的字段
+    #[arg(long, global = true, env = "LOG_FORMAT", value_enum)]
+    log_format: Option<LogFormat>,
+    /// 除 --rpc-url 外同时发送的 RPC 节点，逗号分隔
+    #[arg(long, global = true, env = "SEND_RPC_URLS", value_delimiter = ',')]
+    send_rpc_urls: Vec<String>,
+    /// 设置后通过 QUIC 直接发送给 leader 的 TPU
+    #[arg(long, global = true, env = "WS_URL")]
+    ws_url: Option<String>,
+    /// Jito 区域选择：broadcast（默认）、round-robin 或 lowest-latency
+    #[arg(long, global = true, env = "JITO_SELECTION")]
+    jito_selection: Option<Selection>,
+    /// tip floor 地址，设置后按 tip floor 动态调整 tip
+    #[arg(long, global = true, env = "TIP_FLOOR_URL")]
+    tip_floor_url: Option<String>,
+    /// 动态 tip 不超过预期利润的比例，设置后不再从配置文件热加载
+    #[arg(long, global = true, env = "TIP_MAX_PROFIT_FRACTION")]
+    tip_max_profit_fraction: Option<f64>,
+    /// 事件、订单和成交写入的 SQLite 文件
+    #[arg(long, global = true, env = "STORE_PATH")]
+    store_path: Option<PathBuf>,
+    /// 回放录制文件代替 Geyser，只能和 --paper 一起使用
+    #[arg(long, global = true, env = "REPLAY_PATH")]
+    replay_path: Option<PathBuf>,
+    /// 回放速度：realtime（默认）、max 或倍速
+    #[arg(long, global = true, env = "REPLAY_SPEED")]
+    replay_speed: Option<String>,
+    /// 连接 Geyser 时同时录制到这个文件
+    #[arg(long, global = true, env = "RECORD_PATH")]
+    record_path: Option<PathBuf>,
+    /// Pyth 价格账户，逗号分隔，`v2:` 前缀为 `PriceUpdateV2`
+    #[arg(long, global = true, env = "PYTH_FEEDS", value_delimiter = ',')]
+    pyth_feeds: Vec<String>,
+    /// 价格发布时间距今的最大秒数
+    #[arg(long, global = true, env = "PRICE_MAX_AGE")]
+    price_max_age: Option<i64>,
+    /// 价格 conf / price 的最大比例
+    #[arg(long, global = true, env = "PRICE_MAX_CONF_RATIO")]
+    price_max_conf_ratio: Option<f64>,
+    /// 备用 HTTP 价格源
+    #[arg(long, global = true, env = "PRICE_FALLBACK_URL")]
+    price_fallback_url: Option<String>,
+    /// 备用价格源响应中价格的 JSON pointer
+    #[arg(long, global = true, env = "PRICE_FALLBACK_POINTER")]
+    price_fallback_pointer: Option<String>,
+    #[command(subcommand)]
+    command: Cmd,
 }
 
-// 用于存储最新价格的结构
-struct LatestPrice {
-    price: Option<f32>,
-    notify: Arc<Notify>, // 用于通知价格已更新
+#[derive(Subcommand)]
+enum Cmd {
+    /// 监听目标代币，利润达到要求时自动卖出，通过本机控制 API 或 --tui 面板的快捷键手动买卖
+    Snipe(SnipeArgs),
+    /// 按当前曲线卖出全部持仓并关闭 token 账户
+    Sell(SellArgs),
+    /// 花费指定 SOL 买入
+    Buy(BuyArgs),
+    /// 创建代币，可以在同一笔交易中买入
+    Create(CreateTokenArgs),
+    /// 打印 pump.fun 成交，指定 --mint 时只看一个代币
+    Watch(WatchArgs),
+    /// 按链上曲线状态报价，不发送交易
+    Quote(QuoteArgs),
+    /// 查询钱包池中每个钱包的 SOL 和代币余额
+    Balance(BalanceArgs),
+    /// 在钱包池内平均分配 SOL
+    Rebalance(RebalanceArgs),
+    /// 把 --pk 指定的私钥（或新生成的）加密保存为 keystore 文件
+    Keystore(KeystoreArgs),
+}
+
+#[derive(Args)]
+struct SnipeArgs {
+    #[arg(long, env = "TOKEN_MINT")]
+    mint: Option<Pubkey>,
+    /// 买入成本（lamports）
+    #[arg(long, env = "LAUNCH_COST")]
+    launch_cost: Option<u64>,
+    /// 扣除成本和 tip 后的最小利润（lamports）
+    #[arg(long, env = "MIN_PROFIT")]
+    min_profit: Option<u64>,
+    /// 不发送交易，按流中的曲线状态记录模拟成交
+    #[arg(long)]
+    paper: bool,
+    /// 发送前用 simulateTransaction 检查构造的交易，实盘时模拟失败的交易不发送
+    #[arg(long)]
+    simulate: bool,
+    /// paper 模式下交易从发出到落地的 slot 数
+    #[arg(long, default_value_t = 1)]
+    paper_delay_slots: u64,
+    /// paper 模式下钱包没有 token 时使用的模拟持仓
+    #[arg(long, requires = "paper")]
+    paper_tokens: Option<u64>,
+    /// 在终端显示面板，快捷键手动卖出。面板绘制在 stderr，stdout 的日志需要重定向到文件
+    #[arg(long)]
+    tui: bool,
+    /// 控制 API 监听地址，只能是本机地址
+    #[arg(long, env = "CONTROL_ADDR", default_value = "127.0.0.1:8787")]
+    control_addr: SocketAddr,
+    /// 控制 API 的 bearer token，没有设置时启动时随机生成并打印到 stderr，不进入日志；`--tui` 时必须设置
+    #[arg(long, env = "CONTROL_TOKEN", hide_env_values = true)]
+    control_token: Option<String>,
+    /// Prometheus 指标地址，设置后提供 `GET /metrics`
+    #[arg(long, env = "METRICS_ADDR")]
+    metrics_addr: Option<SocketAddr>,
+}
+
+#[derive(Args)]
+struct SellArgs {
+    #[arg(long, env = "TOKEN_MINT")]
+    mint: Option<Pubkey>,
+    /// 滑点（bps）
+    #[arg(long, default_value = "1500", value_parser = parse_slippage_bps)]
+    slippage_bps: f64,
+}
+
+#[derive(Args)]
+struct BuyArgs {
+    #[arg(long, env = "TOKEN_MINT")]
+    mint: Option<Pubkey>,
+    /// 花费的 SOL（含 1% 手续费），配置了钱包池时拆分到多个钱包
+    #[arg(long, value_parser = parse_sol)]
+    sol: u64,
+    /// 每个钱包最多花费的 SOL
+    #[arg(long, value_parser = parse_sol)]
+    max_per_wallet: Option<u64>,
+    /// 滑点（bps）
+    #[arg(long, default_value = "1500", value_parser = parse_slippage_bps)]
+    slippage_bps: f64,
+}
+
+#[derive(Args)]
+struct CreateTokenArgs {
+    #[arg(long)]
+    name: String,
+    #[arg(long)]
+    symbol: String,
+    /// metadata JSON 地址
+    #[arg(long)]
+    uri: String,
+    /// 创建后立即买入的 SOL
+    #[arg(long, value_parser = parse_sol)]
+    buy_sol: Option<u64>,
+    /// 滑点（bps）
+    #[arg(long, default_value = "1500", value_parser = parse_slippage_bps)]
+    slippage_bps: f64,
+}
+
+#[derive(Args)]
+struct WatchArgs {
+    /// 只打印这个代币的事件
+    #[arg(long)]
+    mint: Option<Pubkey>,
+}
+
+#[derive(Args)]
+struct QuoteArgs {
+    #[arg(long, env = "TOKEN_MINT")]
+    mint: Option<Pubkey>,
+    /// 报价买入花费的 SOL
+    #[arg(long, value_parser = parse_sol)]
+    buy_sol: Option<u64>,
+    /// 报价卖出的 token 数量
+    #[arg(long)]
+    sell_tokens: Option<u64>,
+}
+
+#[derive(Args)]
+struct BalanceArgs {
+    #[arg(long, env = "TOKEN_MINT")]
+    mint: Option<Pubkey>,
+    /// 查询其他钱包，不需要私钥
+    #[arg(long)]
+    wallet: Option<Pubkey>,
+}
+
+#[derive(Args)]
+struct RebalanceArgs {
+    /// 少于这个数量（SOL）的差额不转账
+    #[arg(long, default_value = "0.01", value_parser = parse_sol)]
+    min_sol: u64,
+    /// 只打印转账计划
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args)]
+struct KeystoreArgs {
+    /// 输出文件，已存在时报错
+    #[arg(long)]
+    out: PathBuf,
+    /// 生成新的 keypair
+    #[arg(long)]
+    new: bool,
+    /// scrypt 参数 log2(N)
+    #[arg(long, default_value_t = DEFAULT_LOG_N)]
+    log_n: u8,
+}
+
+/// 合并命令行、环境变量和配置文件后的全局配置，必填项在用到时才检查
+struct Settings {
+    config: Config,
+    /// 配置文件和 profile，用于热加载
+    source: Option<(PathBuf, Option<String>)>,
+    rpc_url: Option<String>,
+    grpc_url: Option<String>,
+    pk: Option<String>,
+    jito: Option<String>,
+    signer_socket: Option<PathBuf>,
+    wallets: Vec<String>,
+    /// 命令行或环境变量指定的 tip，优先于配置文件
+    tip: Option<u64>,
+    /// 命令行或环境变量指定的 tip 比例，优先于配置文件
+    tip_max_profit_fraction: Option<f64>,
+    tip_floor_url: Option<String>,
+    store_path: Option<PathBuf>,
+    log: LogSettings,
+    send: SendSettings,
+    price: PriceSettings,
+    stream: StreamSettings,
+}
+
+impl Settings {
+    fn new(cli: &Cli) -> Result<Self> {
+        let (config, source) = match &cli.config {
+            Some(path) => (
+                Config::load(path, cli.profile.as_deref())?,
+                Some((path.clone(), cli.profile.clone())),
+            ),
+            None if cli.profile.is_some() => bail!("--profile needs a config file: pass --config or set PUMP_CONFIG"),
+            None => (Config::default(), None),
+        };
+        if let Some(fraction) = cli.tip_max_profit_fraction {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                bail!("--tip-max-profit-fraction must be in (0, 1], got {}", fraction);
+            }
+        }
+        Ok(Self {
+            rpc_url: cli.rpc_url.clone().or_else(|| config.rpc_url.clone()),
+            grpc_url: cli.grpc_url.clone().or_else(|| config.grpc_url.clone()),
+            pk: cli.pk.clone().or_else(|| config.pk.clone()),
+            jito: cli.jito.clone().or_else(|| config.jito.clone()),
+            signer_socket: cli.signer_socket.clone().or_else(|| config.signer_socket.clone()),
+            wallets: match cli.wallets.is_empty() {
+                true => config.wallets.clone(),
+                false => cli.wallets.clone(),
+            },
+            tip: cli.tip,
+            tip_max_profit_fraction: cli.tip_max_profit_fraction,
+            tip_floor_url: cli.tip_floor_url.clone().or_else(|| config.tip_floor_url.clone()),
+            store_path: cli.store_path.clone().or_else(|| config.store_path.clone()),
+            log: LogSettings {
+                level: cli.log_level.clone().unwrap_or_else(|| config.log.level.clone()),
+                format: cli.log_format.unwrap_or(config.log.format),
+            },
+            send: SendSettings {
+                rpc_urls: match cli.send_rpc_urls.is_empty() {
+                    true => config.send.rpc_urls.clone(),
+                    false => cli.send_rpc_urls.clone(),
+                },
+                ws_url: cli.ws_url.clone().or_else(|| config.send.ws_url.clone()),
+                jito_selection: cli.jito_selection.or(config.send.jito_selection),
+            },
+            price: PriceSettings {
+                feeds: match cli.pyth_feeds.is_empty() {
+                    true => config.price.feeds.clone(),
+                    false => cli.pyth_feeds.clone(),
+                },
+                max_age: cli.price_max_age.or(config.price.max_age),
+                max_conf_ratio: cli.price_max_conf_ratio.or(config.price.max_conf_ratio),
+                fallback_url: cli.price_fallback_url.clone().or_else(|| config.price.fallback_url.clone()),
+                fallback_pointer: cli.price_fallback_pointer.clone().or_else(|| config.price.fallback_pointer.clone()),
+            },
+            stream: StreamSettings {
+                replay_path: cli.replay_path.clone().or_else(|| config.stream.replay_path.clone()),
+                replay_speed: cli.replay_speed.clone().or_else(|| config.stream.replay_speed.clone()),
+                record_path: cli.record_path.clone().or_else(|| config.stream.record_path.clone()),
+            },
+            config,
+            source,
+        })
+    }
+
+    fn rpc_url(&self) -> Result<String> {
+        require(self.rpc_url.clone(), "rpc-url", "RPC_URL", "rpc_url")
+    }
+
+    fn grpc_url(&self) -> Result<String> {
+        require(self.grpc_url.clone(), "grpc-url", "GRPC_URL", "grpc_url")
+    }
+
+    fn keypair(&self) -> Result<Keypair> {
+        parse_keypair(require(self.pk.as_deref(), "pk", "PK", "pk")?)
+    }
+
+    /// 配置了签名进程时远程签名，否则使用本地私钥
+    fn signer(&self) -> Result<Arc<dyn Signer + Send + Sync>> {
+        match &self.signer_socket {
+            Some(path) => Ok(Arc::new(RemoteSigner::connect(path)?)),
+            None => Ok(Arc::new(self.keypair()?)),
+        }
+    }
+
+    /// 配置了 --wallets 时使用钱包池，否则只有 --pk 或签名进程的一个钱包
+    fn wallet_pool(&self) -> Result<WalletPool> {
+        if self.wallets.is_empty() {
+            return WalletPool::new(vec![self.signer()?]);
+        }
+        let signers = self
+            .wallets
+            .iter()
+            .map(|pk| Ok(Arc::new(parse_keypair(pk)?) as Arc<dyn Signer + Send + Sync>))
+            .collect::<Result<_>>()?;
+        WalletPool::new(signers)
+    }
+
+    /// 命令行或环境变量没有给出时使用配置文件中的 mint
+    fn mint(&self, mint: Option<Pubkey>) -> Result<Option<Pubkey>> {
+        match (mint, &self.config.mint) {
+            (Some(mint), _) => Ok(Some(mint)),
+            (None, Some(mint)) => parse_mint(mint).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn require_mint(&self, mint: Option<Pubkey>) -> Result<Pubkey> {
+        require(self.mint(mint)?, "mint", "TOKEN_MINT", "mint")
+    }
+
+    fn tip(&self, config: &Config) -> u64 {
+        self.tip.unwrap_or(config.fees.tip)
+    }
+
+    fn tip_max_profit_fraction(&self, config: &Config) -> Option<f64> {
+        self.tip_max_profit_fraction.or(config.fees.tip_max_profit_fraction)
+    }
+
+    /// tip 大于 0 时必须配置 Jito
+    fn jito_pool(&self) -> Result<Option<Arc<JitoPool>>> {
+        match self.tip(&self.config) {
+            0 => Ok(None),
+            tip => {
+                let urls = require(self.jito.as_deref(), "jito", "JITO", "jito")
+                    .map_err(|e| anyhow!("tip is {} lamports: {}", tip, e))?;
+                let selection = self.send.jito_selection.unwrap_or_default();
+                Ok(Some(Arc::new(JitoPool::from_env_list(urls, selection))))
+            }
+        }
+    }
+
+    /// 风控使用配置文件中的 `[risk]`
+    fn risk_guard(&self) -> Arc<RiskGuard> {
+        RiskGuard::new(self.config.risk.clone())
+    }
+
+    /// 配置文件修改后推送新的配置，没有配置文件时不会变化
+    fn watch_config(&self) -> watch::Receiver<Config> {
+        match &self.source {
+            Some((path, profile)) => watch_config(
+                path.clone(),
+                profile.clone(),
+                self.config.clone(),
+                Duration::from_secs(2),
+            ),
+            None => watch::channel(self.config.clone()).1,
+        }
+    }
+}
+
+/// snipe 的阈值：命令行参数和环境变量优先，其次是配置文件（可以热加载）
+#[derive(Debug)]
+struct Thresholds {
+    launch_cost: u64,
+    /// 成本和最小利润之和，tip 已在利润中扣除，不再计入
+    min_profit: u64,
+    tip: u64,
+    tip_policy: TipPolicy,
+    slippage_bps: f64,
+    exit_slippage_bps: f64,
+    budget: ComputeBudget,
+}
+
+impl Thresholds {
+    fn new(settings: &Settings, args: &SnipeArgs, config: &Config) -> Result<Self> {
+        let strategy = &config.strategy;
+        let launch_cost = require(
+            args.launch_cost.or(strategy.launch_cost),
+            "launch-cost",
+            "LAUNCH_COST",
+            "strategy.launch_cost",
+        )?;
+        let min_profit = require(
+            args.min_profit.or(strategy.min_profit),
+            "min-profit",
+            "MIN_PROFIT",
+            "strategy.min_profit",
+        )?;
+        let tip = settings.tip(config);
+
+        // 配置了 tip floor 时按 tip floor 动态调整 tip，tip 作为最小值
+        let mut tip_policy = TipPolicy { min_tip: tip, ..Default::default() };
+        if let Some(fraction) = settings.tip_max_profit_fraction(config) {
+            tip_policy.max_profit_fraction = fraction;
+        }
+        Ok(Self {
+            launch_cost,
+            min_profit: launch_cost
+                .checked_add(min_profit)
+                .ok_or_else(|| anyhow!("launch cost {} + min profit {} overflows", launch_cost, min_profit))?,
+            tip,
+            tip_policy,
+            slippage_bps: strategy.slippage_bps,
+            exit_slippage_bps: strategy.exit_slippage_bps,
+            budget: config.fees.compute_budget(),
+        })
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
-    let target_key: Pubkey = env::var("TOKEN_MINT")?.parse()?;
-    println!("target_key: {}", target_key); 
+    let cli = Cli::parse();
+    let settings = Settings::new(&cli)?;
+    settings.log.init()?;
+    match cli.command {
+        Cmd::Snipe(args) => snipe(&settings, args).await,
+        Cmd::Sell(args) => sell(&settings, args).await,
+        Cmd::Buy(args) => buy(&settings, args).await,
+        Cmd::Create(args) => create(&settings, args).await,
+        Cmd::Watch(args) => watch(&settings, args).await,
+        Cmd::Quote(args) => quote(&settings, args).await,
+        Cmd::Balance(args) => balance(&settings, args).await,
+        Cmd::Rebalance(args) => rebalance(&settings, args).await,
+        Cmd::Keystore(args) => keystore(&settings, args),
+    }
+}
+
+async fn snipe(settings: &Settings, args: SnipeArgs) -> Result<()> {
+    let target_key = settings.require_mint(args.mint)?;
+    info!(mint = %target_key, "狙击目标");
     let bd = find_bonding_curve(&target_key);
-    let keypair = Keypair::from_base58_string(&env::var("PK")?);
+    let rpc_url = settings.rpc_url()?;
+    let rpc = RpcClient::new(rpc_url.clone());
+    let mut pool = settings.wallet_pool()?;
+    pool.refresh(&rpc, &[target_key]).await?;
+    for wallet in pool.wallets() {
+        info!(wallet = %wallet.pubkey(), sol = wallet.sol, tokens = wallet.position(&target_key), "钱包余额");
+    }
+    // paper 模式使用第一个钱包
+    let wallet = pool.primary().pubkey();
+    let paper_signer = pool.primary().signer.clone();
+    let balance = pool.total_position(&target_key);
+    info!(balance, "token 持仓");
+    let balance = match args.paper_tokens {
+        Some(tokens) if args.paper && balance == 0 => tokens,
+        _ => balance,
+    };
 
-    let wallet = keypair.pubkey();
-    // println!("wallet: {}", wallet);
-    let rpc = RpcClient::new(env::var("RPC_URL")?);
-    let balance = get_balance(&rpc, &wallet, &target_key).await?;
-    println!("token balance: {}", balance);
+    let mut config = settings.watch_config();
+    let mut thresholds = Thresholds::new(settings, &args, &config.borrow_and_update())?;
+    info!(?thresholds, "阈值");
 
-    let launch_cost = env::var("LAUNCH_COST")?.parse::<u64>()?;
-    println!("launch_cost: {}", launch_cost);
-    let min_profit = env::var("MIN_PROFIT")?.parse::<u64>()?;
+    // kill switch 文件出现或收到 SIGUSR1 时清仓退出
+    let guard = settings.risk_guard();
+    let mut killed = guard.spawn_kill_switch(Duration::from_secs(1));
+    if let Some(reason) = guard.killed() {
+        error!(%reason, "kill switch 已触发");
+        killed.mark_changed();
+    }
+
+    let jito = settings.jito_pool()?;
+    let jito_enabled = jito.is_some();
+    // 启动时拉取 tip 账户并定时刷新，失败时使用内置列表
+    let tip_accounts = match &jito {
+        Some(pool) => {
+            let tip_accounts = TipAccounts::fetch(pool).await;
+            tip_accounts.spawn_refresh(pool.clone(), Duration::from_secs(300));
+            tip_accounts
+        }
+        None => TipAccounts::default(),
+    };
+    let submitter = Arc::new(build_submitter(&rpc_url, jito, &settings.send).await?);
 
-    let jito = JitoJsonRpcSDK::new(&env::var("JITO")?, None);
-    let tip = env::var("TIP")?.parse::<u64>()?;
+    info!(paths = ?submitter.paths().collect::<Vec<_>>(), "发送通道");
 
-    let min_profit = launch_cost + min_profit + tip;
+    let tip_floor = match &settings.tip_floor_url {
+        Some(url) if jito_enabled => Some(TipFloorCache::spawn_refresh(
+            TipFloorSource::from_url(url),
+            Duration::from_secs(10),
+        )),
+        _ => None,
+    };
 
     if balance == 0 {
-        println!("wallet balance = 0, return");
-        return Ok(());
+        info!("token 持仓为 0，等待通过控制 API 买入");
     }
 
-    println!(" ========================================= Pump 狙击手 ========================================= ");
-    let wallet_balance = rpc.get_balance(&wallet).await? as f64;
-    println!("阻击手 原始资金: {} SOL", wallet_balance / 1000000000.0);
-    let sol_price_usd = get_sol_price(&rpc).await?;
-    println!("当前 SOL 价格: {}", sol_price_usd);
+    let wallet_balance = pool.total_sol() as f64;
+    // 通过 Geyser 订阅 Pyth 账户持续更新 SOL/USD 价格
+    let shared_rpc = Arc::new(RpcClient::new(rpc_url));
+    let sol_usd = SolUsdTracker::new(build_price_oracle(&settings.price)?)
+        .spawn(shared_rpc.clone(), GrpcClient::new(settings.grpc_url()?))
+        .await?;
+    let sol_price_usd = sol_usd.price().unwrap_or_default();
     let wallet_balance_usd = wallet_balance * sol_price_usd / 1000000000.0;
-    println!("阻击手 原始资金: {} USDT", wallet_balance_usd);
-    
-    // 创建命令通道
-    let (tx, mut rx) = mpsc::channel::<Command>(100);
-    
-    // 创建通知器
-    let notify = Arc::new(Notify::new());
+    info!(sol = wallet_balance / 1e9, sol_usd = sol_price_usd, usd = wallet_balance_usd, "狙击手原始资金");
 
-    let pumpfun_reserve = get_pumpfun_reserve(&rpc, target_key).await.expect("Failed to get pumpfun reserve");
+    // 配置了 store_path 时把事件、订单和成交写入 SQLite
+    let store = match &settings.store_path {
+        Some(path) => Some(Store::open(path)?),
+        None => None,
+    };
 
-    let init_price = cal_pumpfun_price(pumpfun_reserve.virtual_sol_reserves, pumpfun_reserve.real_token_reserves);
-    
-    // 创建共享的最新价格
-    let latest_price = Arc::new(Mutex::new(LatestPrice { 
-        price: Some(init_price),
-        notify: notify.clone(),
-    }));
-    let latest_price_clone = latest_price.clone();
-    
-    // 启动用户输入监听线程
-    tokio::spawn(async move {
-        println!("按 'q' 并回车执行清仓卖出操作然后退出程序...");
-        
-        let mut input = String::new();
-
-        loop {
-            input.clear();
-            if io::stdin().read_line(&mut input).is_ok() {
-                if input.trim() == "q" {
-                    println!("收到清仓卖出命令，准备执行...");
-                    
-                    // 获取最新价格，如果没有则等待
-                    let price_data = latest_price_clone.lock().await;
-                    let current_price = price_data.price.unwrap();  
-                    println!("获取到价格: {}, 执行清仓卖出...", current_price);
-                    tx.send(Command::Sell(current_price)).await.ok();
-                }
-            }
+    // 通过 gRPC 流确认自己的交易，流中没出现的用 getSignatureStatuses 兜底
+    let tracker = ConfirmationTracker::new();
+    tracker.spawn_rpc_fallback(
+        shared_rpc.clone(),
+        Duration::from_secs(2),
+        Duration::from_secs(90),
+        Duration::from_secs(1),
+    );
+    // 本机控制 API，没有配置 token 时随机生成。面板启动后会覆盖 stderr，看不到生成的 token
+    let control_token = match args.control_token.clone() {
+        Some(token) => token,
+        None if args.tui => bail!("--tui requires --control-token: a generated token would be hidden behind the dashboard"),
+        None => {
+            let token = bs58::encode(rand::random::<[u8; 32]>()).into_string();
+            eprintln!("控制 API token: {}", token);
+            token
         }
-    });
+    };
+    // 控制 API 和终端面板的快捷键都通过这个通道发送命令
+    let (control_tx, mut control_rx) = mpsc::channel::<ControlRequest>(100);
+    // 面板在函数返回时恢复终端
+    let (dashboard, _dashboard_thread) = match args.tui {
+        true => {
+            let (handle, thread) = spawn_dashboard(&[target_key], control_tx.clone())?;
+            (Some(handle), Some(thread))
+        }
+        false => (None, None),
+    };
+    let landing = Landing {
+        tracker: tracker.clone(),
+        submitter: submitter.clone(),
+        ledger: Arc::new(StdMutex::new(PnlLedger::new())),
+        rpc: shared_rpc,
+        tip_accounts: tip_accounts.clone(),
+        sol_usd: sol_usd.clone(),
+        store: store.clone(),
+        wallets: Arc::new(StdMutex::new(pool)),
+        guard: guard.clone(),
+        dashboard,
+        latency: Arc::new(StdMutex::new(LatencyTracker::new())),
+        pending_sells: Arc::new(StdMutex::new(HashMap::new())),
+    };
     
-    // 主循环处理 pump 监听和手动卖出命令
-    let mut stream = get_pumpfun_stream().await?;
+    let pumpfun_reserve = get_pumpfun_reserve(&rpc, target_key)
+        .await
+        .ok_or_else(|| anyhow!("{} has no pump.fun bonding curve", target_key))?;
+
+    let mut latest_price = cal_pumpfun_price(pumpfun_reserve.virtual_sol_reserves, pumpfun_reserve.real_token_reserves);
+
+    // paper 模式不发送交易，用流中的曲线状态模拟成交
+    let mut paper = if args.paper {
+        let mut paper = PaperTrader::new(wallet, args.paper_delay_slots);
+        paper.set_curve(target_key, Curve::from(&pumpfun_reserve));
+        paper.set_position(target_key, balance);
+        info!(balance, "paper 模式，模拟持仓");
+        Some(paper)
+    } else {
+        None
+    };
+    let mut last_slot = 0;
+    // 暂停时不自动卖出，控制 API 的命令照常执行
+    let mut paused = false;
+    // 退出时发出的卖出，全部确认或过期后退出；期间继续处理交易流，但不再自动卖出
+    let mut exit_orders: Option<BoxFuture<'static, ()>> = None;
+
+    let kill_tx = control_tx.clone();
+    let control_addr = spawn_control_api(args.control_addr, control_token, control_tx).await?;
+    info!(addr = %control_addr, "控制 API 已启动");
+    if let Some(addr) = args.metrics_addr {
+        let metrics_addr = spawn_metrics_server(addr).await?;
+        info!(addr = %metrics_addr, "指标已启动");
+    }
+
+    // 主循环处理 pump 监听和控制命令
+    let mut stream = open_stream(settings, args.paper).await?;
+    // Ctrl-C 时正常返回，交易流释放时录制文件刷盘
+    let mut ctrl_c = std::pin::pin!(tokio::signal::ctrl_c());
     
     loop {
         tokio::select! {
-            // 处理用户命令
-            Some(cmd) = rx.recv() => {
-                match cmd {
-                    Command::Sell(price) => {
-                        println!("执行手动清仓卖出，价格: {}...", price);
-                        
-                        let blockhash = rpc.get_latest_blockhash().await?;
-                        
-                        // 创建卖出交易，不进行利润检查
-                        let tx1 = create_sell_transaction(
-                            &bd,
-                            price,
-                            &target_key,
-                            &keypair,
-                            balance,
-                            5000.0,
-                            blockhash,
-                        )?;
-                        
-                        // 执行卖出交易，不考虑利润
-                        if tip > 0 {
-                            let tx2 = transfer_tx(
-                                &wallet,
-                                &get_tip_account()?,
-                                &keypair,
-                                tip,
-                                blockhash,
-                            );
-                            let bundle_id = send_bundle(&jito, vec![tx1, tx2]).await?;
-                            println!("手动卖出 bundle id {:?}", bundle_id);
-                        } else {
-                            println!("没有配置 tip，只执行卖出交易");
-                            let sig = rpc.send_and_confirm_transaction(&tx1).await?;
-                            println!("手动卖出 sig {:?}", sig);
+            // 配置文件修改后更新阈值，无效时继续使用旧的
+            Ok(()) = config.changed() => {
+                let updated_config = config.borrow_and_update().clone();
+                guard.set_limits(updated_config.risk.clone());
+                match Thresholds::new(settings, &args, &updated_config) {
+                    Ok(updated) if updated.tip > 0 && !jito_enabled => {
+                        warn!(tip = updated.tip, "启动时没有启用 Jito，忽略新的 tip 设置");
+                    }
+                    Ok(updated) => {
+                        info!(thresholds = ?updated, "阈值已更新");
+                        thresholds = updated;
+                    }
+                    Err(e) => warn!("新配置无效，继续使用旧阈值: {:?}", e),
+                }
+            },
+
+            // kill switch 触发后不再等待利润，按最新价格清仓退出
+            Ok(()) = killed.changed() => {
+                if *killed.borrow_and_update() {
+                    error!(price = latest_price, "kill switch 触发，清仓卖出");
+                    let (request, _) = ControlRequest::new(ControlCommand::Sell { percent: 100.0, exit: true });
+                    kill_tx.send(request).await.ok();
+                }
+            },
+
+            // 处理控制 API 的命令
+            Some(request) = control_rx.recv() => {
+                let mut exit = false;
+                let span = info_span!("control", command = ?request.command);
+                let reply: Result<serde_json::Value> = async {
+                    match &request.command {
+                        ControlCommand::Status => {
+                            let pool = landing.wallets.lock().unwrap();
+                            let positions = pool
+                                .wallets()
+                                .iter()
+                                .map(|w| PositionStatus {
+                                    wallet: w.pubkey().to_string(),
+                                    sol: w.sol,
+                                    tokens: match &paper {
+                                        Some(paper) if w.pubkey() == wallet => paper.position(&target_key),
+                                        _ => w.position(&target_key),
+                                    },
+                                })
+                                .collect();
+                            let status = Status {
+                                mint: target_key.to_string(),
+                                price: latest_price,
+                                paused,
+                                kill_switch: guard.killed(),
+                                positions,
+                                pnl: PnlStatus::new(landing.ledger.lock().unwrap().session(), guard.daily_pnl()),
+                                latency: landing.latency.lock().unwrap().report(),
+                                strategy: json!({
+                                    "launch_cost": thresholds.launch_cost,
+                                    "min_profit": thresholds.min_profit - thresholds.launch_cost,
+                                    "tip": thresholds.tip,
+                                    "slippage_bps": thresholds.slippage_bps,
+                                    "exit_slippage_bps": thresholds.exit_slippage_bps,
+                                }),
+                            };
+                            anyhow::Ok(serde_json::to_value(status)?)
+                        }
+                        ControlCommand::Sell { percent, exit: then_exit } => {
+                            let price = latest_price;
+                            info!(percent, price, "手动卖出");
+                            let blockhash = rpc.get_latest_blockhash().await?;
+                            // 手动卖出不考虑利润，使用退出滑点
+                            let tip = thresholds.tip;
+                            if let Some(paper) = paper.as_mut() {
+                                let position = paper.position(&target_key);
+                                let amount = portion(position, *percent);
+                                if amount == 0 {
+                                    bail!("no {} position to sell", target_key);
+                                }
+                                let params = SellParams {
+                                    bonding_curve: &bd,
+                                    mint: &target_key,
+                                    price,
+                                    amount_in_token: amount,
+                                    slippage_bps: thresholds.exit_slippage_bps,
+                                    budget: &thresholds.budget,
+                                };
+                                let tx1 = match amount == position {
+                                    true => create_sell_transaction(&params, paper_signer.as_ref(), blockhash)?,
+                                    false => create_partial_sell_transaction(&params, paper_signer.as_ref(), blockhash)?,
+                                };
+                                let order = PaperOrder {
+                                    signature: tx1.signatures[0],
+                                    mint: target_key,
+                                    side: PaperSide::Sell,
+                                    amount,
+                                    min_out: min_sol_out(price, amount, thresholds.exit_slippage_bps),
+                                    tip,
+                                    signatures: tx1.signatures.len() + usize::from(tip > 0),
+                                    priority_fee: thresholds.budget.priority_fee(),
+                                    land_slot: last_slot + paper.delay_slots(),
+                                };
+                                landing.sent(tx1.signatures[0], wallet, "sell");
+                                submit_paper(paper, &rpc, args.simulate, &tx1, order).await;
+                                // 退出前不再等待落地 slot，直接结算
+                                if *then_exit {
+                                    for fill in paper.settle_all() {
+                                        landing.record(&wallet, fill.with_sol_usd(sol_usd.price()));
+                                    }
+                                    exit = true;
+                                }
+                                return Ok(json!({ "sells": [{ "wallet": wallet.to_string(), "tokens": amount, "signature": tx1.signatures[0].to_string() }] }));
+                            }
+
+                            // 每个持有 token 的钱包各卖一笔
+                            let plan = SellPlan {
+                                mint: &target_key,
+                                price,
+                                percent: *percent,
+                                slippage_bps: thresholds.exit_slippage_bps,
+                                tip: (tip > 0).then_some((tip, &tip_accounts)),
+                                budget: &thresholds.budget,
+                            };
+                            let holders = landing.wallets.lock().unwrap().holder_signers(&target_key);
+                            let sells = sell_txs(holders, &plan, blockhash)?;
+                            if sells.is_empty() {
+                                bail!("no {} position to sell", target_key);
+                            }
+                            let mut sent = Vec::new();
+                            let mut watched = Vec::new();
+                            let mut rejected = 0;
+                            for sell in &sells {
+                                if let Err(e) = sell.check(&guard, price) {
+                                    warn!(wallet = %sell.wallet, "风控拒绝卖出: {}", e);
+                                    rejected += 1;
+                                    continue;
+                                }
+                                if args.simulate && !simulate(&rpc, &sell.tx).await {
+                                    warn!(wallet = %sell.wallet, "模拟失败，不发送卖出");
+                                    rejected += 1;
+                                    continue;
+                                }
+                                let signature = sell.tx.signatures[0];
+                                watched.push(landing.watch(&sell.tx, sell.wallet, target_key, "sell"));
+                                if let Some(tip_tx) = &sell.tip_tx {
+                                    landing.watch(tip_tx, sell.wallet, target_key, "tip");
+                                }
+                                let report = submitter.submit(&sell.tx, sell.tip_tx.as_ref()).await?;
+                                info!(order = %signature, wallet = %sell.wallet, tokens = sell.amount, ?report, "手动卖出已发送");
+                                if let Some(store) = &store {
+                                    store.record(StoreRecord::Order(OrderRecord {
+                                        signature: report.signature.to_string(),
+                                        mint: target_key,
+                                        side: "sell".to_string(),
+                                        token_amount: sell.amount,
+                                        price,
+                                        slippage_bps: thresholds.exit_slippage_bps,
+                                        tip,
+                                        created_at: unix_now(),
+                                    }));
+                                }
+                                sent.push(json!({ "wallet": sell.wallet.to_string(), "tokens": sell.amount, "signature": signature.to_string() }));
+                            }
+                            if *then_exit {
+                                // 有卖出被风控或模拟拒绝时仍有持仓，不退出
+                                if rejected > 0 {
+                                    bail!("{} of {} exit sells were rejected by the risk guard or simulation, not exiting; sent {:?}", rejected, sells.len(), sent);
+                                }
+                                // 卖出在后台确认和记账，全部结束后主循环退出
+                                info!(orders = watched.len(), "等待退出卖出确认");
+                                exit_orders = Some(join_all(watched).map(|_| ()).boxed());
+                            }
+                            Ok(json!({ "sells": sent }))
+                        }
+                        ControlCommand::Buy { lamports } => {
+                            let blockhash = rpc.get_latest_blockhash().await?;
+                            let slippage_bps = thresholds.slippage_bps;
+                            let tip = thresholds.tip;
+                            if let Some(paper) = paper.as_mut() {
+                                let curve = *paper
+                                    .curve(&target_key)
+                                    .ok_or_else(|| anyhow!("paper: no curve state for {}", target_key))?;
+                                let quote = curve.buy_with_sol(*lamports);
+                                if quote.tokens == 0 {
+                                    bail!("{} lamports is too small to buy any {}", lamports, target_key);
+                                }
+                                let tx1 = create_buy_transaction(&bd, &target_key, paper_signer.as_ref(), quote.tokens, max_sol_cost(quote.sol_in(), slippage_bps), &thresholds.budget, blockhash)?;
+                                let order = PaperOrder {
+                                    signature: tx1.signatures[0],
+                                    mint: target_key,
+                                    side: PaperSide::Buy,
+                                    amount: *lamports,
+                                    min_out: (quote.tokens as f64 * (10000.0 - slippage_bps) / 10000.0) as u64,
+                                    tip,
+                                    signatures: tx1.signatures.len() + usize::from(tip > 0),
+                                    priority_fee: thresholds.budget.priority_fee(),
+                                    land_slot: last_slot + paper.delay_slots(),
+                                };
+                                landing.sent(tx1.signatures[0], wallet, "buy");
+                                submit_paper(paper, &rpc, args.simulate, &tx1, order).await;
+                                return Ok(json!({ "buys": [{ "wallet": wallet.to_string(), "tokens": quote.tokens, "signature": tx1.signatures[0].to_string() }] }));
+                            }
+
+                            // 拆分到钱包池，后面的钱包按前面买入后的曲线报价
+                            let mut curve = fetch_curve(&rpc, &target_key).await?;
+                            let parts = landing.wallets.lock().unwrap().split_buy(*lamports, None, slippage_bps, buy_fees(thresholds.budget.priority_fee(), tip))?;
+                            let mut sent = Vec::new();
+                            for (buyer, sol) in parts {
+                                let quote = curve.buy_with_sol(sol);
+                                if quote.tokens == 0 {
+                                    bail!("{} lamports is too small to buy any {}", sol, target_key);
+                                }
+                                let price = curve.price();
+                                curve.apply_buy(&quote);
+                                let signer = landing.wallets.lock().unwrap().get(&buyer).unwrap().signer.clone();
+                                let tx1 = create_buy_transaction(&bd, &target_key, signer.as_ref(), quote.tokens, max_sol_cost(quote.sol_in(), slippage_bps), &thresholds.budget, blockhash)?;
+                                let tip_tx = match tip {
+                                    0 => None,
+                                    tip => Some(transfer_tx(&tip_accounts.random()?, signer.as_ref(), tip, blockhash)?),
+                                };
+                                guard.check(&tx1, price)?;
+                                if let Some(tip_tx) = &tip_tx {
+                                    guard.check(tip_tx, price)?;
+                                }
+                                if args.simulate && !simulate(&rpc, &tx1).await {
+                                    bail!("simulation of the buy from {} failed, not sending; sent {:?}", buyer, sent);
+                                }
+                                if let Some(tip_tx) = &tip_tx {
+                                    landing.watch(tip_tx, buyer, target_key, "tip");
+                                }
+                                landing.watch(&tx1, buyer, target_key, "buy");
+                                let report = submitter.submit(&tx1, tip_tx.as_ref()).await?;
+                                info!(order = %tx1.signatures[0], wallet = %buyer, tokens = quote.tokens, ?report, "手动买入已发送");
+                                if let Some(store) = &store {
+                                    store.record(StoreRecord::Order(OrderRecord {
+                                        signature: report.signature.to_string(),
+                                        mint: target_key,
+                                        side: "buy".to_string(),
+                                        token_amount: quote.tokens,
+                                        price,
+                                        slippage_bps,
+                                        tip,
+                                        created_at: unix_now(),
+                                    }));
+                                }
+                                sent.push(json!({ "wallet": buyer.to_string(), "tokens": quote.tokens, "signature": tx1.signatures[0].to_string() }));
+                            }
+                            Ok(json!({ "buys": sent }))
+                        }
+                        ControlCommand::Pause => {
+                            paused = true;
+                            landing.show(DashboardEvent::Paused(paused));
+                            info!("自动卖出已暂停");
+                            Ok(json!({ "paused": paused }))
+                        }
+                        ControlCommand::Resume => {
+                            paused = false;
+                            landing.show(DashboardEvent::Paused(paused));
+                            info!("自动卖出已恢复");
+                            Ok(json!({ "paused": paused }))
+                        }
+                        // 配置文件重新加载时会覆盖这里的修改
+                        ControlCommand::UpdateStrategy(update) => {
+                            let max_slippage_bps = guard.limits().max_slippage_bps;
+                            for bps in [update.slippage_bps, update.exit_slippage_bps].into_iter().flatten() {
+                                if !(0.0..10000.0).contains(&bps) || bps > max_slippage_bps {
+                                    bail!("slippage {} bps must be in [0, {}]", bps, max_slippage_bps.min(9999.0));
+                                }
+                            }
+                            let launch_cost = update.launch_cost.unwrap_or(thresholds.launch_cost);
+                            let min_profit = update.min_profit.unwrap_or(thresholds.min_profit - thresholds.launch_cost);
+                            let Some(total) = launch_cost.checked_add(min_profit) else {
+                                bail!("launch_cost {} + min_profit {} overflows", launch_cost, min_profit);
+                            };
+                            thresholds.launch_cost = launch_cost;
+                            thresholds.min_profit = total;
+                            thresholds.slippage_bps = update.slippage_bps.unwrap_or(thresholds.slippage_bps);
+                            thresholds.exit_slippage_bps = update.exit_slippage_bps.unwrap_or(thresholds.exit_slippage_bps);
+                            info!(?thresholds, "阈值已更新");
+                            Ok(json!({}))
+                        }
+                        ControlCommand::Shutdown => {
+                            exit = true;
+                            Ok(json!({}))
                         }
-                        println!("手动清仓卖出完成！");
-                        println!("退出程序...");
-                        // 执行完毕后退出程序
-                        std::process::exit(0);
                     }
                 }
+                .instrument(span)
+                .await;
+                if let Err(e) = &reply {
+                    warn!(command = ?request.command, "控制命令失败: {:?}", e);
+                }
+                request.respond(reply.map_err(|e| format!("{:#}", e)));
+                if exit {
+                    // 等待控制 API 发出回复
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    return landing.finish().await;
+                }
+            },
+
+            // 退出卖出全部确认或过期
+            _ = async { exit_orders.as_mut().unwrap().await }, if exit_orders.is_some() => {
+                return landing.finish().await;
             },
             
             // 处理 pump 监听流
             Some(data) = stream.next() => {
+                let received = Instant::now();
                 match data {
                     Ok(update) => {
-                        if let Some(update_oneof) = update.update_oneof {
-                            match update_oneof {
-                                UpdateOneof::Transaction(sub_tx) => {
-                                    if let Some(tx_info) = sub_tx.transaction {
-                                        let tx_with_meta = convert_to_encoded_tx(tx_info)?;
-                                        if let Some(price) = process_tx_with_meta(target_key, tx_with_meta) { 
-                                            // 更新最新价格
-                                            {
-                                                let mut price_data = latest_price.lock().await;
-                                                price_data.price = Some(price);
-                                                // 通知价格已更新
-                                                price_data.notify.notify_waiters();
-                                            }
-                                            println!("更新最新价格: {}", price);
-                                            
-                                            let blockhash = rpc.get_latest_blockhash().await?;
-                                            // 在创建卖的交易的时候
-                                            let tx1 = create_sell_transaction(
-                                                &bd,
-                                                price,
-                                                &target_key,
-                                                &keypair,
-                                                balance,
-                                                1500.0,
-                                                blockhash,
-                                            )?; 
-                                            
-                                            // 检查卖出交易是否是亏本交易
-                                            // 亏本或者亏本小于10000
-                                            let sol_change = check_sol_change(price, balance, launch_cost, tip)?;
-                                            println!("狙击手利润为 {}", sol_change);
-                                            if sol_change < 0.0 || sol_change < min_profit as f32 {
-                                                println!("狙击手利润为 {}，亏本或者亏本小于最小利润 {}，不交易", sol_change, min_profit);
-                                                continue;
-                                            }
-                                            if tip > 0 {
-                                                let tx2 = transfer_tx(
-                                                    &wallet,
-                                                    &get_tip_account()?,
-                                                    &keypair,
-                                                    tip,
-                                                    blockhash, 
-                                                );
-                                                let bundle_id = send_bundle(&jito, vec![tx1, tx2]).await?;
-                                                println!("bundle id {:?}", bundle_id);
-                                            } else {
-                                                let sig = rpc.send_and_confirm_transaction(&tx1).await?;
-                                                // todo! 去模拟交易
-                                                println!("sig {:?}", sig);
-                                            }
-                                        }
+                        let created_at = update.created_at;
+                        if let Some(UpdateOneof::Transaction(sub_tx)) = update.update_oneof {
+                            // 自己的交易也会改变价格，确认后继续处理
+                            tracker.on_transaction(&sub_tx);
+                            let Some(tx_info) = sub_tx.transaction else {
+                                continue;
+                            };
+                            let slot = sub_tx.slot;
+                            // 事件和由它触发的交易都带着收到的时间和 slot
+                            let stamp = EventStamp::new(slot, received, created_at);
+                            let signature = bs58::encode(&tx_info.signature).into_string();
+                            // 每笔交易一个 span，日志中的 latency_us 是从收到 Geyser 更新开始的耗时
+                            let span = info_span!("event", %signature, slot, mint = %target_key);
+                            async {
+                                let tx_with_meta = convert_to_encoded_tx(tx_info)?;
+                                let events = parse_events(&tx_with_meta);
+                                let decoded = Instant::now();
+                                landing.latency.lock().unwrap().record_event(&stamp, decoded);
+                                if let Some(store) = &store {
+                                    for event in events.iter().cloned() {
+                                        store.record(StoreRecord::from_event(slot, &signature, event));
+                                    }
+                                }
+                                last_slot = slot;
+                                for event in &events {
+                                    if let TargetEvent::PumpfunBuy(trade) | TargetEvent::PumpfunSell(trade) = event {
+                                        landing.show(DashboardEvent::Trade { slot, trade: *trade });
+                                    }
+                                }
+                                if let Some(paper) = paper.as_mut() {
+                                    for fill in paper.on_transaction(slot, &events) {
+                                        info!(signature = %fill.signature, success = fill.success, sol_change = fill.sol_change, "paper 成交");
+                                        landing.record(&wallet, fill.with_sol_usd(sol_usd.price()));
                                     }
                                 }
-                                _ => {}
+                                let Some(price) = process_tx_with_meta(target_key, tx_with_meta) else {
+                                    return Ok(());
+                                };
+                                latest_price = price;
+                                debug!(price, latency_us = received.elapsed().as_micros() as u64, "价格更新");
+                                // 持仓随成交和控制 API 的买卖变化
+                                let balance = match &paper {
+                                    Some(paper) => paper.position(&target_key),
+                                    None => landing.wallets.lock().unwrap().total_position(&target_key),
+                                };
+                                landing.show(DashboardEvent::Position { mint: target_key, tokens: balance });
+                                if paused || exit_orders.is_some() || balance == 0 {
+                                    return Ok(());
+                                }
+                                // 订单未落地时不再下单
+                                let pending = match &paper {
+                                    Some(paper) => paper.has_pending(&target_key),
+                                    None => landing.has_pending_sell(&target_key),
+                                };
+                                if pending {
+                                    return Ok(());
+                                }
+
+                                let blockhash = rpc.get_latest_blockhash().await?;
+
+                                // 根据 tip floor 和预期利润计算本次 tip
+                                let trade_tip = match tip_floor.as_ref().and_then(|cache| cache.get()) {
+                                    Some(floor) => thresholds.tip_policy.choose(&floor, check_sol_change(price, balance, thresholds.launch_cost, 0)?),
+                                    None => thresholds.tip,
+                                };
+
+                                // 扣除成本和 tip 后利润不足时不卖
+                                let sol_change = check_sol_change(price, balance, thresholds.launch_cost, trade_tip)?;
+                                let usd = sol_usd.to_usd(sol_change as i64);
+                                if !should_sell(sol_change, thresholds.min_profit) {
+                                    debug!(profit = sol_change, ?usd, min_profit = thresholds.min_profit, "利润未达到阈值，不卖出");
+                                    return Ok(());
+                                }
+                                info!(profit = sol_change, ?usd, tip = trade_tip, balance, "利润达到阈值，卖出");
+                                if let Some(paper) = paper.as_mut() {
+                                    let (tx1, built, signed) = {
+                                        let signer = TimedSigner::new(paper_signer.as_ref());
+                                        let params = SellParams {
+                                            bonding_curve: &bd,
+                                            mint: &target_key,
+                                            price,
+                                            amount_in_token: balance,
+                                            slippage_bps: thresholds.slippage_bps,
+                                            budget: &thresholds.budget,
+                                        };
+                                        let tx1 = create_sell_transaction(&params, &signer, blockhash)?;
+                                        let signed = Instant::now();
+                                        (tx1, signed - signer.elapsed(), signed)
+                                    };
+                                    let order = PaperOrder {
+                                        signature: tx1.signatures[0],
+                                        mint: target_key,
+                                        side: PaperSide::Sell,
+                                        amount: balance,
+                                        min_out: min_sol_out(price, balance, thresholds.slippage_bps),
+                                        tip: trade_tip,
+                                        signatures: tx1.signatures.len() + usize::from(trade_tip > 0),
+                                        priority_fee: thresholds.budget.priority_fee(),
+                                        land_slot: slot + paper.delay_slots(),
+                                    };
+                                    landing.sent(tx1.signatures[0], wallet, "sell");
+                                    submit_paper(paper, &rpc, args.simulate, &tx1, order).await;
+                                    let timing = OrderTiming { trigger: stamp, decoded, built, signed, sent: Instant::now() };
+                                    landing.latency.lock().unwrap().record_order(tx1.signatures[0], &timing);
+                                    return Ok(());
+                                }
+                                // 卖出路由到持有 token 的钱包，每个钱包一笔
+                                let plan = SellPlan {
+                                    mint: &target_key,
+                                    price,
+                                    percent: 100.0,
+                                    slippage_bps: thresholds.slippage_bps,
+                                    tip: (trade_tip > 0).then_some((trade_tip, &tip_accounts)),
+                                    budget: &thresholds.budget,
+                                };
+                                let holders = landing.wallets.lock().unwrap().holder_signers(&target_key);
+                                let sells = sell_txs(holders, &plan, blockhash)?;
+                                for sell in &sells {
+                                    if let Err(e) = sell.check(&guard, price) {
+                                        warn!(wallet = %sell.wallet, "风控拒绝卖出: {}", e);
+                                        continue;
+                                    }
+                                    if args.simulate && !simulate(&rpc, &sell.tx).await {
+                                        warn!(wallet = %sell.wallet, "模拟失败，不发送卖出");
+                                        continue;
+                                    }
+                                    landing.watch(&sell.tx, sell.wallet, target_key, "sell");
+                                    if let Some(tip_tx) = &sell.tip_tx {
+                                        landing.watch(tip_tx, sell.wallet, target_key, "tip");
+                                    }
+                                    let sending = Instant::now();
+                                    let report = submitter.submit(&sell.tx, sell.tip_tx.as_ref()).await?;
+                                    // 发出时间取最先成功返回的通道
+                                    let sent = report.ack_latency().map_or_else(Instant::now, |latency| sending + latency);
+                                    metrics::EVENT_TO_SEND.observe(sent.duration_since(received).as_secs_f64());
+                                    if report.is_accepted() {
+                                        let timing = OrderTiming {
+                                            trigger: stamp,
+                                            decoded,
+                                            built: sell.built,
+                                            signed: sell.signed,
+                                            sent,
+                                        };
+                                        landing.latency.lock().unwrap().record_order(report.signature, &timing);
+                                    }
+                                    info!(
+                                        order = %report.signature,
+                                        wallet = %sell.wallet,
+                                        tokens = sell.amount,
+                                        latency_us = sent.duration_since(received).as_micros() as u64,
+                                        ?report,
+                                        "自动卖出已发送"
+                                    );
+                                    if let Some(store) = &store {
+                                        store.record(StoreRecord::Order(OrderRecord {
+                                            signature: report.signature.to_string(),
+                                            mint: target_key,
+                                            side: "sell".to_string(),
+                                            token_amount: sell.amount,
+                                            price,
+                                            slippage_bps: thresholds.slippage_bps,
+                                            tip: trade_tip,
+                                            created_at: unix_now(),
+                                        }));
+                                    }
+                                }
+                                anyhow::Ok(())
                             }
+                            .instrument(span)
+                            .await?;
                         }
                     }
                     Err(e) => {
-                        println!("Stream error: {:?}", e);
+                        warn!("交易流错误: {:?}", e);
                     }
                 }
             },
             
+            _ = &mut ctrl_c => {
+                warn!("收到 Ctrl-C，退出");
+                return landing.finish().await;
+            },
+
             // 处理结束条件
             else => {
-                println!("Stream closed or error occurred");
+                warn!("交易流已关闭");
                 break;
             }
         }
@@ -233,56 +1174,616 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-use anyhow::anyhow;
-use solana_transaction_status::{EncodedTransactionWithStatusMeta, UiTransactionEncoding};
-use yellowstone_grpc_proto::convert_from;
-use yellowstone_grpc_proto::prelude::SubscribeUpdateTransactionInfo;
+/// 交易落地后的处理：确认、发送通道统计和实际盈亏记账
+#[derive(Clone)]
+struct Landing {
+    tracker: Arc<ConfirmationTracker>,
+    submitter: Arc<Submitter>,
+    ledger: Arc<StdMutex<PnlLedger>>,
+    rpc: Arc<RpcClient>,
+    tip_accounts: TipAccounts,
+    sol_usd: SolUsdTracker,
+    store: Option<Store>,
+    wallets: Arc<StdMutex<WalletPool>>,
+    guard: Arc<RiskGuard>,
+    /// `--tui` 时的终端面板
+    dashboard: Option<DashboardHandle>,
+    /// 各阶段延迟和落地 slot 统计
+    latency: Arc<StdMutex<LatencyTracker>>,
+    /// 已发出但还没有确认记账或过期的卖出及其 (钱包, mint)
+    pending_sells: Arc<StdMutex<HashMap<Signature, (Pubkey, Pubkey)>>>,
+}
+
+impl Landing {
+    fn show(&self, event: DashboardEvent) {
+        if let Some(dashboard) = &self.dashboard {
+            dashboard.send(event);
+        }
+    }
+
+    /// 在面板上显示发出的交易，paper 模式的模拟订单也走这里
+    fn sent(&self, signature: Signature, wallet: Pubkey, side: &'static str) {
+        self.show(DashboardEvent::TxSent { signature, wallet, side });
+    }
+
+    /// `mint` 是否有已发出但还没有确认记账的卖出，实盘的持仓只在成交记账后变化
+    fn has_pending_sell(&self, mint: &Pubkey) -> bool {
+        self.pending_sells.lock().unwrap().values().any(|(_, pending)| pending == mint)
+    }
+
+    /// 跟踪 `wallet` 为 `mint` 发出的交易，落地后打印结果、记录发送通道统计，并按实际成交记账。
+    /// 返回的任务在交易确认并记账或过期后结束
+    fn watch(&self, tx: &Transaction, wallet: Pubkey, mint: Pubkey, side: &'static str) -> JoinHandle<()> {
+        let signature = tx.signatures[0];
+        let programs = instruction_programs(&tx.message);
+        self.sent(signature, wallet, side);
+        if side == "sell" {
+            self.pending_sells.lock().unwrap().insert(signature, (wallet, mint));
+        }
+        let receiver = self.tracker.track(signature, wallet);
+        // 使用发送时的 SOL/USD 价格记账
+        let sol_usd = self.sol_usd.price();
+        let landing = self.clone();
+        // 父 span 是触发这笔交易的事件，日志可以从事件一直追踪到落地
+        let span = info_span!("order", order = %signature, %wallet, side);
+        tokio::spawn(async move {
+            async {
+                let confirmation = match receiver.await {
+                    Ok(confirmation) => confirmation,
+                    Err(_) => {
+                        landing.submitter.forget(&signature);
+                        landing.latency.lock().unwrap().forget(&signature);
+                        landing.show(DashboardEvent::TxExpired(signature));
+                        warn!("交易未确认，已过期");
+                        return;
+                    }
+                };
+                landing.show(DashboardEvent::TxLanded {
+                    signature,
+                    slot: confirmation.slot,
+                    success: confirmation.is_success(),
+                });
+                match &confirmation.err {
+                    None => {
+                        landing.submitter.mark_landed(&signature);
+                    }
+                    Some(err) => {
+                        landing.submitter.forget(&signature);
+                        metrics::TRANSACTION_FAILURES.with_label_values(&[&metrics::error_label(err, &programs)]).inc();
+                    }
+                }
+                info!(slot = confirmation.slot, success = confirmation.is_success(), elapsed_ms = confirmation.elapsed.as_millis() as u64, source = ?confirmation.source, "交易确认");
+
+                let tip_accounts = landing.tip_accounts.accounts();
+                match fetch_fill(&landing.rpc, &signature, &wallet, &tip_accounts).await {
+                    Ok(fill) => landing.record(&wallet, fill.with_mint(mint).with_sol_usd(sol_usd)),
+                    Err(e) => {
+                        landing.latency.lock().unwrap().forget(&signature);
+                        warn!("解析成交失败: {:?}", e);
+                    }
+                }
+            }
+            .await;
+            // 确认并记账或过期后才允许对这个 mint 再次卖出
+            landing.pending_sells.lock().unwrap().remove(&signature);
+        }
+        .instrument(span))
+    }
+
+    /// 退出前写入数据库并打印本次运行的盈亏和延迟统计
+    async fn finish(&self) -> Result<()> {
+        if let Some(store) = self.store.clone() {
+            // flush 阻塞等待写线程，不占用 async 线程
+            tokio::task::spawn_blocking(move || store.flush()).await??;
+        }
+        info!(session = ?self.ledger.lock().unwrap().session(), "本次运行盈亏，退出");
+        info!(report = ?self.latency.lock().unwrap().report(), "延迟统计");
+        Ok(())
+    }
+
+    /// 成交写入数据库、更新钱包持仓并记账，paper 模式的模拟成交也走这里
+    fn record(&self, wallet: &Pubkey, fill: Fill) {
+        if let Some(store) = &self.store {
+            store.record(StoreRecord::Fill(FillRecord::from(&fill)));
+        }
+        self.wallets.lock().unwrap().apply_fill(wallet, &fill);
+        self.guard.record_fill(&fill);
+        metrics::TIPS_PAID.inc_by(fill.jito_tip);
+        // 落地 slot 取自确认的交易数据
+        if let Some(slots) = self.latency.lock().unwrap().landed(&fill.signature, fill.slot) {
+            info!(signature = %fill.signature, slot = fill.slot, slots, "落地 slot 比触发事件晚");
+        }
+        self.show(DashboardEvent::TxLanded {
+            signature: fill.signature,
+            slot: fill.slot,
+            success: fill.success,
+        });
+        let mut ledger = self.ledger.lock().unwrap();
+        ledger.record(fill);
+        info!(session = ?ledger.session(), "实际盈亏");
+        metrics::REALIZED_PNL.set(ledger.mints().values().map(|pnl| pnl.realized()).sum());
+        metrics::SESSION_PNL.set(ledger.session().sol_change);
+        self.show(DashboardEvent::Pnl {
+            session: ledger.session().clone(),
+            realized: ledger.mints().iter().map(|(mint, pnl)| (*mint, pnl.realized())).collect(),
+        });
+    }
+}
+
+/// 一个钱包的卖出交易和可选的 tip 交易
+struct SellTx {
+    wallet: Pubkey,
+    amount: u64,
+    tx: Transaction,
+    tip_tx: Option<Transaction>,
+    /// 交易构造完成和签名完成的时间
+    built: Instant,
+    signed: Instant,
+}
 
-pub fn convert_to_encoded_tx(
-    tx_info: SubscribeUpdateTransactionInfo,
-) -> Result<EncodedTransactionWithStatusMeta> {
-    convert_from::create_tx_with_meta(tx_info)
-        .unwrap()
-        .encode(UiTransactionEncoding::Base64, Some(u8::MAX), true)
-        .map_err(|e| anyhow!("{}", e))
+impl SellTx {
+    /// 卖出交易和 tip 交易都通过风控才发送
+    fn check(&self, guard: &RiskGuard, price: f32) -> Result<(), RiskError> {
+        guard.check(&self.tx, price)?;
+        match &self.tip_tx {
+            Some(tip_tx) => guard.check(tip_tx, price),
+            None => Ok(()),
+        }
+    }
 }
 
-pub async fn get_balance(rpc: &RpcClient, wallet: &Pubkey, mint: &Pubkey) -> Result<u64> {
-    let token_ata = get_associated_token_address_with_program_id(wallet, mint, &spl_token::id());
-    
-    // 首先检查账户是否存在
-    match rpc.get_account_with_commitment(&token_ata, CommitmentConfig::confirmed()).await {
-        Ok(response) => {
-            if let Some(_) = response.value {
-                // 账户存在，获取余额
-                match rpc.get_token_account_balance_with_commitment(&token_ata, CommitmentConfig::confirmed()).await {
-                    Ok(balance) => Ok(balance.value.amount.as_str().parse::<u64>().unwrap()),
-                    Err(e) => Err(e.into()),
+/// 按比例卖出所有钱包的持仓，`tip` 为 `(lamports, tip 账户)`
+struct SellPlan<'a> {
+    mint: &'a Pubkey,
+    price: f32,
+    percent: f64,
+    slippage_bps: f64,
+    tip: Option<(u64, &'a TipAccounts)>,
+    budget: &'a ComputeBudget,
+}
+
+/// 每个持有 `mint` 的钱包卖出持仓的 `percent`%，各一笔交易。全部卖出时关闭 token 账户。
+/// `holders` 来自 [`WalletPool::holder_signers`]，签名时不持有钱包池的锁
+fn sell_txs(holders: Vec<(Arc<dyn Signer + Send + Sync>, u64)>, plan: &SellPlan, blockhash: Hash) -> Result<Vec<SellTx>> {
+    let bonding_curve = find_bonding_curve(plan.mint);
+    holders
+        .into_iter()
+        .map(|(signer, position)| (signer, position, portion(position, plan.percent)))
+        .filter(|(_, _, amount)| *amount > 0)
+        .map(|(signer, position, amount)| {
+            let wallet = signer.pubkey();
+            let signer = TimedSigner::new(signer.as_ref());
+            let params = SellParams {
+                bonding_curve: &bonding_curve,
+                mint: plan.mint,
+                price: plan.price,
+                amount_in_token: amount,
+                slippage_bps: plan.slippage_bps,
+                budget: plan.budget,
+            };
+            let tx = match amount == position {
+                true => create_sell_transaction(&params, &signer, blockhash)?,
+                false => create_partial_sell_transaction(&params, &signer, blockhash)?,
+            };
+            let tip_tx = match plan.tip {
+                Some((tip, tip_accounts)) => Some(transfer_tx(&tip_accounts.random()?, &signer, tip, blockhash)?),
+                None => None,
+            };
+            // 签名在构造函数的最后，构造完成的时间按签名耗时倒推
+            let signed = Instant::now();
+            let built = signed - signer.elapsed();
+            Ok(SellTx { wallet, amount, tx, tip_tx, built, signed })
+        })
+        .collect()
+}
+
+/// 持仓的 `percent`%，向下取整
+fn portion(position: u64, percent: f64) -> u64 {
+    if percent >= 100.0 {
+        return position;
+    }
+    (position as f64 * percent / 100.0) as u64
+}
+
+/// 链上的曲线状态，mint 不是 pump.fun 代币或已经迁移时报错
+async fn fetch_curve(rpc: &RpcClient, mint: &Pubkey) -> Result<Curve> {
+    let reserve = get_pumpfun_reserve(rpc, *mint)
+        .await
+        .ok_or_else(|| anyhow!("{} has no pump.fun bonding curve", mint))?;
+    let curve = Curve::from(&reserve);
+    if curve.is_complete() {
+        bail!("{} bonding curve is complete, the token has migrated", mint);
+    }
+    Ok(curve)
+}
+
+/// 最多花费的 SOL：报价加上滑点
+fn max_sol_cost(sol_in: u64, slippage_bps: f64) -> u64 {
+    (sol_in as f64 * (10000.0 + slippage_bps) / 10000.0) as u64
+}
+
+/// 一次性交易命令：发送交易（配置了 tip 时附带 Jito tip 交易），等待确认并打印实际成交
+async fn send_and_confirm(
+    settings: &Settings,
+    rpc: Arc<RpcClient>,
+    signer: &dyn Signer,
+    tx: &Transaction,
+    guard: &RiskGuard,
+    price: f32,
+) -> Result<()> {
+    let wallet = signer.try_pubkey()?;
+    let jito = settings.jito_pool()?;
+    let tip_accounts = match &jito {
+        Some(pool) => TipAccounts::fetch(pool).await,
+        None => TipAccounts::default(),
+    };
+    let submitter = build_submitter(&settings.rpc_url()?, jito, &settings.send).await?;
+    let tip_tx = match settings.tip(&settings.config) {
+        0 => None,
+        tip => Some(transfer_tx(
+            &tip_accounts.random()?,
+            signer,
+            tip,
+            tx.message.recent_blockhash,
+        )?),
+    };
+    // 交易和 tip 交易都要通过风控
+    guard.check(tx, price)?;
+    if let Some(tip_tx) = &tip_tx {
+        guard.check(tip_tx, price)?;
+    }
+
+    // 没有订阅交易流，只用 getSignatureStatuses 确认
+    let tracker = ConfirmationTracker::new();
+    tracker.spawn_rpc_fallback(rpc.clone(), Duration::ZERO, Duration::from_secs(90), Duration::from_secs(1));
+    let signature = tx.signatures[0];
+    let receiver = tracker.track(signature, wallet);
+    let report = submitter.submit(tx, tip_tx.as_ref()).await?;
+    info!(order = %signature, ?report, "交易已发送");
+    if !report.is_accepted() {
+        bail!("transaction {} was rejected by every send path", signature);
+    }
+    match receiver.await {
+        Ok(confirmation) => {
+            info!(order = %signature, slot = confirmation.slot, success = confirmation.is_success(), "交易确认");
+            // 交易已确认，解析失败只影响记账
+            match fetch_fill(&rpc, &signature, &wallet, &tip_accounts.accounts()).await {
+                Ok(fill) => info!(order = %signature, ?fill, "成交"),
+                Err(e) => warn!(order = %signature, "解析成交失败: {:?}", e),
+            }
+        }
+        Err(_) => warn!(order = %signature, "交易未确认，已过期"),
+    }
+    Ok(())
+}
+
+async fn sell(settings: &Settings, args: SellArgs) -> Result<()> {
+    let mint = settings.require_mint(args.mint)?;
+    let rpc = Arc::new(RpcClient::new(settings.rpc_url()?));
+    let mut pool = settings.wallet_pool()?;
+    pool.refresh(&rpc, &[mint]).await?;
+    let balance = pool.total_position(&mint);
+    if balance == 0 {
+        bail!("no wallet holds {}", mint);
+    }
+    let curve = fetch_curve(&rpc, &mint).await?;
+    let price = curve.price();
+    let quote = curve.sell(balance);
+    info!(
+        tokens = balance,
+        price,
+        sol_out = quote.sol_out(),
+        min_sol_out = min_sol_out(price, balance, args.slippage_bps),
+        "卖出报价"
+    );
+
+    // 每个持有 token 的钱包各卖一笔，tip 交易由 send_and_confirm 附带
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let plan = SellPlan {
+        mint: &mint,
+        price,
+        percent: 100.0,
+        slippage_bps: args.slippage_bps,
+        tip: None,
+        budget: &settings.config.fees.compute_budget(),
+    };
+    let sells = sell_txs(pool.holder_signers(&mint), &plan, blockhash)?;
+    let guard = settings.risk_guard();
+    try_join_all(sells.iter().map(|sell| {
+        info!(wallet = %sell.wallet, tokens = sell.amount, "卖出");
+        let signer = pool.get(&sell.wallet).unwrap().signer.as_ref();
+        send_and_confirm(settings, rpc.clone(), signer, &sell.tx, &guard, price)
+    }))
+    .await?;
+    Ok(())
+}
+
+async fn buy(settings: &Settings, args: BuyArgs) -> Result<()> {
+    let mint = settings.require_mint(args.mint)?;
+    let rpc = Arc::new(RpcClient::new(settings.rpc_url()?));
+    let mut pool = settings.wallet_pool()?;
+    pool.refresh(&rpc, &[]).await?;
+    let mut curve = fetch_curve(&rpc, &mint).await?;
+    let budget = settings.config.fees.compute_budget();
+    let blockhash = rpc.get_latest_blockhash().await?;
+
+    // 拆分到钱包池，后面的钱包按前面买入后的曲线报价
+    let mut txs = Vec::new();
+    let fees = buy_fees(budget.priority_fee(), settings.tip(&settings.config));
+    for (wallet, sol) in pool.split_buy(args.sol, args.max_per_wallet, args.slippage_bps, fees)? {
+        let quote = curve.buy_with_sol(sol);
+        if quote.tokens == 0 {
+            bail!("{} lamports is too small to buy any {}", sol, mint);
+        }
+        let max_cost = max_sol_cost(quote.sol_in(), args.slippage_bps);
+        info!(%wallet, tokens = quote.tokens, sol_in = quote.sol_in(), max_cost, "买入报价");
+        let price = curve.price();
+        curve.apply_buy(&quote);
+        let signer = pool.get(&wallet).unwrap().signer.as_ref();
+        let tx = create_buy_transaction(
+            &find_bonding_curve(&mint),
+            &mint,
+            signer,
+            quote.tokens,
+            max_cost,
+            &budget,
+            blockhash,
+        )?;
+        txs.push((signer, tx, price));
+    }
+    let guard = settings.risk_guard();
+    try_join_all(
+        txs.iter()
+            .map(|(signer, tx, price)| send_and_confirm(settings, rpc.clone(), *signer, tx, &guard, *price)),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn create(settings: &Settings, args: CreateTokenArgs) -> Result<()> {
+    let keypair = settings.signer()?;
+    let rpc = Arc::new(RpcClient::new(settings.rpc_url()?));
+    let mint = Keypair::new();
+    println!("mint: {}", mint.pubkey());
+
+    // 新代币的曲线状态是固定的，不需要查询
+    let buy = args.buy_sol.map(|sol| {
+        let quote = Curve::initial().buy_with_sol(sol);
+        info!(tokens = quote.tokens, sol_in = quote.sol_in(), "创建后买入");
+        (quote.tokens, max_sol_cost(quote.sol_in(), args.slippage_bps))
+    });
+    let metadata = CreateArgs {
+        name: args.name,
+        symbol: args.symbol,
+        uri: args.uri,
+    };
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let tx = create_token_transaction(
+        &mint,
+        &keypair,
+        &metadata,
+        buy,
+        &settings.config.fees.compute_budget(),
+        blockhash,
+    )?;
+    let guard = settings.risk_guard();
+    send_and_confirm(settings, rpc, &keypair, &tx, &guard, Curve::initial().price()).await
+}
+
+async fn watch(settings: &Settings, args: WatchArgs) -> Result<()> {
+    let mut stream = open_stream(settings, true).await?;
+    // Ctrl-C 时正常返回，交易流释放时录制文件刷盘
+    let mut ctrl_c = std::pin::pin!(tokio::signal::ctrl_c());
+    while let Some(update) = tokio::select! {
+        update = stream.next() => update,
+        _ = &mut ctrl_c => None,
+    } {
+        let update = match update {
+            Ok(update) => update,
+            Err(e) => {
+                warn!("交易流错误: {:?}", e);
+                continue;
+            }
+        };
+        let Some(UpdateOneof::Transaction(sub_tx)) = update.update_oneof else {
+            continue;
+        };
+        let Some(tx_info) = sub_tx.transaction else {
+            continue;
+        };
+        let signature = bs58::encode(&tx_info.signature).into_string();
+        for event in parse_events(&convert_to_encoded_tx(tx_info)?) {
+            if args.mint.is_some_and(|mint| mint != event.mint()) {
+                continue;
+            }
+            match &event {
+                TargetEvent::PumpfunBuy(trade) | TargetEvent::PumpfunSell(trade) => println!(
+                    "{} {} {} {} 个 {} lamports 价格 {} ({})",
+                    sub_tx.slot,
+                    trade.mint,
+                    if trade.is_buy { "买入" } else { "卖出" },
+                    trade.token_amount,
+                    trade.sol_amount,
+                    Curve::from(trade).price(),
+                    signature
+                ),
+                other => println!("{} {:?} ({})", sub_tx.slot, other, signature),
+            }
+        }
+    }
+    println!("Stream closed");
+    Ok(())
+}
+
+async fn quote(settings: &Settings, args: QuoteArgs) -> Result<()> {
+    let mint = settings.require_mint(args.mint)?;
+    let rpc = RpcClient::new(settings.rpc_url()?);
+    let curve = fetch_curve(&rpc, &mint).await?;
+    println!("{:?}", curve);
+    println!("价格: {}", curve.price());
+    if let Some(sol) = args.buy_sol {
+        let quote = curve.buy_with_sol(sol);
+        println!("花费 {} lamports 买入 {} 个，手续费 {}", quote.sol_in(), quote.tokens, quote.fee);
+    }
+    if let Some(tokens) = args.sell_tokens {
+        let quote = curve.sell(tokens);
+        println!("卖出 {} 个收到 {} lamports，手续费 {}", quote.tokens, quote.sol_out(), quote.fee);
+    }
+    Ok(())
+}
+
+async fn balance(settings: &Settings, args: BalanceArgs) -> Result<()> {
+    let rpc = RpcClient::new(settings.rpc_url()?);
+    let mint = settings.mint(args.mint)?;
+    if let Some(wallet) = args.wallet {
+        println!("wallet: {}", wallet);
+        println!("SOL: {}", rpc.get_balance(&wallet).await? as f64 / 1e9);
+        if let Some(mint) = mint {
+            println!("{}: {}", mint, get_token_balance(&rpc, &wallet, &mint).await?);
+        }
+        return Ok(());
+    }
+    let mut pool = settings.wallet_pool()?;
+    let mints: Vec<Pubkey> = mint.into_iter().collect();
+    pool.refresh(&rpc, &mints).await?;
+    for wallet in pool.wallets() {
+        println!("wallet: {}", wallet.pubkey());
+        println!("  SOL: {}", wallet.sol as f64 / 1e9);
+        for mint in &mints {
+            println!("  {}: {}", mint, wallet.position(mint));
+        }
+    }
+    if pool.wallets().len() > 1 {
+        println!("合计 SOL: {}", pool.total_sol() as f64 / 1e9);
+        for mint in &mints {
+            println!("合计 {}: {}", mint, pool.total_position(mint));
+        }
+    }
+    Ok(())
+}
+
+async fn rebalance(settings: &Settings, args: RebalanceArgs) -> Result<()> {
+    let rpc = RpcClient::new(settings.rpc_url()?);
+    let mut pool = settings.wallet_pool()?;
+    pool.refresh(&rpc, &[]).await?;
+    let transfers = pool.rebalance(args.min_sol);
+    if transfers.is_empty() {
+        println!("钱包余额已经平衡");
+        return Ok(());
+    }
+    let guard = settings.risk_guard();
+    for transfer in &transfers {
+        println!("{} -> {}: {} SOL", transfer.from, transfer.to, transfer.lamports as f64 / 1e9);
+        if args.dry_run {
+            continue;
+        }
+        // 转账不是卖出，kill switch 触发后也不发送
+        if let Some(reason) = guard.killed() {
+            return Err(RiskError::KillSwitch(reason).into());
+        }
+        let blockhash = rpc.get_latest_blockhash().await?;
+        let signer = pool.get(&transfer.from).unwrap().signer.as_ref();
+        let tx = transfer_tx(&transfer.to, signer, transfer.lamports, blockhash)?;
+        guard.check(&tx, 0.0)?;
+        info!(order = %rpc.send_and_confirm_transaction(&tx).await?, "交易确认");
+    }
+    Ok(())
+}
+
+fn keystore(settings: &Settings, args: KeystoreArgs) -> Result<()> {
+    let keypair = match args.new {
+        true => Keypair::new(),
+        false => settings.keypair()?,
+    };
+    let passphrase = read_new_passphrase()?;
+    Keystore::encrypt(&keypair, &passphrase, args.log_n)?.save(&args.out)?;
+    println!("已保存 {} 到 {}", keypair.pubkey(), args.out.display());
+    Ok(())
+}
+
+/// 模拟交易并记录失败原因。只有模拟返回交易错误时返回 false，RPC 请求失败不拦截交易
+async fn simulate(rpc: &RpcClient, tx: &Transaction) -> bool {
+    match rpc.simulate_transaction(tx).await {
+        Ok(resp) => {
+            info!(order = %tx.signatures[0], err = ?resp.value.err, logs = ?resp.value.logs, "模拟交易");
+            match &resp.value.err {
+                Some(err) => {
+                    let programs = instruction_programs(&tx.message);
+                    metrics::SIMULATION_FAILURES.with_label_values(&[&metrics::error_label(err, &programs)]).inc();
+                    false
                 }
-            } else {
-                // 账户不存在，返回0余额
-                Ok(0)
+                None => true,
             }
-        },
-        Err(e) => Err(e.into()),
-    }
-}
-
-use rand::{rng, seq::IteratorRandom};
-pub fn get_tip_account() -> Result<Pubkey> {
-    let accounts = [
-        "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
-        "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
-        "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
-        "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
-        "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
-        "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
-        "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
-        "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
-    ];
-    let mut rng = rng();
-    match accounts.iter().choose(&mut rng) {
-        Some(acc) => Ok(Pubkey::from_str(acc).inspect_err(|err| {})?),
-        None => Err(anyhow!("jito: no tip accounts available")),
+        }
+        Err(e) => {
+            warn!("模拟交易请求失败: {:?}", e);
+            true
+        }
+    }
+}
+
+/// paper 模式下单：配置了 `--simulate` 时先模拟交易，然后按落地 slot 挂单
+async fn submit_paper(paper: &mut PaperTrader, rpc: &RpcClient, simulate_first: bool, tx: &Transaction, order: PaperOrder) {
+    if simulate_first {
+        simulate(rpc, tx).await;
+    }
+    info!(?order, "paper 挂单");
+    paper.submit(order);
+}
+
+/// 交易流：配置了 `replay_path` 时回放录制文件，否则连接 Geyser，配置了 `record_path` 时同时录制。
+/// `allow_replay` 为 false 时不允许回放，避免按录制的旧行情发出真实交易
+async fn open_stream(settings: &Settings, allow_replay: bool) -> Result<UpdateStream> {
+    let stream = &settings.stream;
+    if let Some(path) = &stream.replay_path {
+        if !allow_replay {
+            bail!("replay is only allowed with --paper");
+        }
+        let playback = stream.replay_speed.as_deref().unwrap_or("realtime").parse::<Playback>()?;
+        info!(path = %path.display(), ?playback, "回放");
+        return replay_stream(path, playback).await;
+    }
+    let updates = get_pumpfun_stream(&settings.grpc_url()?).await?;
+    match &stream.record_path {
+        Some(path) => Ok(record_stream(updates, StreamRecorder::create(path).await?)),
+        None => Ok(updates),
+    }
+}
+
+/// SOL/USD 价格源，没有设置的字段使用默认值
+fn build_price_oracle(price: &PriceSettings) -> Result<PriceOracle> {
+    let mut oracle = PriceOracle::default();
+    if !price.feeds.is_empty() {
+        oracle.feeds = price
+            .feeds
+            .iter()
+            .map(|feed| feed.trim().parse::<PriceFeed>())
+            .collect::<Result<_>>()?;
+    }
+    oracle.max_age = price.max_age.unwrap_or(oracle.max_age);
+    oracle.max_conf_ratio = price.max_conf_ratio.unwrap_or(oracle.max_conf_ratio);
+    if let Some(url) = &price.fallback_url {
+        oracle.fallback = Some(HttpPrice {
+            url: url.clone(),
+            pointer: price.fallback_pointer.clone().unwrap_or_else(|| "/price".to_string()),
+        });
+    }
+    Ok(oracle)
+}
+
+/// 组装发送通道：`rpc_url` 和 `send.rpc_urls` 为 RPC 通道，
+/// 配置了 tip 时启用 Jito，配置了 `send.ws_url` 时启用 TPU 直连。
+async fn build_submitter(rpc_url: &str, jito: Option<Arc<JitoPool>>, send: &SendSettings) -> Result<Submitter> {
+    let mut paths = vec![SubmitPath::rpc(rpc_url)];
+    for url in send.rpc_urls.iter().map(|url| url.trim()).filter(|u| !u.is_empty()) {
+        paths.push(SubmitPath::rpc(url));
+    }
+    if let Some(pool) = jito {
+        paths.push(SubmitPath::jito(pool));
+    }
+    if let Some(ws_url) = &send.ws_url {
+        let rpc = Arc::new(RpcClient::new(rpc_url.to_string()));
+        paths.push(SubmitPath::tpu(rpc, ws_url).await?);
     }
+    Ok(Submitter::new(paths))
 }
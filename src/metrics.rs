@@ -0,0 +1,179 @@
+use std::net::SocketAddr;
+
+use anyhow::{bail, Result};
+use axum::{
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use once_cell::sync::Lazy;
+use prometheus::{
+    exponential_buckets, linear_buckets, register_histogram, register_histogram_vec, register_int_counter,
+    register_int_counter_vec, register_int_gauge, Encoder, Histogram, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, TextEncoder,
+};
+use solana_sdk::{pubkey::Pubkey, transaction::TransactionError};
+use tokio::net::TcpListener;
+use tracing::error;
+
+use crate::monitor::PumpfunError;
+
+/// 从流中解析出的 pump.fun 事件，按类型统计
+pub static EVENTS_DECODED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!("pump_events_decoded_total", "pump.fun events decoded from transactions", &["type"])
+        .unwrap()
+});
+
+/// Geyser 断线后的重新订阅次数
+pub static STREAM_RECONNECTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("pump_stream_reconnects_total", "Geyser resubscriptions after the stream failed").unwrap()
+});
+
+/// 从收到 Geyser 更新到交易发出的耗时，0.1ms 到约 3s
+pub static EVENT_TO_SEND: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "pump_event_to_send_seconds",
+        "Time from receiving the triggering Geyser update to submitting the transaction",
+        exponential_buckets(0.0001, 2.0, 16).unwrap()
+    )
+    .unwrap()
+});
+
+/// 各阶段的耗时，阶段见 `latency::Stage`
+pub static STAGE_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "pump_stage_seconds",
+        "Latency of each stage from Geyser update to submission",
+        &["stage"],
+        exponential_buckets(0.00001, 2.0, 20).unwrap()
+    )
+    .unwrap()
+});
+
+/// 落地 slot 比触发事件晚的 slot 数
+pub static LANDING_SLOTS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "pump_landing_slot_delay",
+        "Slots between the triggering event and the slot our transaction landed in",
+        linear_buckets(0.0, 1.0, 11).unwrap()
+    )
+    .unwrap()
+});
+
+/// 每个发送通道的结果，`result` 为 ok 或 error
+pub static SUBMISSIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!("pump_submissions_total", "Transaction submissions per path", &["path", "result"])
+        .unwrap()
+});
+
+/// 每个 Jito 区域的 sendBundle 请求，`result` 为 ok、error 或 rate_limited；不计入 `SUBMISSIONS`
+pub static JITO_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!("pump_jito_requests_total", "sendBundle requests per Jito region", &["region", "result"])
+        .unwrap()
+});
+
+/// Jito 接受的 bundle 数，和 `BUNDLES_LANDED` 一起算落地率
+pub static BUNDLES_SENT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("pump_bundles_sent_total", "Bundles accepted by the Jito block engine").unwrap()
+});
+
+pub static BUNDLES_LANDED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("pump_bundles_landed_total", "Bundled transactions that landed successfully").unwrap()
+});
+
+/// simulateTransaction 失败，`error` 为 `PumpfunError` 名称或交易错误类型
+pub static SIMULATION_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!("pump_simulation_failures_total", "Failed transaction simulations by error", &["error"])
+        .unwrap()
+});
+
+/// 上链但执行失败的交易，标签同 `SIMULATION_FAILURES`
+pub static TRANSACTION_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!("pump_transaction_failures_total", "Landed transactions that failed by error", &["error"])
+        .unwrap()
+});
+
+pub static TIPS_PAID: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("pump_tips_paid_lamports_total", "Jito tips paid by landed transactions").unwrap()
+});
+
+/// 所有 mint 的已实现盈亏之和
+pub static REALIZED_PNL: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("pump_realized_pnl_lamports", "Realized PnL across all mints this session").unwrap()
+});
+
+/// 本次运行钱包 SOL 的净变化，包含手续费和 tip
+pub static SESSION_PNL: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("pump_session_pnl_lamports", "Net wallet SOL change this session including fees").unwrap()
+});
+
+/// 错误的标签：pump.fun 自定义错误用 IDL 中的名称，其他错误用 `TransactionError` 的变体名
+pub fn error_label(err: &TransactionError, programs: &[Pubkey]) -> String {
+    if let Some(e) = PumpfunError::from_transaction_error(err, programs) {
+        return e.name().to_string();
+    }
+    let debug = format!("{:?}", err);
+    debug.split(['(', ' ', '{']).next().unwrap_or_default().to_string()
+}
+
+/// Prometheus 文本格式的所有指标
+pub fn render() -> Result<String> {
+    let mut buf = vec![];
+    TextEncoder::new().encode(&prometheus::gather(), &mut buf)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+async fn metrics() -> Response {
+    match render() {
+        Ok(body) => ([(CONTENT_TYPE, prometheus::TEXT_FORMAT)], body).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// 在 `addr` 上提供 `GET /metrics`，返回实际监听的地址。指标没有鉴权，只允许监听回环地址
+pub async fn spawn_metrics_server(addr: SocketAddr) -> Result<SocketAddr> {
+    if !addr.ip().is_loopback() {
+        bail!("metrics server must listen on a loopback address, got {}", addr);
+    }
+    let app = Router::new().route("/metrics", get(metrics));
+    let listener = TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            error!("指标服务已停止: {:?}", e);
+        }
+    });
+    Ok(local_addr)
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    use solana_sdk::instruction::InstructionError;
+
+    use crate::monitor::PUMPFUN_PROGRAM_ID;
+
+    let programs = [solana_sdk::compute_budget::id(), solana_sdk::system_program::id(), PUMPFUN_PROGRAM_ID];
+    let slippage = TransactionError::InstructionError(2, InstructionError::Custom(6003));
+    assert_eq!(error_label(&slippage, &programs), "TooLittleSolReceived");
+    assert_eq!(error_label(&TransactionError::BlockhashNotFound, &programs), "BlockhashNotFound");
+    assert_eq!(
+        error_label(&TransactionError::InstructionError(0, InstructionError::Custom(1)), &programs),
+        "InstructionError"
+    );
+    // 非 pump.fun 指令的相同错误码
+    assert_eq!(
+        error_label(&TransactionError::InstructionError(1, InstructionError::Custom(6003)), &programs),
+        "InstructionError"
+    );
+    assert!(spawn_metrics_server("0.0.0.0:0".parse().unwrap()).await.is_err());
+
+    EVENTS_DECODED.with_label_values(&["buy"]).inc();
+    SIMULATION_FAILURES.with_label_values(&[&error_label(&slippage, &programs)]).inc();
+    EVENT_TO_SEND.observe(0.002);
+    let addr = spawn_metrics_server("127.0.0.1:0".parse().unwrap()).await.unwrap();
+    let body = reqwest::get(format!("http://{}/metrics", addr)).await.unwrap().text().await.unwrap();
+    assert!(body.contains("pump_events_decoded_total{type=\"buy\"}"));
+    assert!(body.contains("pump_simulation_failures_total{error=\"TooLittleSolReceived\"}"));
+    assert!(body.contains("pump_event_to_send_seconds_bucket"));
+}
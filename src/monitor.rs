@@ -1,23 +1,28 @@
 use std::str::FromStr;
 
-use jito_sdk_rust::JitoJsonRpcSDK;
-use serde_json::json;
-use solana_client::{nonblocking::rpc_client::RpcClient, rpc_client::SerializableTransaction};
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_program::pubkey;
 use solana_sdk::{
-    bs58, compute_budget::ComputeBudgetInstruction, hash::Hash, pubkey::Pubkey, signature::Keypair,
-    signer::Signer, system_instruction, transaction::Transaction,
+    bs58, compute_budget::ComputeBudgetInstruction, hash::Hash, instruction::InstructionError, message::Message,
+    pubkey::Pubkey, signer::Signer, system_instruction, transaction::{Transaction, TransactionError},
 };
 use solana_transaction_status::{
     option_serializer::OptionSerializer, EncodedTransactionWithStatusMeta, UiCompiledInstruction,
-    UiInnerInstructions, UiInstruction,
+    UiInnerInstructions, UiInstruction, UiTransactionEncoding,
 };
 use spl_associated_token_account::{
     get_associated_token_address, get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
 };
 use spl_token::instruction::close_account;
+use thiserror::Error;
+use tracing::debug;
+use yellowstone_grpc_proto::{convert_from, prelude::SubscribeUpdateTransactionInfo};
 
 use anyhow::{anyhow, Result};
+use crate::{
+    metrics,
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::instruction::{AccountMeta, Instruction};
 
@@ -33,12 +38,43 @@ pub const KEY_PREFIX: &'static str = "token:info:";
 pub const UNIT_LIMIT: u32 = 500000;
 pub const UNIT_PRICE: u64 = 20000;
 
+/// 交易的计算单元上限和优先费单价（micro-lamports）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudget {
+    pub unit_limit: u32,
+    pub unit_price: u64,
+}
+
+impl Default for ComputeBudget {
+    fn default() -> Self {
+        Self {
+            unit_limit: UNIT_LIMIT,
+            unit_price: UNIT_PRICE,
+        }
+    }
+}
+
+impl ComputeBudget {
+    pub fn ixs(&self) -> [Instruction; 2] {
+        [
+            ComputeBudgetInstruction::set_compute_unit_limit(self.unit_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(self.unit_price),
+        ]
+    }
+
+    /// 按上限计算的优先费（lamports）
+    pub fn priority_fee(&self) -> u64 {
+        self.unit_limit as u64 * self.unit_price / 1_000_000
+    }
+}
+
 // pumpfun
 pub const PUMPFUN_PROGRAM_ID: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
 pub const PUMPFUN_GLOBAL: Pubkey = pubkey!("4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf");
 pub const PUMPFUN_FEE_RECIPIENT: Pubkey = pubkey!("CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM");
 pub const INIT_SOL_REVERSES: u64 = 30_000_000_000;
 pub const INIT_TOKEN_REVERSES: u64 = 1_073_000_191_000_000;
+pub const INIT_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000;
 pub const INIT_PRICE: f32 = (INIT_SOL_REVERSES as f32 / 1e9) / (INIT_TOKEN_REVERSES as f32 / 1e6);
 pub const PUMPFUN_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000;
 
@@ -54,7 +90,7 @@ pub const SECONDS: u64 = 1000;
 /// 鉴别符
 const PUMPFUN_CREATE_EVENT: [u8; 8] = [27, 114, 169, 77, 222, 235, 99, 118];
 const PUMPFUN_COMPLETE_EVENT: [u8; 8] = [95, 114, 97, 156, 212, 46, 152, 8];
-const PUMPFUN_TRADE_EVENT: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+pub(crate) const PUMPFUN_TRADE_EVENT: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
 
 #[derive(Debug, Clone)]
 pub enum TargetEvent {
@@ -64,6 +100,93 @@ pub enum TargetEvent {
     PumpfunComplete(CompleteEvent),
 }
 
+impl TargetEvent {
+    pub fn mint(&self) -> Pubkey {
+        match self {
+            TargetEvent::PumpfunBuy(trade) | TargetEvent::PumpfunSell(trade) => trade.mint,
+            TargetEvent::PumpfunCreate(create) => create.mint,
+            TargetEvent::PumpfunComplete(complete) => complete.mint,
+        }
+    }
+
+    /// 事件类型，用作指标的标签
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TargetEvent::PumpfunBuy(_) => "buy",
+            TargetEvent::PumpfunSell(_) => "sell",
+            TargetEvent::PumpfunCreate(_) => "create",
+            TargetEvent::PumpfunComplete(_) => "complete",
+        }
+    }
+}
+
+/// pump.fun 程序的自定义错误，错误码见 IDL 的 `errors`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum PumpfunError {
+    #[error("the given account is not authorized to execute this instruction")]
+    NotAuthorized = 6000,
+    #[error("the program is already initialized")]
+    AlreadyInitialized = 6001,
+    #[error("slippage: too much SOL required to buy the given amount of tokens")]
+    TooMuchSolRequired = 6002,
+    #[error("slippage: too little SOL received to sell the given amount of tokens")]
+    TooLittleSolReceived = 6003,
+    #[error("the mint does not match the bonding curve")]
+    MintDoesNotMatchBondingCurve = 6004,
+    #[error("the bonding curve has completed and liquidity migrated to raydium")]
+    BondingCurveComplete = 6005,
+    #[error("the bonding curve has not completed")]
+    BondingCurveNotComplete = 6006,
+    #[error("the program is not initialized")]
+    NotInitialized = 6007,
+    #[error("withdraw too frequent")]
+    WithdrawTooFrequent = 6008,
+}
+
+impl PumpfunError {
+    pub fn from_code(code: u32) -> Option<Self> {
+        Some(match code {
+            6000 => Self::NotAuthorized,
+            6001 => Self::AlreadyInitialized,
+            6002 => Self::TooMuchSolRequired,
+            6003 => Self::TooLittleSolReceived,
+            6004 => Self::MintDoesNotMatchBondingCurve,
+            6005 => Self::BondingCurveComplete,
+            6006 => Self::BondingCurveNotComplete,
+            6007 => Self::NotInitialized,
+            6008 => Self::WithdrawTooFrequent,
+            _ => return None,
+        })
+    }
+
+    /// 交易因为 pump.fun 指令返回自定义错误而失败时解析出错误，`programs` 为各顶层指令的程序（见 `instruction_programs`）。
+    /// 其他程序的自定义错误码和 pump.fun 的重叠，不能只看错误码
+    pub fn from_transaction_error(err: &TransactionError, programs: &[Pubkey]) -> Option<Self> {
+        match err {
+            TransactionError::InstructionError(index, InstructionError::Custom(code))
+                if programs.get(*index as usize) == Some(&PUMPFUN_PROGRAM_ID) =>
+            {
+                Self::from_code(*code)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NotAuthorized => "NotAuthorized",
+            Self::AlreadyInitialized => "AlreadyInitialized",
+            Self::TooMuchSolRequired => "TooMuchSolRequired",
+            Self::TooLittleSolReceived => "TooLittleSolReceived",
+            Self::MintDoesNotMatchBondingCurve => "MintDoesNotMatchBondingCurve",
+            Self::BondingCurveComplete => "BondingCurveComplete",
+            Self::BondingCurveNotComplete => "BondingCurveNotComplete",
+            Self::NotInitialized => "NotInitialized",
+            Self::WithdrawTooFrequent => "WithdrawTooFrequent",
+        }
+    }
+}
+
 impl TryFrom<UiInstruction> for TargetEvent {
     type Error = anyhow::Error;
 
@@ -201,6 +324,34 @@ pub fn process_tx_with_meta(
     }
 }
 
+/// gRPC 推送的交易转换成 RPC 的编码格式
+pub fn convert_to_encoded_tx(
+    tx_info: SubscribeUpdateTransactionInfo,
+) -> Result<EncodedTransactionWithStatusMeta> {
+    convert_from::create_tx_with_meta(tx_info)
+        .map_err(|e| anyhow!("{}", e))?
+        .encode(UiTransactionEncoding::Base64, Some(u8::MAX), true)
+        .map_err(|e| anyhow!("{}", e))
+}
+
+/// 解析交易中所有的 pump.fun 事件
+pub fn parse_events(tx_with_meta: &EncodedTransactionWithStatusMeta) -> Vec<TargetEvent> {
+    let mut events = vec![];
+    if let Some(meta) = &tx_with_meta.meta {
+        if let OptionSerializer::Some(inner_ixs) = &meta.inner_instructions {
+            for inner in inner_ixs {
+                for ix in &inner.instructions {
+                    if let Ok(event) = TargetEvent::try_from(ix.clone()) {
+                        metrics::EVENTS_DECODED.with_label_values(&[event.kind()]).inc();
+                        events.push(event);
+                    }
+                }
+            }
+        }
+    }
+    events
+}
+
 fn process_ixs(target: Pubkey, inner_ixs: Vec<UiInnerInstructions>) -> Option<f32> {
     for inner in inner_ixs {
         for ix in inner.instructions {
@@ -214,7 +365,7 @@ fn process_ixs(target: Pubkey, inner_ixs: Vec<UiInnerInstructions>) -> Option<f3
                             buy_event.virtual_token_reserves,
                         );
                         if buy_event.mint.eq(&target) {
-                        println!("buy_event {:?} \n ", buy_event);
+                            debug!(?buy_event, "目标买入");
 
                             return Some(price);
                         } else {
@@ -257,6 +408,24 @@ pub struct BondingCurveAccount {
     pub complete: bool,
 }
 
+/// 账户鉴别符
+pub const BONDING_CURVE_DISCRIMINATOR: [u8; 8] = [23, 183, 248, 55, 96, 216, 172, 96];
+pub const GLOBAL_DISCRIMINATOR: [u8; 8] = [167, 232, 232, 177, 200, 108, 114, 127];
+
+/// pump.fun global config account
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct GlobalAccount {
+    pub discriminator: [u8; 8],
+    pub initialized: bool,
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub fee_basis_points: u64,
+}
+
 
 /// Gets the Program Derived Address (PDA) for a token's bonding curve account
 ///
@@ -278,7 +447,8 @@ pub fn get_bonding_curve_pda(mint: &Pubkey) -> Option<Pubkey> {
 pub async fn get_pumpfun_reserve(rpc: &RpcClient, target: Pubkey) -> Option<BondingCurveAccount> {
     let pda = get_bonding_curve_pda(&target)?;
                
-    let account_data = rpc.get_account(&pda).await.expect("Failed to get account");
+    // 账户不存在（mint 不是 pump.fun 代币）时返回 None
+    let account_data = rpc.get_account(&pda).await.ok()?;
 
         match BondingCurveAccount::try_from_slice(&account_data.data) {
         Ok(bonding_curve_account) => {
@@ -300,6 +470,84 @@ async fn test_get_pumpfun_reserve() {
 }
 
 
+pub const MPL_TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+pub const MINT_AUTHORITY_SEED: &[u8] = b"mint-authority";
+
+pub fn find_mint_authority() -> Pubkey {
+    Pubkey::find_program_address(&[MINT_AUTHORITY_SEED], &PUMPFUN_PROGRAM_ID).0
+}
+
+pub fn find_metadata(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", MPL_TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &MPL_TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+#[derive(Debug, BorshSerialize, Clone, BorshDeserialize)]
+pub struct CreateArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// 创建代币和 bonding curve，`mint` 需要签名
+pub fn create_ix(mint: &Pubkey, user: &Pubkey, args: &CreateArgs) -> Result<Instruction> {
+    let bonding_curve = find_bonding_curve(mint);
+    let accounts = vec![
+        AccountMeta::new(*mint, true),
+        AccountMeta::new_readonly(find_mint_authority(), false),
+        AccountMeta::new(bonding_curve, false),
+        AccountMeta::new(get_associated_token_address(&bonding_curve, mint), false),
+        AccountMeta::new_readonly(PUMPFUN_GLOBAL, false),
+        AccountMeta::new_readonly(MPL_TOKEN_METADATA_PROGRAM_ID, false),
+        AccountMeta::new(find_metadata(mint), false),
+        AccountMeta::new(*user, true),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        AccountMeta::new_readonly(ASSOC_TOKEN_ACC_PROGRAM_ID, false),
+        AccountMeta::new_readonly(SYSTEM_RENT_PROGRAM_ID, false),
+        AccountMeta::new_readonly(EVENT_AUTHORITY, false),
+        AccountMeta::new_readonly(PUMPFUN_PROGRAM_ID, false),
+    ];
+
+    let mut data: Vec<u8> = Vec::new();
+    data.extend_from_slice(&[0x18, 0x1e, 0xc8, 0x28, 0x05, 0x1c, 0x07, 0x77]);
+    data.extend_from_slice(&borsh::to_vec(args)?);
+
+    Ok(Instruction {
+        program_id: PUMPFUN_PROGRAM_ID,
+        accounts,
+        data,
+    })
+}
+
+pub const BUY_DISCRIMINATOR: [u8; 8] = [0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea];
+pub const SELL_DISCRIMINATOR: [u8; 8] = [0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad];
+
+/// pump.fun 买卖指令的参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PumpfunOrder {
+    Buy { amount: u64, max_sol_cost: u64 },
+    Sell { amount: u64, min_sol_output: u64 },
+}
+
+/// 解析 pump.fun 指令数据，不是 buy/sell 时返回 None
+pub fn decode_order(data: &[u8]) -> Option<PumpfunOrder> {
+    if data.len() < 24 {
+        return None;
+    }
+    let amount = u64::from_le_bytes(data[8..16].try_into().unwrap());
+    let sol = u64::from_le_bytes(data[16..24].try_into().unwrap());
+    match <[u8; 8]>::try_from(&data[..8]).unwrap() {
+        BUY_DISCRIMINATOR => Some(PumpfunOrder::Buy { amount, max_sol_cost: sol }),
+        SELL_DISCRIMINATOR => Some(PumpfunOrder::Sell { amount, min_sol_output: sol }),
+        _ => None,
+    }
+}
+
 pub fn buy_amount_out_ix(
     mint: &Pubkey,
     bonding_curve: &Pubkey,
@@ -325,7 +573,7 @@ pub fn buy_amount_out_ix(
     ];
 
     let mut data: Vec<u8> = Vec::new();
-    data.extend_from_slice(&[0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea]);
+    data.extend_from_slice(&BUY_DISCRIMINATOR);
     data.extend_from_slice(&amount_out.to_le_bytes());
     data.extend_from_slice(&max_amount_in_sol.to_le_bytes());
 
@@ -361,8 +609,7 @@ pub fn sell_amount_in_ix(
     ];
 
     let mut data: Vec<u8> = Vec::new();
-    // 33e685a4017f83ad
-    data.extend_from_slice(&[0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad]);
+    data.extend_from_slice(&SELL_DISCRIMINATOR);
     data.extend_from_slice(&amount_in.to_le_bytes());
     data.extend_from_slice(&min_amount_out_sol.to_le_bytes());
 
@@ -373,38 +620,67 @@ pub fn sell_amount_in_ix(
     }
 }
 
-pub fn create_sell_transaction(
-    bonding_curve: &Pubkey,
-    price: f32,
-    mint: &Pubkey,
-    keypair: &Keypair,
-    amount_in_token: u64,
-    slippage_bps: f64,
-    recent_block_hash: Hash,
-) -> Result<Transaction> {
-    let owner = keypair.pubkey();
-
+/// 按价格和滑点计算卖出的最少收到 SOL
+pub fn min_sol_out(price: f32, amount_in_token: u64, slippage_bps: f64) -> u64 {
     let amount_out_sol = (amount_in_token as f32 * price * 1000.0) as u64;
-    let min_amount_out_sol = (amount_out_sol as f64 * (10000.0 - slippage_bps) / 10000.0) as u64;
-
-    let mut ixs: Vec<Instruction> = Vec::new();
+    (amount_out_sol as f64 * (10000.0 - slippage_bps) / 10000.0) as u64
+}
 
-    let modify_compute_units = ComputeBudgetInstruction::set_compute_unit_limit(UNIT_LIMIT);
-    let add_priority_fee = ComputeBudgetInstruction::set_compute_unit_price(UNIT_PRICE);
-    ixs.insert(0, modify_compute_units);
-    ixs.insert(1, add_priority_fee);
+/// 签名交易，第一个签名者付手续费。签名失败（例如远程签名被拒绝）时返回错误
+pub fn sign_transaction(
+    ixs: &[Instruction],
+    signers: &[&dyn Signer],
+    recent_block_hash: Hash,
+) -> Result<Transaction> {
+    let payer = signers
+        .first()
+        .ok_or_else(|| anyhow!("sign_transaction: no signers"))?
+        .try_pubkey()?;
+    let mut tx = Transaction::new_unsigned(Message::new(ixs, Some(&payer)));
+    tx.try_sign(signers, recent_block_hash)?;
+    Ok(tx)
+}
 
-    let token_ata = get_associated_token_address_with_program_id(&owner, mint, &spl_token::id());
+/// 卖出交易的参数，最少收到的 SOL 按价格和滑点计算
+#[derive(Debug, Clone, Copy)]
+pub struct SellParams<'a> {
+    pub bonding_curve: &'a Pubkey,
+    pub mint: &'a Pubkey,
+    pub price: f32,
+    pub amount_in_token: u64,
+    pub slippage_bps: f64,
+    pub budget: &'a ComputeBudget,
+}
 
-    ixs.push(sell_amount_in_ix(
+/// 卖出 `params.amount_in_token` 个 token 的指令
+fn sell_ix(params: &SellParams, owner: &Pubkey) -> Instruction {
+    let SellParams { bonding_curve, mint, price, amount_in_token, slippage_bps, .. } = *params;
+    let min_amount_out_sol = min_sol_out(price, amount_in_token, slippage_bps);
+    let token_ata = get_associated_token_address_with_program_id(owner, mint, &spl_token::id());
+    sell_amount_in_ix(
         mint,
-        &bonding_curve,
+        bonding_curve,
         &get_associated_token_address(bonding_curve, mint),
-        &owner,
+        owner,
         &token_ata,
         amount_in_token,
         min_amount_out_sol,
-    ));
+    )
+}
+
+/// 卖出并关闭 token 账户回收租金，`amount_in_token` 必须是全部持仓
+pub fn create_sell_transaction(
+    params: &SellParams,
+    signer: &dyn Signer,
+    recent_block_hash: Hash,
+) -> Result<Transaction> {
+    let owner = signer.try_pubkey()?;
+
+    let mut ixs: Vec<Instruction> = params.budget.ixs().to_vec();
+
+    let token_ata = get_associated_token_address_with_program_id(&owner, params.mint, &spl_token::id());
+
+    ixs.push(sell_ix(params, &owner));
 
     ixs.push(close_account(
         &spl_token::id(),
@@ -414,14 +690,80 @@ pub fn create_sell_transaction(
         &[],
     )?);
 
-    let tx = Transaction::new_signed_with_payer(
-        &ixs.to_vec(),
-        Some(&owner),
-        &[&keypair],
-        recent_block_hash,
-    );
+    sign_transaction(&ixs, &[signer], recent_block_hash)
+}
 
-    Ok(tx)
+/// 卖出部分持仓，保留 token 账户
+pub fn create_partial_sell_transaction(
+    params: &SellParams,
+    signer: &dyn Signer,
+    recent_block_hash: Hash,
+) -> Result<Transaction> {
+    let owner = signer.try_pubkey()?;
+    let mut ixs: Vec<Instruction> = params.budget.ixs().to_vec();
+    ixs.push(sell_ix(params, &owner));
+
+    sign_transaction(&ixs, &[signer], recent_block_hash)
+}
+
+/// 买入 `amount_out_token` 个 token，最多花费 `max_sol_cost`，token 账户不存在时创建
+pub fn create_buy_transaction(
+    bonding_curve: &Pubkey,
+    mint: &Pubkey,
+    signer: &dyn Signer,
+    amount_out_token: u64,
+    max_sol_cost: u64,
+    budget: &ComputeBudget,
+    recent_block_hash: Hash,
+) -> Result<Transaction> {
+    let owner = signer.try_pubkey()?;
+    let mut ixs = budget.ixs().to_vec();
+    ixs.extend(buy_ixs(bonding_curve, mint, &owner, amount_out_token, max_sol_cost));
+
+    sign_transaction(&ixs, &[signer], recent_block_hash)
+}
+
+/// 创建代币，`buy` 为 `(amount_out_token, max_sol_cost)` 时在同一笔交易中买入
+pub fn create_token_transaction(
+    mint: &dyn Signer,
+    signer: &dyn Signer,
+    args: &CreateArgs,
+    buy: Option<(u64, u64)>,
+    budget: &ComputeBudget,
+    recent_block_hash: Hash,
+) -> Result<Transaction> {
+    let owner = signer.try_pubkey()?;
+    let mint_key = mint.try_pubkey()?;
+    let mut ixs = budget.ixs().to_vec();
+    ixs.push(create_ix(&mint_key, &owner, args)?);
+    if let Some((amount_out_token, max_sol_cost)) = buy {
+        let bonding_curve = find_bonding_curve(&mint_key);
+        ixs.extend(buy_ixs(&bonding_curve, &mint_key, &owner, amount_out_token, max_sol_cost));
+    }
+
+    sign_transaction(&ixs, &[signer, mint], recent_block_hash)
+}
+
+fn buy_ixs(
+    bonding_curve: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount_out_token: u64,
+    max_sol_cost: u64,
+) -> [Instruction; 2] {
+    let token_ata = get_associated_token_address_with_program_id(owner, mint, &spl_token::id());
+    [
+        create_associated_token_account_idempotent(owner, owner, mint, &spl_token::id()),
+        buy_amount_out_ix(
+            mint,
+            bonding_curve,
+            &get_associated_token_address(bonding_curve, mint),
+            owner,
+            &token_ata,
+            amount_out_token,
+            max_sol_cost,
+        ),
+    ]
 }
 
 pub fn find_bonding_curve(mint: &Pubkey) -> Pubkey {
@@ -432,34 +774,20 @@ pub fn find_bonding_curve(mint: &Pubkey) -> Pubkey {
     .0
 }
 
+/// 消息中各顶层指令的程序，和 `TransactionError::InstructionError` 的指令序号一一对应
+pub fn instruction_programs(message: &Message) -> Vec<Pubkey> {
+    message.instructions.iter().map(|ix| message.account_keys[ix.program_id_index as usize]).collect()
+}
+
+/// `signer` 转账给 `to` 的交易
 pub fn transfer_tx(
-    from: &Pubkey,
     to: &Pubkey,
-    keypair: &Keypair,
+    signer: &dyn Signer,
     lamports: u64,
     blockhash: Hash,
-) -> Transaction {
-    let ix = system_instruction::transfer(from, to, lamports);
-    Transaction::new_signed_with_payer(&[ix], Some(from), &[&keypair], blockhash)
-}
-
-pub async fn send_bundle(
-    jito: &JitoJsonRpcSDK,
-    bundle: Vec<impl SerializableTransaction>,
-) -> Result<Option<String>> {
-    let mut params = vec![];
-    for tx in bundle {
-        params.push(bs58::encode(bincode::serialize(&tx)?).into_string());
-    }
-    let bundle = json!(params);
-    let result = match jito.send_bundle(Some(bundle), None).await {
-        Ok(resp) => match resp.get("result") {
-            Some(bundle_id) => Some(bundle_id.as_str().unwrap().to_string()),
-            None => None,
-        },
-        Err(_) => None,
-    };
-    Ok(result)
+) -> Result<Transaction> {
+    let ix = system_instruction::transfer(&signer.pubkey(), to, lamports);
+    sign_transaction(&[ix], &[signer], blockhash)
 }
 
 //  // 构造购买交易
@@ -480,3 +808,29 @@ pub async fn send_bundle(
 //     blockhash,
 // );
 // let bundle_id = send_bundle(&self.jito, vec![tx1, tx2]).await?;
+
+#[test]
+fn test_pumpfun_error() {
+    assert_eq!(PumpfunError::from_code(6002), Some(PumpfunError::TooMuchSolRequired));
+    assert_eq!(PumpfunError::from_code(6009), None);
+    let other = Pubkey::new_unique();
+    let programs = [other, other, other, PUMPFUN_PROGRAM_ID];
+    let err = TransactionError::InstructionError(3, InstructionError::Custom(6005));
+    assert_eq!(PumpfunError::from_transaction_error(&err, &programs), Some(PumpfunError::BondingCurveComplete));
+    // 其他程序返回相同的错误码
+    let err = TransactionError::InstructionError(2, InstructionError::Custom(6005));
+    assert_eq!(PumpfunError::from_transaction_error(&err, &programs), None);
+    let err = TransactionError::InstructionError(9, InstructionError::Custom(6005));
+    assert_eq!(PumpfunError::from_transaction_error(&err, &programs), None);
+    assert_eq!(PumpfunError::from_transaction_error(&TransactionError::AccountInUse, &programs), None);
+
+    let payer = Pubkey::new_unique();
+    let message = Message::new(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(1),
+            Instruction::new_with_bytes(PUMPFUN_PROGRAM_ID, &[], vec![]),
+        ],
+        Some(&payer),
+    );
+    assert_eq!(instruction_programs(&message), vec![solana_sdk::compute_budget::id(), PUMPFUN_PROGRAM_ID]);
+}
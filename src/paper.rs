@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tracing::warn;
+
+use crate::{
+    curve::{Curve, Quote},
+    monitor::{TargetEvent, TradeEvent},
+    pnl::{Fill, LAMPORTS_PER_SIGNATURE},
+    store::unix_now,
+};
+
+/// token 账户租金，卖出时 close_account 退回
+pub const TOKEN_ACCOUNT_RENT: u64 = 2_039_280;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaperSide {
+    /// 花费 `amount` lamports（含手续费）买入，至少得到 `min_out` 个 token
+    Buy,
+    /// 卖出 `amount` 个 token，至少收到 `min_out` lamports，卖完时关闭 token 账户
+    Sell,
+}
+
+/// 没有发送的订单，在落地 slot 结束时的曲线状态上成交
+#[derive(Debug, Clone)]
+pub struct PaperOrder {
+    pub signature: Signature,
+    pub mint: Pubkey,
+    pub side: PaperSide,
+    pub amount: u64,
+    pub min_out: u64,
+    pub tip: u64,
+    /// 交易签名数，用于计算基础手续费
+    pub signatures: usize,
+    /// 交易设置的优先费，见 `ComputeBudget::priority_fee`
+    pub priority_fee: u64,
+    pub land_slot: u64,
+}
+
+/// `--paper` 模式的模拟成交：按流跟踪每个 mint 的曲线，把订单转成和落地交易相同的 [`Fill`]
+#[derive(Debug)]
+pub struct PaperTrader {
+    wallet: Pubkey,
+    delay_slots: u64,
+    curves: HashMap<Pubkey, Curve>,
+    positions: HashMap<Pubkey, u64>,
+    pending: Vec<PaperOrder>,
+}
+
+impl PaperTrader {
+    pub fn new(wallet: Pubkey, delay_slots: u64) -> Self {
+        Self {
+            wallet,
+            delay_slots,
+            curves: HashMap::new(),
+            positions: HashMap::new(),
+            pending: vec![],
+        }
+    }
+
+    pub fn delay_slots(&self) -> u64 {
+        self.delay_slots
+    }
+
+    pub fn set_curve(&mut self, mint: Pubkey, curve: Curve) {
+        self.curves.insert(mint, curve);
+    }
+
+    pub fn curve(&self, mint: &Pubkey) -> Option<&Curve> {
+        self.curves.get(mint)
+    }
+
+    /// 模拟持仓的 token 数量
+    pub fn position(&self, mint: &Pubkey) -> u64 {
+        self.positions.get(mint).copied().unwrap_or_default()
+    }
+
+    pub fn set_position(&mut self, mint: Pubkey, tokens: u64) {
+        self.positions.insert(mint, tokens);
+    }
+
+    pub fn has_pending(&self, mint: &Pubkey) -> bool {
+        self.pending.iter().any(|order| order.mint == *mint)
+    }
+
+    pub fn submit(&mut self, order: PaperOrder) {
+        self.pending.push(order);
+    }
+
+    /// 处理流中的一笔交易：先结算落地 slot 已经结束的订单，再用交易中的成交更新曲线状态
+    pub fn on_transaction(&mut self, slot: u64, events: &[TargetEvent]) -> Vec<Fill> {
+        let (ready, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|order| order.land_slot < slot);
+        self.pending = pending;
+        let fills = ready.into_iter().filter_map(|order| self.fill(order)).collect();
+
+        for event in events {
+            if let TargetEvent::PumpfunBuy(trade) | TargetEvent::PumpfunSell(trade) = event {
+                self.curves.insert(trade.mint, Curve::from(trade));
+            }
+        }
+        fills
+    }
+
+    /// 按当前曲线状态结算所有挂单，用于退出前
+    pub fn settle_all(&mut self) -> Vec<Fill> {
+        std::mem::take(&mut self.pending)
+            .into_iter()
+            .filter_map(|order| self.fill(order))
+            .collect()
+    }
+
+    fn fill(&mut self, order: PaperOrder) -> Option<Fill> {
+        let Some(curve) = self.curves.get_mut(&order.mint) else {
+            warn!(mint = %order.mint, "paper 没有曲线状态，丢弃订单");
+            return None;
+        };
+        let base_fee = LAMPORTS_PER_SIGNATURE * order.signatures as u64;
+        let priority_fee = order.priority_fee;
+        let fees = (base_fee + priority_fee) as i64;
+
+        let (quote, filled) = match order.side {
+            PaperSide::Buy => {
+                let quote = curve.buy_with_sol(order.amount);
+                (quote, quote.tokens > 0 && quote.tokens >= order.min_out)
+            }
+            PaperSide::Sell => {
+                let quote = curve.sell(order.amount);
+                (quote, quote.tokens > 0 && quote.sol_out() >= order.min_out)
+            }
+        };
+        // 滑点超出时交易失败，只扣手续费
+        if !filled {
+            return Some(Fill {
+                signature: order.signature,
+                slot: order.land_slot,
+                success: false,
+                mint: Some(order.mint),
+                trades: vec![],
+                sol_change: -fees,
+                base_fee,
+                priority_fee,
+                jito_tip: 0,
+                rent_reclaimed: 0,
+                sol_usd: None,
+            });
+        }
+
+        let position = self.positions.entry(order.mint).or_default();
+        let (sol_change, rent_reclaimed) = match order.side {
+            PaperSide::Buy => {
+                curve.apply_buy(&quote);
+                *position += quote.tokens;
+                (-(quote.sol_in() as i64), 0)
+            }
+            PaperSide::Sell => {
+                curve.apply_sell(&quote);
+                *position = position.saturating_sub(quote.tokens);
+                let rent = if *position == 0 { TOKEN_ACCOUNT_RENT } else { 0 };
+                (quote.sol_out() as i64 + rent as i64, rent)
+            }
+        };
+        Some(Fill {
+            signature: order.signature,
+            slot: order.land_slot,
+            success: true,
+            mint: Some(order.mint),
+            trades: vec![trade_event(self.wallet, &order, &quote, curve)],
+            sol_change: sol_change - fees - order.tip as i64,
+            base_fee,
+            priority_fee,
+            jito_tip: order.tip,
+            rent_reclaimed,
+            sol_usd: None,
+        })
+    }
+}
+
+/// 模拟成交对应的事件，曲线为成交后的状态
+fn trade_event(wallet: Pubkey, order: &PaperOrder, quote: &Quote, curve: &Curve) -> TradeEvent {
+    TradeEvent {
+        mint: order.mint,
+        sol_amount: quote.sol,
+        token_amount: quote.tokens,
+        is_buy: order.side == PaperSide::Buy,
+        user: wallet,
+        timestamp: unix_now(),
+        virtual_sol_reserves: curve.virtual_sol_reserves,
+        virtual_token_reserves: curve.virtual_token_reserves,
+        real_sol_reserves: curve.real_sol_reserves,
+        real_token_reserves: curve.real_token_reserves,
+    }
+}
+
+#[test]
+fn test_paper_sell_fills_at_landing_slot() {
+    use crate::{
+        monitor::{ComputeBudget, INIT_SOL_REVERSES, INIT_TOKEN_REVERSES},
+        pnl::PnlLedger,
+    };
+
+    let wallet = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let mut curve = Curve {
+        virtual_sol_reserves: INIT_SOL_REVERSES,
+        virtual_token_reserves: INIT_TOKEN_REVERSES,
+        real_sol_reserves: 0,
+        real_token_reserves: 793_100_000_000_000,
+    };
+    let trade = |curve: &mut Curve, sol_in: u64| {
+        let quote = curve.buy_with_sol(sol_in);
+        curve.apply_buy(&quote);
+        TargetEvent::PumpfunBuy(TradeEvent {
+            mint,
+            sol_amount: quote.sol,
+            token_amount: quote.tokens,
+            is_buy: true,
+            user: Pubkey::new_unique(),
+            timestamp: 0,
+            virtual_sol_reserves: curve.virtual_sol_reserves,
+            virtual_token_reserves: curve.virtual_token_reserves,
+            real_sol_reserves: curve.real_sol_reserves,
+            real_token_reserves: curve.real_token_reserves,
+        })
+    };
+
+    let mut paper = PaperTrader::new(wallet, 1);
+    let tokens = curve.buy_with_sol(1_000_000_000).tokens;
+    paper.set_position(mint, tokens);
+    assert!(paper.on_transaction(10, &[trade(&mut curve, 5_000_000_000)]).is_empty());
+
+    paper.submit(PaperOrder {
+        signature: Signature::new_unique(),
+        mint,
+        side: PaperSide::Sell,
+        amount: tokens,
+        min_out: 0,
+        tip: 10_000,
+        signatures: 1,
+        priority_fee: ComputeBudget::default().priority_fee(),
+        land_slot: 11,
+    });
+    // 落地 slot 内的成交先于自己的订单
+    assert!(paper.on_transaction(11, &[trade(&mut curve, 2_000_000_000)]).is_empty());
+    let expected = curve.sell(tokens);
+    let fills = paper.on_transaction(12, &[]);
+    assert_eq!(fills.len(), 1);
+    let fill = &fills[0];
+    assert!(fill.success);
+    assert_eq!(fill.slot, 11);
+    assert_eq!(
+        fill.sol_change,
+        expected.sol_out() as i64 + TOKEN_ACCOUNT_RENT as i64
+            - (fill.base_fee + fill.priority_fee + fill.jito_tip) as i64
+    );
+    assert_eq!(paper.position(&mint), 0);
+
+    let mut ledger = PnlLedger::new();
+    ledger.record(fill.clone());
+    assert_eq!(ledger.mint(&mint).unwrap().tokens_sold, tokens);
+}
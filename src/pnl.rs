@@ -0,0 +1,496 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{anyhow, Result};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+    system_instruction::SystemInstruction, system_program,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedTransactionWithStatusMeta, UiTransactionEncoding,
+};
+
+use crate::{
+    monitor::{TargetEvent, TradeEvent},
+    utils::lamports_to_usd,
+};
+
+/// 每个签名的基础手续费
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+/// spl-token CloseAccount 指令序号
+const CLOSE_ACCOUNT_IX: u8 = 9;
+
+/// 自己落地的一笔交易，按 lamports 去向拆分
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub signature: Signature,
+    pub slot: u64,
+    pub success: bool,
+    /// 交易所属的 mint，tip 和失败的交易没有成交事件，由下单时的 mint 给出
+    pub mint: Option<Pubkey>,
+    /// 属于自己钱包的 pump.fun 成交
+    pub trades: Vec<TradeEvent>,
+    /// 钱包 SOL 实际变化 (post - pre)
+    pub sol_change: i64,
+    pub base_fee: u64,
+    pub priority_fee: u64,
+    pub jito_tip: u64,
+    /// close_account 退回的租金
+    pub rent_reclaimed: u64,
+    /// 交易时的 SOL/USD 价格
+    pub sol_usd: Option<f64>,
+}
+
+impl Fill {
+    pub fn with_sol_usd(mut self, sol_usd: Option<f64>) -> Self {
+        self.sol_usd = sol_usd;
+        self
+    }
+
+    pub fn with_mint(mut self, mint: Pubkey) -> Self {
+        self.mint = Some(mint);
+        self
+    }
+
+    /// 按交易时价格换算的美元变化
+    pub fn usd_change(&self) -> Option<f64> {
+        self.sol_usd.map(|price| lamports_to_usd(self.sol_change, price))
+    }
+
+    pub fn from_encoded(
+        signature: Signature,
+        slot: u64,
+        wallet: &Pubkey,
+        tx: &EncodedTransactionWithStatusMeta,
+        tip_accounts: &[Pubkey],
+    ) -> Result<Fill> {
+        let meta = tx.meta.as_ref().ok_or_else(|| anyhow!("fill: missing meta"))?;
+        let decoded = tx
+            .transaction
+            .decode()
+            .ok_or_else(|| anyhow!("fill: failed to decode transaction"))?;
+
+        let mut keys: Vec<Pubkey> = decoded.message.static_account_keys().to_vec();
+        if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+            for key in loaded.writable.iter().chain(loaded.readonly.iter()) {
+                keys.push(key.parse()?);
+            }
+        }
+        let wallet_index = keys
+            .iter()
+            .position(|key| key == wallet)
+            .ok_or_else(|| anyhow!("fill: wallet {} not in transaction", wallet))?;
+        let sol_change =
+            meta.post_balances[wallet_index] as i64 - meta.pre_balances[wallet_index] as i64;
+
+        let base_fee = LAMPORTS_PER_SIGNATURE * decoded.signatures.len() as u64;
+        let priority_fee = meta.fee.saturating_sub(base_fee);
+
+        let mut jito_tip = 0;
+        let mut rent_reclaimed = 0;
+        for ix in decoded.message.instructions() {
+            let program_id = keys[ix.program_id_index as usize];
+            let account = |i: usize| ix.accounts.get(i).map(|idx| *idx as usize);
+            if program_id == system_program::id() {
+                if let Ok(SystemInstruction::Transfer { lamports }) = bincode::deserialize(&ix.data) {
+                    let (Some(from), Some(to)) = (account(0), account(1)) else {
+                        continue;
+                    };
+                    if from == wallet_index && tip_accounts.contains(&keys[to]) {
+                        jito_tip += lamports;
+                    }
+                }
+            } else if program_id == spl_token::id() && ix.data.first() == Some(&CLOSE_ACCOUNT_IX) {
+                let (Some(closed), Some(dest)) = (account(0), account(1)) else {
+                    continue;
+                };
+                if dest == wallet_index && meta.err.is_none() {
+                    rent_reclaimed += meta.pre_balances[closed];
+                }
+            }
+        }
+
+        let mut trades = vec![];
+        if let OptionSerializer::Some(inner_ixs) = &meta.inner_instructions {
+            for inner in inner_ixs {
+                for ix in &inner.instructions {
+                    match TargetEvent::try_from(ix.clone()) {
+                        Ok(TargetEvent::PumpfunBuy(trade)) | Ok(TargetEvent::PumpfunSell(trade))
+                            if trade.user == *wallet =>
+                        {
+                            trades.push(trade)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(Fill {
+            signature,
+            slot,
+            success: meta.err.is_none(),
+            mint: trades.first().map(|trade| trade.mint),
+            trades,
+            sol_change,
+            base_fee,
+            priority_fee,
+            jito_tip,
+            rent_reclaimed,
+            sol_usd: None,
+        })
+    }
+}
+
+/// 通过 getTransaction 拉取交易并解析，交易刚落地时 RPC 可能还查不到，会重试几次
+pub async fn fetch_fill(
+    rpc: &RpcClient,
+    signature: &Signature,
+    wallet: &Pubkey,
+    tip_accounts: &[Pubkey],
+) -> Result<Fill> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let mut attempts = 0;
+    loop {
+        match rpc.get_transaction_with_config(signature, config).await {
+            Ok(tx) => {
+                return Fill::from_encoded(*signature, tx.slot, wallet, &tx.transaction, tip_accounts)
+            }
+            Err(e) if attempts >= 10 => return Err(e.into()),
+            Err(_) => {
+                attempts += 1;
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+/// 单个 mint 的累计盈亏
+#[derive(Debug, Clone, Default)]
+pub struct MintPnl {
+    pub tokens_bought: u64,
+    pub tokens_sold: u64,
+    /// 买入交易的钱包 SOL 支出（含手续费）
+    pub sol_spent: u64,
+    /// 卖出交易的钱包 SOL 收入（已扣手续费，含退回租金）
+    pub sol_received: u64,
+    pub fees: u64,
+    pub tips: u64,
+    pub rent_reclaimed: u64,
+    /// 按每笔交易时的价格换算
+    pub usd_spent: f64,
+    pub usd_received: f64,
+}
+
+impl MintPnl {
+    pub fn realized(&self) -> i64 {
+        self.sol_received as i64 - self.sol_spent as i64
+    }
+
+    pub fn realized_usd(&self) -> f64 {
+        self.usd_received - self.usd_spent
+    }
+
+    pub fn open_tokens(&self) -> u64 {
+        self.tokens_bought.saturating_sub(self.tokens_sold)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionPnl {
+    pub fills: usize,
+    pub failed: usize,
+    pub sol_change: i64,
+    pub fees: u64,
+    pub tips: u64,
+    pub rent_reclaimed: u64,
+    pub usd_change: f64,
+    /// 没有 SOL/USD 价格、未计入 `usd_change` 的成交数
+    pub unpriced: usize,
+}
+
+/// 按落地成交统计的单笔和本次运行盈亏
+#[derive(Debug, Default)]
+pub struct PnlLedger {
+    fills: Vec<Fill>,
+    mints: HashMap<Pubkey, MintPnl>,
+    session: SessionPnl,
+}
+
+impl PnlLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, fill: Fill) {
+        let fees = fill.base_fee + fill.priority_fee;
+        self.session.fills += 1;
+        if !fill.success {
+            self.session.failed += 1;
+        }
+        self.session.sol_change += fill.sol_change;
+        self.session.fees += fees;
+        self.session.tips += fill.jito_tip;
+        self.session.rent_reclaimed += fill.rent_reclaimed;
+        let usd_change = fill.usd_change();
+        match usd_change {
+            Some(usd) => self.session.usd_change += usd,
+            None => self.session.unpriced += 1,
+        }
+
+        // tip 和失败的交易也计入所属 mint 的支出
+        if let Some(mint) = fill.mint {
+            let mint = self.mints.entry(mint).or_default();
+            for trade in &fill.trades {
+                if trade.is_buy {
+                    mint.tokens_bought += trade.token_amount;
+                } else {
+                    mint.tokens_sold += trade.token_amount;
+                }
+            }
+            let usd = usd_change.unwrap_or_default();
+            if fill.sol_change < 0 {
+                mint.sol_spent += fill.sol_change.unsigned_abs();
+                mint.usd_spent -= usd;
+            } else {
+                mint.sol_received += fill.sol_change as u64;
+                mint.usd_received += usd;
+            }
+            mint.fees += fees;
+            mint.tips += fill.jito_tip;
+            mint.rent_reclaimed += fill.rent_reclaimed;
+        }
+        self.fills.push(fill);
+    }
+
+    pub fn fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    pub fn mint(&self, mint: &Pubkey) -> Option<&MintPnl> {
+        self.mints.get(mint)
+    }
+
+    pub fn mints(&self) -> &HashMap<Pubkey, MintPnl> {
+        &self.mints
+    }
+
+    pub fn session(&self) -> &SessionPnl {
+        &self.session
+    }
+}
+
+/// 测试用的已落地卖出交易：通过地址查找表引用 tip 账户，卖出后关闭 token 账户。
+/// 返回编码后的交易和 token 账户的租金
+#[cfg(test)]
+fn encoded_sell_fixture(
+    wallet: &solana_sdk::signature::Keypair,
+    tip_account: Pubkey,
+    tip: u64,
+    err: Option<solana_sdk::transaction::TransactionError>,
+) -> (EncodedTransactionWithStatusMeta, u64) {
+    use solana_sdk::{
+        address_lookup_table::AddressLookupTableAccount,
+        compute_budget::ComputeBudgetInstruction,
+        hash::Hash,
+        instruction::{AccountMeta, CompiledInstruction, Instruction},
+        message::{v0, v0::LoadedAddresses, VersionedMessage},
+        signer::Signer,
+        system_instruction,
+        transaction::VersionedTransaction,
+    };
+    use solana_transaction_status::{
+        InnerInstruction, InnerInstructions, TransactionStatusMeta, VersionedTransactionWithStatusMeta,
+    };
+
+    use crate::monitor::{PUMPFUN_PROGRAM_ID, PUMPFUN_TRADE_EVENT};
+
+    let owner = wallet.pubkey();
+    let token_account = Pubkey::new_unique();
+    let ixs = [
+        ComputeBudgetInstruction::set_compute_unit_price(10_000),
+        Instruction::new_with_bytes(PUMPFUN_PROGRAM_ID, &[0], vec![AccountMeta::new(owner, true)]),
+        system_instruction::transfer(&owner, &tip_account, tip),
+        spl_token::instruction::close_account(&spl_token::id(), &token_account, &owner, &owner, &[]).unwrap(),
+    ];
+    let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: vec![tip_account] };
+    let message = v0::Message::try_compile(&owner, &ixs, &[table], Hash::new_unique()).unwrap();
+    let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[wallet]).unwrap();
+
+    // 静态账户之后是查找表加载的 tip 账户
+    let keys = tx.message.static_account_keys();
+    assert!(!keys.contains(&tip_account));
+    let index = |key: &Pubkey| keys.iter().position(|k| k == key).unwrap();
+    let rent = 2_039_280;
+    let fee = LAMPORTS_PER_SIGNATURE + 2_000;
+    let sol_out = 50_000_000;
+    let mut pre_balances = vec![0; keys.len() + 1];
+    pre_balances[index(&owner)] = 1_000_000_000;
+    pre_balances[index(&token_account)] = rent;
+    let mut post_balances = pre_balances.clone();
+    post_balances[index(&token_account)] = 0;
+    post_balances[index(&owner)] = match err {
+        None => 1_000_000_000 + sol_out + rent - fee - tip,
+        Some(_) => 1_000_000_000 - fee,
+    };
+
+    // 自己的成交和同一笔交易中其他用户的成交
+    let trade = |user: Pubkey| {
+        let event = TradeEvent {
+            mint: Pubkey::new_unique(),
+            sol_amount: sol_out,
+            token_amount: 1_000_000,
+            is_buy: false,
+            user,
+            timestamp: 0,
+            virtual_sol_reserves: 0,
+            virtual_token_reserves: 0,
+            real_sol_reserves: 0,
+            real_token_reserves: 0,
+        };
+        let mut data = vec![0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d];
+        data.extend_from_slice(&PUMPFUN_TRADE_EVENT);
+        data.extend(borsh::to_vec(&event).unwrap());
+        InnerInstruction {
+            instruction: CompiledInstruction::new_from_raw_parts(index(&PUMPFUN_PROGRAM_ID) as u8, data, vec![]),
+            stack_height: Some(2),
+        }
+    };
+    let meta = TransactionStatusMeta {
+        status: err.clone().map_or(Ok(()), Err),
+        fee,
+        pre_balances,
+        post_balances,
+        inner_instructions: Some(vec![InnerInstructions {
+            index: 1,
+            instructions: match err {
+                None => vec![trade(owner), trade(Pubkey::new_unique())],
+                Some(_) => vec![],
+            },
+        }]),
+        loaded_addresses: LoadedAddresses { writable: vec![tip_account], readonly: vec![] },
+        ..Default::default()
+    };
+    let encoded = VersionedTransactionWithStatusMeta { transaction: tx, meta }
+        .encode(UiTransactionEncoding::Base64, Some(0), false)
+        .unwrap();
+    (encoded, rent)
+}
+
+#[test]
+fn test_fill_from_encoded() {
+    use solana_sdk::{instruction::InstructionError, signature::Keypair, signer::Signer, transaction::TransactionError};
+
+    let wallet = Keypair::new();
+    let tip_account = Pubkey::new_unique();
+    let signature = Signature::new_unique();
+
+    let (encoded, rent) = encoded_sell_fixture(&wallet, tip_account, 100_000, None);
+    let fill = Fill::from_encoded(signature, 7, &wallet.pubkey(), &encoded, &[tip_account]).unwrap();
+    assert!(fill.success);
+    assert_eq!(fill.slot, 7);
+    assert_eq!((fill.base_fee, fill.priority_fee), (LAMPORTS_PER_SIGNATURE, 2_000));
+    // tip 账户只在查找表中，需要加上 loaded addresses 才能识别
+    assert_eq!(fill.jito_tip, 100_000);
+    assert_eq!(fill.rent_reclaimed, rent);
+    assert_eq!(fill.sol_change, 50_000_000 + rent as i64 - 7_000 - 100_000);
+    assert_eq!(fill.trades.len(), 1);
+    assert_eq!(fill.trades[0].user, wallet.pubkey());
+    assert_eq!(fill.mint, Some(fill.trades[0].mint));
+
+    // 转给非 tip 账户的不算 tip
+    let fill = Fill::from_encoded(signature, 7, &wallet.pubkey(), &encoded, &[Pubkey::new_unique()]).unwrap();
+    assert_eq!(fill.jito_tip, 0);
+
+    // 失败的交易没有退回租金，只扣手续费
+    let err = TransactionError::InstructionError(1, InstructionError::Custom(6003));
+    let (encoded, _) = encoded_sell_fixture(&wallet, tip_account, 100_000, Some(err));
+    let fill = Fill::from_encoded(signature, 7, &wallet.pubkey(), &encoded, &[tip_account]).unwrap();
+    assert!(!fill.success);
+    assert_eq!(fill.rent_reclaimed, 0);
+    assert_eq!(fill.sol_change, -7_000);
+    assert!(fill.trades.is_empty());
+    assert_eq!(fill.mint, None);
+
+    // 钱包不在交易中
+    assert!(Fill::from_encoded(signature, 7, &Pubkey::new_unique(), &encoded, &[tip_account]).is_err());
+}
+
+#[tokio::test]
+async fn test_fetch_fill() {
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{signature::Keypair, signer::Signer};
+    use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+    let wallet = Keypair::new();
+    let tip_account = Pubkey::new_unique();
+    let (transaction, rent) = encoded_sell_fixture(&wallet, tip_account, 100_000, None);
+    let confirmed = EncodedConfirmedTransactionWithStatusMeta { slot: 42, transaction, block_time: None };
+    let mocks = HashMap::from([(RpcRequest::GetTransaction, serde_json::to_value(confirmed).unwrap())]);
+    let rpc = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+
+    let signature = Signature::new_unique();
+    let fill = fetch_fill(&rpc, &signature, &wallet.pubkey(), &[tip_account]).await.unwrap();
+    assert_eq!(fill.signature, signature);
+    assert_eq!(fill.slot, 42);
+    assert_eq!((fill.jito_tip, fill.rent_reclaimed), (100_000, rent));
+}
+
+#[test]
+fn test_ledger_tip_fill() {
+    let mint = Pubkey::new_unique();
+    let sell = Fill {
+        signature: Signature::new_unique(),
+        slot: 1,
+        success: true,
+        mint: Some(mint),
+        trades: vec![TradeEvent {
+            mint,
+            sol_amount: 50_000_000,
+            token_amount: 1_000_000,
+            is_buy: false,
+            user: Pubkey::new_unique(),
+            timestamp: 0,
+            virtual_sol_reserves: 0,
+            virtual_token_reserves: 0,
+            real_sol_reserves: 0,
+            real_token_reserves: 0,
+        }],
+        sol_change: 50_000_000 - 5_000,
+        base_fee: 5_000,
+        priority_fee: 0,
+        jito_tip: 0,
+        rent_reclaimed: 0,
+        sol_usd: None,
+    };
+    // 单独发送的 tip 交易没有成交事件，按下单的 mint 记账
+    let tip = Fill {
+        signature: Signature::new_unique(),
+        slot: 1,
+        success: true,
+        mint: None,
+        trades: vec![],
+        sol_change: -(100_000 + 5_000),
+        base_fee: 5_000,
+        priority_fee: 0,
+        jito_tip: 100_000,
+        rent_reclaimed: 0,
+        sol_usd: None,
+    };
+    let mut ledger = PnlLedger::new();
+    ledger.record(sell);
+    // 没有 mint 的交易只计入本次运行
+    ledger.record(tip.clone());
+    ledger.record(tip.with_mint(mint));
+
+    let pnl = ledger.mint(&mint).unwrap();
+    assert_eq!(pnl.tips, 100_000);
+    assert_eq!(pnl.fees, 10_000);
+    assert_eq!(pnl.realized(), 50_000_000 - 5_000 - 105_000);
+    assert_eq!(ledger.session().tips, 200_000);
+    assert_eq!(ledger.session().fills, 3);
+}
@@ -0,0 +1,472 @@
+use std::{
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use borsh::BorshDeserialize;
+use futures_util::StreamExt;
+use pyth_sdk_solana::state::SolanaPriceAccount;
+use serde_json::Value;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use thiserror::Error;
+use tracing::warn;
+use yellowstone_grpc_proto::{geyser::CommitmentLevel, prelude::subscribe_update::UpdateOneof};
+
+use crate::{grpc::GrpcClient, utils::lamports_to_usd};
+
+/// Pyth pull oracle SOL/USD `PriceUpdateV2` 账户 (shard 0)
+pub const PYTH_SOL_USD_V2: &str = "7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE";
+/// `PriceUpdateV2` 账户鉴别符
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+#[derive(Debug, Error)]
+pub enum PriceError {
+    #[error("{feed}: price is stale, published {age}s ago (max {max_age}s)")]
+    Stale { feed: String, age: i64, max_age: i64 },
+    #[error("{feed}: confidence ratio {ratio:.4} exceeds max {max_ratio}")]
+    Uncertain {
+        feed: String,
+        ratio: f64,
+        max_ratio: f64,
+    },
+    #[error("{0}: invalid price {1}")]
+    Invalid(String, f64),
+    #[error("{0}: failed to decode price account: {1}")]
+    Decode(String, String),
+    #[error("{0}: request failed: {1}")]
+    Request(String, String),
+    #[error("no price source available")]
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedKind {
+    /// 旧版 push oracle 价格账户
+    Legacy,
+    /// pull oracle 的 `PriceUpdateV2` 账户
+    PriceUpdateV2,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PriceFeed {
+    pub key: Pubkey,
+    pub kind: FeedKind,
+}
+
+impl FromStr for PriceFeed {
+    type Err = anyhow::Error;
+
+    /// `v2:<pubkey>` 为 `PriceUpdateV2` 账户，否则按旧版价格账户处理
+    fn from_str(s: &str) -> Result<Self> {
+        match s.strip_prefix("v2:") {
+            Some(key) => Ok(Self {
+                key: key.parse()?,
+                kind: FeedKind::PriceUpdateV2,
+            }),
+            None => Ok(Self {
+                key: s.strip_prefix("legacy:").unwrap_or(s).parse()?,
+                kind: FeedKind::Legacy,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+enum VerificationLevel {
+    Partial { _num_signatures: u8 },
+    Full,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+struct PriceFeedMessage {
+    _feed_id: [u8; 32],
+    price: i64,
+    conf: u64,
+    exponent: i32,
+    publish_time: i64,
+    _prev_publish_time: i64,
+    _ema_price: i64,
+    _ema_conf: u64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+struct PriceUpdateV2 {
+    _write_authority: Pubkey,
+    verification_level: VerificationLevel,
+    price_message: PriceFeedMessage,
+    _posted_slot: u64,
+}
+
+/// 一次读取到的美元报价
+#[derive(Debug, Clone)]
+pub struct UsdQuote {
+    pub price: f64,
+    pub conf: f64,
+    pub publish_time: i64,
+    pub source: String,
+}
+
+/// 备用价格源，所有 Pyth feed 都不合格时查询
+#[derive(Debug, Clone)]
+pub struct HttpPrice {
+    pub url: String,
+    /// JSON pointer，例如 `/price`
+    pub pointer: String,
+}
+
+impl HttpPrice {
+    pub async fn fetch(&self) -> Result<UsdQuote, PriceError> {
+        let request_error = |e: reqwest::Error| PriceError::Request(self.url.clone(), e.to_string());
+        let body: Value = reqwest::get(&self.url)
+            .await
+            .map_err(request_error)?
+            .json()
+            .await
+            .map_err(request_error)?;
+        // 有些接口把价格返回成字符串
+        let price = match body.pointer(&self.pointer) {
+            Some(Value::Number(n)) => n.as_f64(),
+            Some(Value::String(s)) => s.parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| PriceError::Decode(self.url.clone(), format!("no price at {}", self.pointer)))?;
+        if price.is_nan() || price <= 0.0 {
+            return Err(PriceError::Invalid(self.url.clone(), price));
+        }
+        Ok(UsdQuote {
+            price,
+            conf: 0.0,
+            publish_time: unix_now(),
+            source: self.url.clone(),
+        })
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// 从 Pyth feed 读取 SOL/USD，拒绝过期或置信区间过大的价格
+#[derive(Debug, Clone)]
+pub struct PriceOracle {
+    pub feeds: Vec<PriceFeed>,
+    /// 发布时间距今的最大秒数
+    pub max_age: i64,
+    /// conf / price 的最大比例
+    pub max_conf_ratio: f64,
+    pub fallback: Option<HttpPrice>,
+}
+
+impl Default for PriceOracle {
+    fn default() -> Self {
+        Self {
+            feeds: vec![
+                PriceFeed {
+                    key: PYTH_SOL_USD_V2.parse().unwrap(),
+                    kind: FeedKind::PriceUpdateV2,
+                },
+                PriceFeed {
+                    key: crate::utils::PYTH_SOL_USD.parse().unwrap(),
+                    kind: FeedKind::Legacy,
+                },
+            ],
+            max_age: 60,
+            max_conf_ratio: 0.01,
+            fallback: None,
+        }
+    }
+}
+
+impl PriceOracle {
+    /// 解析价格账户并检查时效和置信区间
+    pub fn decode(
+        &self,
+        feed: &PriceFeed,
+        account: &mut Account,
+        now: i64,
+    ) -> Result<UsdQuote, PriceError> {
+        let name = feed.key.to_string();
+        let (price, conf, expo, publish_time) = match feed.kind {
+            FeedKind::Legacy => {
+                let price_feed = SolanaPriceAccount::account_to_feed(&feed.key, account)
+                    .map_err(|e| PriceError::Decode(name.clone(), format!("{:?}", e)))?;
+                let price = price_feed.get_price_unchecked();
+                (price.price, price.conf, price.expo, price.publish_time)
+            }
+            FeedKind::PriceUpdateV2 => {
+                let data = &account.data;
+                if data.len() < 8 || data[..8] != PRICE_UPDATE_V2_DISCRIMINATOR {
+                    return Err(PriceError::Decode(name, "not a PriceUpdateV2 account".into()));
+                }
+                let update = PriceUpdateV2::deserialize(&mut &data[8..])
+                    .map_err(|e| PriceError::Decode(name.clone(), e.to_string()))?;
+                if !matches!(update.verification_level, VerificationLevel::Full) {
+                    return Err(PriceError::Decode(name, "price update is not fully verified".into()));
+                }
+                let msg = update.price_message;
+                (msg.price, msg.conf, msg.exponent, msg.publish_time)
+            }
+        };
+
+        let scale = 10f64.powi(expo);
+        let quote = UsdQuote {
+            price: price as f64 * scale,
+            conf: conf as f64 * scale,
+            publish_time,
+            source: name,
+        };
+        self.check(quote, now)
+    }
+
+    pub fn check(&self, quote: UsdQuote, now: i64) -> Result<UsdQuote, PriceError> {
+        if quote.price.is_nan() || quote.price <= 0.0 {
+            return Err(PriceError::Invalid(quote.source, quote.price));
+        }
+        let age = now - quote.publish_time;
+        if age > self.max_age {
+            return Err(PriceError::Stale {
+                feed: quote.source,
+                age,
+                max_age: self.max_age,
+            });
+        }
+        let ratio = quote.conf / quote.price;
+        if ratio > self.max_conf_ratio {
+            return Err(PriceError::Uncertain {
+                feed: quote.source,
+                ratio,
+                max_ratio: self.max_conf_ratio,
+            });
+        }
+        Ok(quote)
+    }
+
+    /// 依次尝试各个 feed，都不合格时使用备用价格源，返回最后一个错误
+    pub async fn fetch(&self, rpc: &RpcClient) -> Result<UsdQuote, PriceError> {
+        let keys: Vec<Pubkey> = self.feeds.iter().map(|feed| feed.key).collect();
+        let mut last_err = PriceError::Unavailable;
+        match rpc.get_multiple_accounts(&keys).await {
+            Ok(accounts) => {
+                let now = unix_now();
+                for (feed, account) in self.feeds.iter().zip(accounts) {
+                    let Some(mut account) = account else {
+                        last_err = PriceError::Decode(feed.key.to_string(), "account not found".into());
+                        continue;
+                    };
+                    match self.decode(feed, &mut account, now) {
+                        Ok(quote) => return Ok(quote),
+                        Err(e) => last_err = e,
+                    }
+                }
+            }
+            Err(e) => last_err = PriceError::Request("rpc".into(), e.to_string()),
+        }
+        match &self.fallback {
+            Some(fallback) => fallback
+                .fetch()
+                .await
+                .inspect_err(|_| warn!("Pyth 价格都不可用，使用备用价格源: {}", last_err)),
+            None => Err(last_err),
+        }
+    }
+}
+
+/// 带更新 slot 的 SOL/USD 价格
+#[derive(Debug, Clone, Copy)]
+pub struct SolUsd {
+    pub price: f64,
+    pub slot: u64,
+    pub publish_time: i64,
+}
+
+/// 最新的 SOL/USD 价格，通过 Geyser 订阅 Pyth 账户更新
+#[derive(Clone)]
+pub struct SolUsdTracker {
+    inner: Arc<RwLock<Option<SolUsd>>>,
+    oracle: Arc<PriceOracle>,
+}
+
+impl SolUsdTracker {
+    pub fn new(oracle: PriceOracle) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(None)),
+            oracle: Arc::new(oracle),
+        }
+    }
+
+    /// 最新价格，超过 `max_age` 没有更新时返回 None
+    pub fn get(&self) -> Option<SolUsd> {
+        let price = (*self.inner.read().unwrap())?;
+        (unix_now() - price.publish_time <= self.oracle.max_age).then_some(price)
+    }
+
+    pub fn price(&self) -> Option<f64> {
+        self.get().map(|p| p.price)
+    }
+
+    pub fn set(&self, price: SolUsd) {
+        let mut inner = self.inner.write().unwrap();
+        // 只接受更新的报价
+        if inner.is_none_or(|current| price.publish_time >= current.publish_time) {
+            *inner = Some(price);
+        }
+    }
+
+    pub fn to_usd(&self, lamports: i64) -> Option<f64> {
+        self.price().map(|price| lamports_to_usd(lamports, price))
+    }
+
+    /// 先用 RPC 取一次初始价格，然后订阅 Pyth 账户更新，断线后自动重连；
+    /// 价格过期时通过 RPC 和备用价格源补齐
+    pub async fn spawn(self, rpc: Arc<RpcClient>, grpc: GrpcClient) -> Result<Self> {
+        self.refresh(&rpc).await?;
+
+        let handle = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = handle.follow(&grpc).await {
+                    warn!("SOL 价格订阅错误: {:?}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        });
+
+        let handle = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(10));
+            loop {
+                ticker.tick().await;
+                if handle.get().is_none() {
+                    if let Err(e) = handle.refresh(&rpc).await {
+                        warn!("刷新 SOL 价格失败: {}", e);
+                    }
+                }
+            }
+        });
+        Ok(self)
+    }
+
+    async fn refresh(&self, rpc: &RpcClient) -> Result<(), PriceError> {
+        let quote = self.oracle.fetch(rpc).await?;
+        self.set(SolUsd {
+            price: quote.price,
+            slot: 0,
+            publish_time: quote.publish_time,
+        });
+        Ok(())
+    }
+
+    async fn follow(&self, grpc: &GrpcClient) -> Result<()> {
+        let feeds = &self.oracle.feeds;
+        let mut stream = grpc
+            .subscribe_account(
+                feeds.iter().map(|feed| feed.key.to_string()).collect(),
+                CommitmentLevel::Processed,
+            )
+            .await?;
+        while let Some(update) = stream.next().await {
+            let Some(UpdateOneof::Account(update)) = update?.update_oneof else {
+                continue;
+            };
+            let info = update.account.ok_or_else(|| anyhow!("account update without info"))?;
+            let Some(feed) = feeds.iter().find(|feed| feed.key.as_ref() == info.pubkey.as_slice())
+            else {
+                continue;
+            };
+            let mut account = Account {
+                lamports: info.lamports,
+                data: info.data,
+                owner: Pubkey::try_from(info.owner.as_slice())
+                    .map_err(|_| anyhow!("invalid account owner"))?,
+                executable: info.executable,
+                rent_epoch: info.rent_epoch,
+            };
+            match self.oracle.decode(feed, &mut account, unix_now()) {
+                Ok(quote) => self.set(SolUsd {
+                    price: quote.price,
+                    slot: update.slot,
+                    publish_time: quote.publish_time,
+                }),
+                Err(e) => warn!("SOL 价格不合格: {}", e),
+            }
+        }
+        Err(anyhow!("sol price stream closed"))
+    }
+}
+
+#[test]
+fn test_price_oracle_rejects_stale_and_uncertain() {
+    let oracle = PriceOracle::default();
+    let quote = |price: f64, conf: f64, publish_time: i64| UsdQuote {
+        price,
+        conf,
+        publish_time,
+        source: "test".into(),
+    };
+
+    assert!(oracle.check(quote(150.0, 0.1, 1_000), 1_010).is_ok());
+    assert!(matches!(
+        oracle.check(quote(150.0, 0.1, 1_000), 1_100),
+        Err(PriceError::Stale { age: 100, .. })
+    ));
+    assert!(matches!(
+        oracle.check(quote(150.0, 5.0, 1_000), 1_000),
+        Err(PriceError::Uncertain { .. })
+    ));
+    assert!(matches!(
+        oracle.check(quote(0.0, 0.0, 1_000), 1_000),
+        Err(PriceError::Invalid(..))
+    ));
+}
+
+#[test]
+fn test_decode_price_update_v2() {
+    // 按 borsh 布局构造 `PriceUpdateV2` 账户，`full` 为 false 时是部分验证的更新
+    let account = |full: bool, price: i64, conf: u64, publish_time: i64| {
+        let mut data = PRICE_UPDATE_V2_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        match full {
+            true => data.push(1),
+            false => data.extend_from_slice(&[0, 3]),
+        }
+        data.extend_from_slice(&[7; 32]);
+        data.extend_from_slice(&price.to_le_bytes());
+        data.extend_from_slice(&conf.to_le_bytes());
+        data.extend_from_slice(&(-8i32).to_le_bytes());
+        data.extend_from_slice(&publish_time.to_le_bytes());
+        data.extend_from_slice(&(publish_time - 1).to_le_bytes());
+        data.extend_from_slice(&price.to_le_bytes());
+        data.extend_from_slice(&conf.to_le_bytes());
+        data.extend_from_slice(&123u64.to_le_bytes());
+        Account { data, ..Account::default() }
+    };
+    let oracle = PriceOracle::default();
+    let feed = PriceFeed {
+        key: PYTH_SOL_USD_V2.parse().unwrap(),
+        kind: FeedKind::PriceUpdateV2,
+    };
+
+    let quote = oracle.decode(&feed, &mut account(true, 15_012_000_000, 5_000_000, 1_000), 1_010).unwrap();
+    assert!((quote.price - 150.12).abs() < 1e-9);
+    assert!((quote.conf - 0.05).abs() < 1e-9);
+    assert_eq!(quote.publish_time, 1_000);
+    assert_eq!(quote.source, PYTH_SOL_USD_V2);
+
+    assert!(matches!(
+        oracle.decode(&feed, &mut account(false, 15_012_000_000, 5_000_000, 1_000), 1_010),
+        Err(PriceError::Decode(..))
+    ));
+    assert!(matches!(
+        oracle.decode(&feed, &mut account(true, 15_012_000_000, 5_000_000, 1_000), 1_100),
+        Err(PriceError::Stale { age: 100, .. })
+    ));
+    let mut wrong = account(true, 15_012_000_000, 5_000_000, 1_000);
+    wrong.data[0] ^= 1;
+    assert!(matches!(oracle.decode(&feed, &mut wrong, 1_010), Err(PriceError::Decode(..))));
+}
@@ -0,0 +1,409 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    bs58, compute_budget,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::{Signer, SignerError},
+    system_instruction::SystemInstruction,
+    system_program,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader as AsyncBufReader},
+    net::{UnixListener, UnixStream as AsyncUnixStream},
+    runtime::{Handle, RuntimeFlavor},
+};
+use tracing::warn;
+
+use crate::{
+    jito::TipAccounts,
+    monitor::{decode_order, PumpfunOrder, ASSOC_TOKEN_ACC_PROGRAM_ID, PUMPFUN_PROGRAM_ID, TOKEN_PROGRAM_ID},
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// 没有设置计算单元上限时按单笔交易的最大值计算优先费
+const MAX_UNIT_LIMIT: u64 = 1_400_000;
+
+/// 一行一个 JSON 请求
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum SignRequest {
+    Pubkey,
+    /// base58 编码的 legacy message
+    Sign { message: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignResponse {
+    Pubkey(String),
+    Signature(String),
+    Error(String),
+}
+
+/// 签名进程的策略：只签 pump.fun 买卖和 Jito tip 交易
+#[derive(Debug, Clone)]
+pub struct SignPolicy {
+    /// 一笔交易中所有 buy 指令的 max_sol_cost 之和上限（lamports）
+    pub max_buy_lamports: u64,
+    /// 一笔交易中所有 sell 指令的 token 数量之和上限
+    pub max_sell_tokens: Option<u64>,
+    /// 一笔交易中所有 tip 之和上限（lamports）
+    pub max_tip_lamports: u64,
+    /// 优先费上限（lamports）
+    pub max_priority_fee: u64,
+    /// 允许转账的 tip 账户，和交易进程一样从 block engine 刷新
+    pub tip_accounts: TipAccounts,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("instruction data too short"))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    data.get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("instruction data too short"))
+}
+
+impl SignPolicy {
+    /// 检查 `wallet` 要签名的交易，任何不认识的指令都拒绝
+    pub fn check(&self, message: &Message, wallet: &Pubkey) -> Result<()> {
+        if message.account_keys.first() != Some(wallet) {
+            bail!("fee payer must be the signing wallet");
+        }
+        let mut unit_limit = None;
+        let mut unit_price = 0;
+        let mut actions = 0;
+        // 多条指令可以拆分金额，按整笔交易累计后检查
+        let mut buy_lamports = 0u64;
+        let mut sell_tokens = 0u64;
+        let mut tip_lamports = 0u64;
+        for ix in &message.instructions {
+            let program = message
+                .account_keys
+                .get(ix.program_id_index as usize)
+                .ok_or_else(|| anyhow!("invalid program index"))?;
+            let account = |i: usize| {
+                ix.accounts
+                    .get(i)
+                    .and_then(|&k| message.account_keys.get(k as usize))
+                    .copied()
+            };
+            match *program {
+                p if p == compute_budget::id() => match ix.data.first() {
+                    Some(2) => unit_limit = Some(read_u32(&ix.data, 1)? as u64),
+                    Some(3) => unit_price = read_u64(&ix.data, 1)?,
+                    _ => bail!("unsupported compute budget instruction"),
+                },
+                p if p == PUMPFUN_PROGRAM_ID => {
+                    if account(6) != Some(*wallet) {
+                        bail!("pump.fun instruction for another user");
+                    }
+                    match decode_order(&ix.data) {
+                        Some(PumpfunOrder::Buy { max_sol_cost, .. }) => buy_lamports = buy_lamports.saturating_add(max_sol_cost),
+                        Some(PumpfunOrder::Sell { amount, .. }) => sell_tokens = sell_tokens.saturating_add(amount),
+                        None => bail!("only pump.fun buy and sell are allowed"),
+                    }
+                    actions += 1;
+                }
+                // 买入前创建自己的 token 账户
+                p if p == ASSOC_TOKEN_ACC_PROGRAM_ID => {
+                    if ix.data != [1] || account(0) != Some(*wallet) || account(2) != Some(*wallet) {
+                        bail!("only idempotent creation of the wallet's token account is allowed");
+                    }
+                }
+                // 卖出后关闭 token 账户，租金退回钱包
+                p if p == TOKEN_PROGRAM_ID => {
+                    if ix.data != [9] || account(1) != Some(*wallet) || account(2) != Some(*wallet) {
+                        bail!("only closing the wallet's token account is allowed");
+                    }
+                }
+                p if p == system_program::id() => {
+                    let Ok(SystemInstruction::Transfer { lamports }) = bincode::deserialize(&ix.data) else {
+                        bail!("only SOL transfers are allowed");
+                    };
+                    let to = account(1).ok_or_else(|| anyhow!("transfer without recipient"))?;
+                    if account(0) != Some(*wallet) || !self.tip_accounts.contains(&to) {
+                        bail!("transfer to {} is not a Jito tip", to);
+                    }
+                    tip_lamports = tip_lamports.saturating_add(lamports);
+                    actions += 1;
+                }
+                other => bail!("program {} is not allowed", other),
+            }
+        }
+        if actions == 0 {
+            bail!("transaction has no pump.fun trade or tip");
+        }
+        if buy_lamports > self.max_buy_lamports {
+            bail!("buy max_sol_cost {} exceeds limit {}", buy_lamports, self.max_buy_lamports);
+        }
+        if self.max_sell_tokens.is_some_and(|max| sell_tokens > max) {
+            bail!("sell amount {} exceeds limit {:?}", sell_tokens, self.max_sell_tokens);
+        }
+        if tip_lamports > self.max_tip_lamports {
+            bail!("tip {} exceeds limit {}", tip_lamports, self.max_tip_lamports);
+        }
+        let priority_fee = unit_limit.unwrap_or(MAX_UNIT_LIMIT) * unit_price / 1_000_000;
+        if priority_fee > self.max_priority_fee {
+            bail!("priority fee {} exceeds limit {}", priority_fee, self.max_priority_fee);
+        }
+        Ok(())
+    }
+}
+
+/// 签名进程：持有私钥，在 Unix socket 上按策略签名
+pub struct SigningDaemon {
+    keypair: Keypair,
+    policy: SignPolicy,
+}
+
+impl SigningDaemon {
+    pub fn new(keypair: Keypair, policy: SignPolicy) -> Self {
+        Self { keypair, policy }
+    }
+
+    pub fn handle(&self, request: SignRequest) -> SignResponse {
+        match request {
+            SignRequest::Pubkey => SignResponse::Pubkey(self.keypair.pubkey().to_string()),
+            SignRequest::Sign { message } => match self.sign(&message) {
+                Ok(signature) => SignResponse::Signature(signature.to_string()),
+                Err(e) => {
+                    warn!("拒绝签名: {}", e);
+                    SignResponse::Error(e.to_string())
+                }
+            },
+        }
+    }
+
+    fn sign(&self, message: &str) -> Result<Signature> {
+        let bytes = bs58::decode(message).into_vec()?;
+        if bytes.first().is_some_and(|b| b & 0x80 != 0) {
+            bail!("versioned messages are not supported");
+        }
+        let parsed: Message = bincode::deserialize(&bytes).map_err(|_| anyhow!("invalid message"))?;
+        // 签名的字节必须和检查过的 message 完全一致
+        if parsed.serialize() != bytes {
+            bail!("message is not canonical");
+        }
+        self.policy.check(&parsed, &self.keypair.pubkey())?;
+        Ok(self.keypair.sign_message(&bytes))
+    }
+
+    pub async fn serve(self: Arc<Self>, listener: UnixListener) -> Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let daemon = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = daemon.serve_connection(stream).await {
+                    warn!("签名连接出错: {:?}", e);
+                }
+            });
+        }
+    }
+
+    async fn serve_connection(&self, stream: AsyncUnixStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = AsyncBufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let response = match serde_json::from_str::<SignRequest>(&line) {
+                Ok(request) => self.handle(request),
+                Err(e) => SignResponse::Error(format!("invalid request: {}", e)),
+            };
+            let mut data = serde_json::to_vec(&response)?;
+            data.push(b'\n');
+            writer.write_all(&data).await?;
+        }
+        Ok(())
+    }
+}
+
+fn request(path: &Path, request: &SignRequest) -> Result<SignResponse> {
+    let mut stream = UnixStream::connect(path)
+        .with_context(|| format!("failed to connect to signer at {}", path.display()))?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut data = serde_json::to_vec(request)?;
+    data.push(b'\n');
+    stream.write_all(&data)?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// 通过 Unix socket 请求 [`SigningDaemon`] 签名的 [`Signer`]，联网进程不持有私钥；多线程运行时下用 `block_in_place` 阻塞等待
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    path: PathBuf,
+    pubkey: Pubkey,
+}
+
+impl RemoteSigner {
+    pub fn connect(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        match request(&path, &SignRequest::Pubkey)? {
+            SignResponse::Pubkey(pubkey) => Ok(Self {
+                pubkey: pubkey.parse()?,
+                path,
+            }),
+            SignResponse::Error(e) => bail!("signer: {}", e),
+            other => bail!("signer: unexpected response {:?}", other),
+        }
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let sign = SignRequest::Sign {
+            message: bs58::encode(message).into_string(),
+        };
+        // 在 tokio 工作线程上签名时让出线程，避免阻塞其他任务
+        let response = match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| request(&self.path, &sign))
+            }
+            _ => request(&self.path, &sign),
+        };
+        match response.map_err(|e| SignerError::Connection(e.to_string()))? {
+            SignResponse::Signature(signature) => {
+                let signature: Signature = signature
+                    .parse()
+                    .map_err(|e| SignerError::Protocol(format!("invalid signature: {}", e)))?;
+                if !signature.verify(self.pubkey.as_ref(), message) {
+                    return Err(SignerError::Protocol("signature does not verify".to_string()));
+                }
+                Ok(signature)
+            }
+            SignResponse::Error(e) => Err(SignerError::Custom(format!("signer rejected: {}", e))),
+            other => Err(SignerError::Protocol(format!("unexpected response {:?}", other))),
+        }
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_remote_signer_enforces_policy() {
+    use crate::monitor::{
+        create_buy_transaction, create_sell_transaction, create_token_transaction, find_bonding_curve,
+        transfer_tx, ComputeBudget, CreateArgs, SellParams,
+    };
+    use solana_sdk::hash::Hash;
+
+    let path = std::env::temp_dir().join(format!("solana_pump_signer_{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let keypair = Keypair::new();
+    let wallet = keypair.pubkey();
+    let policy = SignPolicy {
+        max_buy_lamports: 1_000_000_000,
+        max_sell_tokens: None,
+        max_tip_lamports: 100_000,
+        max_priority_fee: 100_000,
+        tip_accounts: TipAccounts::default(),
+    };
+    let tip_account = policy.tip_accounts.accounts()[0];
+    let listener = UnixListener::bind(&path).unwrap();
+    tokio::spawn(Arc::new(SigningDaemon::new(keypair, policy)).serve(listener));
+
+    // 在工作线程上签名时通过 block_in_place 让出线程
+    let signer = tokio::task::block_in_place(|| RemoteSigner::connect(&path)).unwrap();
+    assert!(transfer_tx(&tip_account, &signer, 10_000, Hash::new_unique()).is_ok());
+
+    // RemoteSigner 是阻塞的，在 blocking 线程上使用
+    let results = tokio::task::spawn_blocking(move || {
+        let signer = RemoteSigner::connect(&path).unwrap();
+        assert_eq!(signer.pubkey(), wallet);
+        let mint = Pubkey::new_unique();
+        let curve = find_bonding_curve(&mint);
+        let budget = ComputeBudget::default();
+        let blockhash = Hash::new_unique();
+        let sell = |budget| SellParams {
+            bonding_curve: &curve,
+            mint: &mint,
+            price: 0.0001,
+            amount_in_token: 1_000_000,
+            slippage_bps: 1500.0,
+            budget,
+        };
+
+        let results = vec![
+            create_sell_transaction(&sell(&budget), &signer, blockhash).is_ok(),
+            create_buy_transaction(&curve, &mint, &signer, 1_000_000, 500_000_000, &budget, blockhash).is_ok(),
+            transfer_tx(&tip_account, &signer, 10_000, blockhash).is_ok(),
+            // 超过买入上限、tip 上限、转给非 tip 账户、优先费过高和创建代币都被拒绝
+            create_buy_transaction(&curve, &mint, &signer, 1_000_000, 2_000_000_000, &budget, blockhash).is_ok(),
+            transfer_tx(&tip_account, &signer, 1_000_000, blockhash).is_ok(),
+            transfer_tx(&Pubkey::new_unique(), &signer, 10_000, blockhash).is_ok(),
+            create_sell_transaction(
+                &sell(&ComputeBudget { unit_limit: 1_400_000, unit_price: 1_000_000 }),
+                &signer,
+                blockhash,
+            )
+            .is_ok(),
+            create_token_transaction(
+                &Keypair::new(),
+                &signer,
+                &CreateArgs {
+                    name: "a".to_string(),
+                    symbol: "A".to_string(),
+                    uri: String::new(),
+                },
+                None,
+                &budget,
+                blockhash,
+            )
+            .is_ok(),
+        ];
+        let _ = std::fs::remove_file(&path);
+        results
+    })
+    .await
+    .unwrap();
+    assert_eq!(results, vec![true, true, true, false, false, false, false, false]);
+}
+
+#[test]
+fn test_sign_policy_sums_amounts_across_instructions() {
+    use crate::monitor::buy_amount_out_ix;
+    use solana_sdk::{instruction::Instruction, system_instruction::transfer};
+
+    let wallet = Pubkey::new_unique();
+    let policy = SignPolicy {
+        max_buy_lamports: 1_000_000_000,
+        max_sell_tokens: None,
+        max_tip_lamports: 100_000,
+        max_priority_fee: 100_000,
+        tip_accounts: TipAccounts::default(),
+    };
+    let tip_account = policy.tip_accounts.accounts()[0];
+    let buy = |max_sol_cost| {
+        let mint = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        buy_amount_out_ix(&mint, &key, &key, &wallet, &key, 1_000_000, max_sol_cost)
+    };
+    let check = |ixs: &[Instruction]| policy.check(&Message::new(ixs, Some(&wallet)), &wallet);
+
+    // 每条指令都在上限内，合计超过上限时拒绝
+    assert!(check(&[buy(600_000_000)]).is_ok());
+    assert!(check(&[buy(600_000_000), buy(600_000_000)]).is_err());
+    assert!(check(&[transfer(&wallet, &tip_account, 60_000)]).is_ok());
+    assert!(check(&[transfer(&wallet, &tip_account, 60_000), transfer(&wallet, &tip_account, 60_000)]).is_err());
+}
@@ -0,0 +1,317 @@
+use std::{
+    path::Path,
+    pin::Pin,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use futures_util::{stream, Stream, StreamExt};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
+    runtime::{Handle, RuntimeFlavor},
+    time::{Instant, Interval, MissedTickBehavior},
+};
+use yellowstone_grpc_proto::{geyser::SubscribeUpdate, prost::Message, tonic::Status};
+use tracing::{error, warn};
+
+/// 文件头
+const MAGIC: &[u8; 8] = b"PUMPREC1";
+/// 单帧的最大长度，超过时认为文件损坏，避免按错误的长度分配内存
+const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+/// 录制时的刷盘间隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 和 `get_pumpfun_stream` 相同的流类型
+pub type UpdateStream = Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, Status>> + Send>>;
+
+/// 把原始 `SubscribeUpdate` 追加到文件，每帧为 `[接收时间 unix 微秒: u64 LE][长度: u32 LE][protobuf]`
+pub struct StreamRecorder {
+    writer: BufWriter<File>,
+}
+
+fn unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default()
+}
+
+impl StreamRecorder {
+    pub async fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let mut writer = BufWriter::new(File::create(path).await?);
+        writer.write_all(MAGIC).await?;
+        Ok(Self { writer })
+    }
+
+    pub async fn write(&mut self, update: &SubscribeUpdate) -> Result<()> {
+        self.write_at(unix_micros(), update).await
+    }
+
+    pub async fn write_at(&mut self, micros: u64, update: &SubscribeUpdate) -> Result<()> {
+        let data = update.encode_to_vec();
+        if data.len() > MAX_FRAME_LEN {
+            return Err(anyhow!("recorder: frame of {} bytes exceeds {}", data.len(), MAX_FRAME_LEN));
+        }
+        self.writer.write_u64_le(micros).await?;
+        self.writer.write_u32_le(data.len() as u32).await?;
+        self.writer.write_all(&data).await?;
+        Ok(())
+    }
+
+    pub async fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush().await?)
+    }
+}
+
+struct RecordState<S> {
+    inner: Pin<Box<S>>,
+    recorder: StreamRecorder,
+    flush: Interval,
+    /// 上次刷盘后有新写入的帧
+    dirty: bool,
+}
+
+impl<S> RecordState<S> {
+    async fn flush(&mut self) {
+        if let Err(e) = self.recorder.flush().await {
+            error!("录制刷新到文件失败: {:?}", e);
+        }
+        self.dirty = false;
+    }
+}
+
+/// 流在结束前被释放（/shutdown、kill switch 退出、Ctrl-C）时刷盘，多线程运行时下用 `block_in_place` 等待
+impl<S> Drop for RecordState<S> {
+    fn drop(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| handle.block_on(self.flush()))
+            }
+            _ => warn!("录制未刷新到文件：不在多线程运行时中"),
+        }
+    }
+}
+
+/// 把流中的消息原样转发，同时写入录制文件。写入经过缓冲，每 `FLUSH_INTERVAL`、流结束和流被释放时刷盘
+pub fn record_stream(
+    inner: impl Stream<Item = Result<SubscribeUpdate, Status>> + Send + 'static,
+    recorder: StreamRecorder,
+) -> UpdateStream {
+    let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+    flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let state = RecordState { inner: Box::pin(inner), recorder, flush, dirty: false };
+    let stream = stream::unfold(state, |mut state| async move {
+        // 等待下一条消息时按间隔刷盘，流空闲时已录制的帧也会落盘
+        let item = loop {
+            tokio::select! {
+                item = state.inner.next() => break item,
+                _ = state.flush.tick(), if state.dirty => state.flush().await,
+            }
+        };
+        let Some(item) = item else {
+            state.flush().await;
+            return None;
+        };
+        if let Ok(update) = &item {
+            match state.recorder.write(update).await {
+                Ok(()) => state.dirty = true,
+                Err(e) => error!("录制写入失败: {:?}", e),
+            }
+        }
+        Some((item, state))
+    });
+    Box::pin(stream)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Playback {
+    /// 按录制时的间隔播放
+    RealTime,
+    /// 间隔按倍数缩短
+    Accelerated(f64),
+    /// 不等待
+    AsFastAsPossible,
+}
+
+impl std::str::FromStr for Playback {
+    type Err = anyhow::Error;
+
+    /// `realtime`、`max` 或倍速数字
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "realtime" => Ok(Self::RealTime),
+            "max" => Ok(Self::AsFastAsPossible),
+            speed => {
+                let speed: f64 = speed.parse()?;
+                if speed <= 0.0 {
+                    return Err(anyhow!("replay speed must be positive: {}", speed));
+                }
+                Ok(Self::Accelerated(speed))
+            }
+        }
+    }
+}
+
+impl Playback {
+    fn delay(&self, since_first: Duration) -> Option<Duration> {
+        match self {
+            Self::RealTime => Some(since_first),
+            Self::Accelerated(speed) => Some(since_first.div_f64(*speed)),
+            Self::AsFastAsPossible => None,
+        }
+    }
+}
+
+/// 读取下一帧，文件结束时返回 None
+async fn read_frame(reader: &mut BufReader<File>) -> Result<Option<(u64, SubscribeUpdate)>> {
+    let micros = match reader.read_u64_le().await {
+        Ok(micros) => micros,
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let len = reader.read_u32_le().await? as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(anyhow!("replay: frame of {} bytes exceeds {}", len, MAX_FRAME_LEN));
+    }
+    let mut data = vec![0u8; len];
+    reader.read_exact(&mut data).await?;
+    Ok(Some((micros, SubscribeUpdate::decode(data.as_slice())?)))
+}
+
+struct ReplayState {
+    reader: BufReader<File>,
+    playback: Playback,
+    /// 第一帧的录制时间和开始播放的时间
+    origin: Option<(u64, Instant)>,
+}
+
+/// 回放录制文件，返回和 Geyser 订阅相同类型的流
+pub async fn replay_stream(path: impl AsRef<Path>, playback: Playback) -> Result<UpdateStream> {
+    let mut reader = BufReader::new(File::open(path).await?);
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic).await?;
+    if &magic != MAGIC {
+        return Err(anyhow!("replay: not a stream recording"));
+    }
+
+    let state = ReplayState {
+        reader,
+        playback,
+        origin: None,
+    };
+    let stream = stream::unfold(state, |mut state| async move {
+        let (micros, mut update) = match read_frame(&mut state.reader).await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return None,
+            Err(e) => {
+                // 文件损坏时打印错误并结束
+                warn!("回放出错: {:?}", e);
+                return None;
+            }
+        };
+        let (first, started) = *state.origin.get_or_insert((micros, Instant::now()));
+        let since_first = Duration::from_micros(micros.saturating_sub(first));
+        if let Some(delay) = state.playback.delay(since_first) {
+            tokio::time::sleep_until(started + delay).await;
+        }
+        // 录制时 Geyser 生成更新的时间和回放无关，不计入延迟统计
+        update.created_at = None;
+        Some((Ok(update), state))
+    });
+    Ok(Box::pin(stream))
+}
+
+#[tokio::test]
+async fn test_record_and_replay() {
+    use yellowstone_grpc_proto::geyser::{subscribe_update::UpdateOneof, SubscribeUpdatePing};
+
+    let path = std::env::temp_dir().join(format!("solana_pump_replay_{}.rec", std::process::id()));
+    let mut recorder = StreamRecorder::create(&path).await.unwrap();
+    for i in 0..3u64 {
+        let update = SubscribeUpdate {
+            filters: vec![format!("f{}", i)],
+            update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+            ..Default::default()
+        };
+        recorder.write_at(1_000_000 + i * 10_000, &update).await.unwrap();
+    }
+    recorder.flush().await.unwrap();
+
+    let replayed: Vec<_> = replay_stream(&path, Playback::AsFastAsPossible)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(replayed.len(), 3);
+    assert_eq!(replayed[2].as_ref().unwrap().filters, vec!["f2".to_string()]);
+
+    // 20ms 的录制按 2 倍速播放至少需要 10ms
+    let start = std::time::Instant::now();
+    let count = replay_stream(&path, Playback::Accelerated(2.0))
+        .await
+        .unwrap()
+        .count()
+        .await;
+    assert_eq!(count, 3);
+    assert!(start.elapsed() >= Duration::from_millis(10));
+
+    // 长度超过上限的帧当作损坏，回放在这里结束
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+    std::io::Write::write_all(&mut file, &[0u8; 8]).unwrap();
+    std::io::Write::write_all(&mut file, &u32::MAX.to_le_bytes()).unwrap();
+    drop(file);
+    let count = replay_stream(&path, Playback::AsFastAsPossible).await.unwrap().count().await;
+    assert_eq!(count, 3);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_record_stream_flushes_on_interval() {
+    use yellowstone_grpc_proto::geyser::{subscribe_update::UpdateOneof, SubscribeUpdatePing};
+
+    let path = std::env::temp_dir().join(format!("solana_pump_record_{}.rec", std::process::id()));
+    let (tx, rx) = futures::channel::mpsc::unbounded();
+    let mut recorded = record_stream(rx, StreamRecorder::create(&path).await.unwrap());
+    let update = SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+        ..Default::default()
+    };
+    tx.unbounded_send(Ok(update)).unwrap();
+    assert!(recorded.next().await.unwrap().is_ok());
+    // 写入只在缓冲区中，流空闲一个刷盘间隔后落盘
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+    let next = tokio::spawn(async move { recorded.next().await.is_none() });
+    tokio::time::sleep(FLUSH_INTERVAL + Duration::from_millis(500)).await;
+    assert!(std::fs::metadata(&path).unwrap().len() > MAGIC.len() as u64);
+    drop(tx);
+    assert!(next.await.unwrap());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_record_stream_flushes_on_drop() {
+    use yellowstone_grpc_proto::geyser::{subscribe_update::UpdateOneof, SubscribeUpdatePing};
+
+    let path = std::env::temp_dir().join(format!("solana_pump_record_drop_{}.rec", std::process::id()));
+    let (tx, rx) = futures::channel::mpsc::unbounded();
+    let mut recorded = record_stream(rx, StreamRecorder::create(&path).await.unwrap());
+    let update = SubscribeUpdate {
+        update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+        ..Default::default()
+    };
+    tx.unbounded_send(Ok(update)).unwrap();
+    assert!(recorded.next().await.unwrap().is_ok());
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+    // 流还没结束、没到刷盘间隔就被释放，已转发的帧也要落盘
+    drop(recorded);
+    let replayed = replay_stream(&path, Playback::AsFastAsPossible).await.unwrap().count().await;
+    assert_eq!(replayed, 1);
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,418 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use solana_sdk::{pubkey::Pubkey, system_instruction::SystemInstruction, system_program, transaction::Transaction};
+use thiserror::Error;
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+};
+use tracing::{error, warn};
+
+use crate::{
+    monitor::{decode_order, PumpfunOrder, PUMPFUN_PROGRAM_ID},
+    pnl::Fill,
+    store::unix_now,
+};
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// 发送交易前的风控限制，修改配置文件后热加载
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskLimits {
+    /// 单笔买入最多花费（max_sol_cost）和单笔转账（tip）的上限（lamports）
+    pub max_sol_per_trade: u64,
+    /// 每分钟最多发送的买卖交易数，只减仓的卖出计入但不会被拒绝
+    pub max_trades_per_minute: usize,
+    /// 买卖的滑点保护不能比按当前价格计算的宽这么多（bps）
+    pub max_slippage_bps: f64,
+    /// 当天（UTC）已实现亏损达到后不再买入（lamports）
+    pub daily_loss_limit: Option<u64>,
+    /// 文件存在时触发 kill switch
+    pub kill_switch_file: Option<PathBuf>,
+}
+
+impl Default for RiskLimits {
+    fn default() -> Self {
+        Self {
+            max_sol_per_trade: 1_000_000_000,
+            max_trades_per_minute: 30,
+            max_slippage_bps: 5000.0,
+            daily_loss_limit: None,
+            kill_switch_file: None,
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RiskError {
+    #[error("kill switch is active ({0}), new entries are blocked")]
+    KillSwitch(String),
+    #[error("daily loss {loss} lamports reached limit {limit}, new entries are blocked")]
+    DailyLoss { loss: u64, limit: u64 },
+    #[error("{lamports} lamports exceeds per-trade limit {limit}")]
+    TradeTooLarge { lamports: u64, limit: u64 },
+    #[error("{count} trades in the last minute, limit {limit}")]
+    RateLimited { count: usize, limit: usize },
+    #[error("{side} limit {limit} lamports is more than {max_slippage_bps} bps away from expected {expected}")]
+    Slippage {
+        side: &'static str,
+        limit: u64,
+        expected: u64,
+        max_slippage_bps: f64,
+    },
+}
+
+/// 当天的持仓成本，用于计算已实现盈亏
+#[derive(Debug, Default)]
+struct CostBasis {
+    tokens: u64,
+    cost: u64,
+}
+
+#[derive(Debug, Default)]
+struct RiskState {
+    sent: VecDeque<Instant>,
+    /// UTC 日期（unix 天数）
+    day: i64,
+    day_pnl: i64,
+    positions: HashMap<Pubkey, CostBasis>,
+    /// 手动或信号触发 kill switch 的原因
+    killed: Option<String>,
+}
+
+/// 交易发出前都要经过 [`RiskGuard::check`]；停止开关或日亏损上限只拦截买入，卖出始终允许
+pub struct RiskGuard {
+    limits: Mutex<RiskLimits>,
+    state: Mutex<RiskState>,
+}
+
+/// 按价格计算的 SOL 数量，和 `min_sol_out` 的算法一致
+fn expected_sol(price: f32, tokens: u64) -> u64 {
+    (tokens as f32 * price * 1000.0) as u64
+}
+
+impl RiskGuard {
+    pub fn new(limits: RiskLimits) -> Arc<Self> {
+        Arc::new(Self {
+            limits: Mutex::new(limits),
+            state: Mutex::new(RiskState::default()),
+        })
+    }
+
+    pub fn limits(&self) -> RiskLimits {
+        self.limits.lock().unwrap().clone()
+    }
+
+    pub fn set_limits(&self, limits: RiskLimits) {
+        *self.limits.lock().unwrap() = limits;
+    }
+
+    /// 手动触发 kill switch
+    pub fn kill(&self, reason: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.killed.is_none() {
+            error!(%reason, "kill switch 已触发");
+            state.killed = Some(reason.to_string());
+        }
+    }
+
+    /// kill switch 是否触发：手动、信号或文件存在
+    pub fn killed(&self) -> Option<String> {
+        if let Some(reason) = self.state.lock().unwrap().killed.clone() {
+            return Some(reason);
+        }
+        let path = self.limits().kill_switch_file?;
+        path.exists().then(|| format!("{} exists", path.display()))
+    }
+
+    /// 当天（UTC）的已实现盈亏
+    pub fn daily_pnl(&self) -> i64 {
+        let mut state = self.state.lock().unwrap();
+        roll_day(&mut state, unix_now());
+        state.day_pnl
+    }
+
+    /// 检查将要发送的交易，通过后计入频率限制。`price` 是构造交易时的价格，用于检查滑点保护
+    pub fn check(&self, tx: &Transaction, price: f32) -> Result<(), RiskError> {
+        self.check_at(tx, price, Instant::now(), unix_now())
+    }
+
+    fn check_at(&self, tx: &Transaction, price: f32, now: Instant, unix_time: i64) -> Result<(), RiskError> {
+        let limits = self.limits();
+        let message = &tx.message;
+        let mut trade = false;
+        // 买入和创建；只有卖出的交易不受频率限制，kill switch 的清仓不会被拦住
+        let mut entry = false;
+        for ix in &message.instructions {
+            let program = message.account_keys.get(ix.program_id_index as usize);
+            if program == Some(&system_program::id()) {
+                if let Ok(SystemInstruction::Transfer { lamports }) = bincode::deserialize(&ix.data) {
+                    if lamports > limits.max_sol_per_trade {
+                        return Err(RiskError::TradeTooLarge { lamports, limit: limits.max_sol_per_trade });
+                    }
+                }
+                continue;
+            }
+            if program != Some(&PUMPFUN_PROGRAM_ID) {
+                continue;
+            }
+            trade = true;
+            match decode_order(&ix.data) {
+                Some(PumpfunOrder::Buy { amount, max_sol_cost }) => {
+                    entry = true;
+                    self.check_entry(&limits, unix_time)?;
+                    if max_sol_cost > limits.max_sol_per_trade {
+                        return Err(RiskError::TradeTooLarge {
+                            lamports: max_sol_cost,
+                            limit: limits.max_sol_per_trade,
+                        });
+                    }
+                    let expected = expected_sol(price, amount);
+                    let ceiling = (expected as f64 * (10000.0 + limits.max_slippage_bps) / 10000.0) as u64;
+                    if max_sol_cost > ceiling {
+                        return Err(RiskError::Slippage {
+                            side: "buy",
+                            limit: max_sol_cost,
+                            expected,
+                            max_slippage_bps: limits.max_slippage_bps,
+                        });
+                    }
+                }
+                Some(PumpfunOrder::Sell { amount, min_sol_output }) => {
+                    let expected = expected_sol(price, amount);
+                    let floor = (expected as f64 * (10000.0 - limits.max_slippage_bps) / 10000.0) as u64;
+                    if min_sol_output == 0 || min_sol_output < floor {
+                        return Err(RiskError::Slippage {
+                            side: "sell",
+                            limit: min_sol_output,
+                            expected,
+                            max_slippage_bps: limits.max_slippage_bps,
+                        });
+                    }
+                }
+                // 创建代币也是新的入场
+                None => {
+                    entry = true;
+                    self.check_entry(&limits, unix_time)?;
+                }
+            }
+        }
+
+        if trade {
+            let mut state = self.state.lock().unwrap();
+            while state.sent.front().is_some_and(|t| now.duration_since(*t) >= RATE_WINDOW) {
+                state.sent.pop_front();
+            }
+            if entry && state.sent.len() >= limits.max_trades_per_minute {
+                return Err(RiskError::RateLimited {
+                    count: state.sent.len(),
+                    limit: limits.max_trades_per_minute,
+                });
+            }
+            state.sent.push_back(now);
+        }
+        Ok(())
+    }
+
+    /// 新的入场（买入、创建）：kill switch 和当天亏损限制
+    fn check_entry(&self, limits: &RiskLimits, unix_time: i64) -> Result<(), RiskError> {
+        if let Some(reason) = self.killed() {
+            return Err(RiskError::KillSwitch(reason));
+        }
+        if let Some(limit) = limits.daily_loss_limit {
+            let mut state = self.state.lock().unwrap();
+            roll_day(&mut state, unix_time);
+            let loss = (-state.day_pnl).max(0) as u64;
+            if loss >= limit {
+                return Err(RiskError::DailyLoss { loss, limit });
+            }
+        }
+        Ok(())
+    }
+
+    /// 按落地的成交计算已实现盈亏：卖出按当天买入的平均成本结算，没有买入记录的按零成本，
+    /// 没有买卖的交易（tip、失败的交易）全部计为亏损
+    pub fn record_fill(&self, fill: &Fill) {
+        self.record_fill_at(fill, unix_now());
+    }
+
+    fn record_fill_at(&self, fill: &Fill, unix_time: i64) {
+        let mut state = self.state.lock().unwrap();
+        roll_day(&mut state, unix_time);
+        let realized = match fill.trades.first() {
+            Some(trade) if fill.success => {
+                let tokens: u64 = fill.trades.iter().map(|t| t.token_amount).sum();
+                let position = state.positions.entry(trade.mint).or_default();
+                if trade.is_buy {
+                    position.tokens += tokens;
+                    position.cost += fill.sol_change.unsigned_abs();
+                    0
+                } else {
+                    let sold = tokens.min(position.tokens);
+                    let basis = match position.tokens {
+                        0 => 0,
+                        held => (position.cost as u128 * sold as u128 / held as u128) as u64,
+                    };
+                    position.tokens -= sold;
+                    position.cost -= basis;
+                    fill.sol_change - basis as i64
+                }
+            }
+            _ => fill.sol_change,
+        };
+        state.day_pnl += realized;
+        if let Some(limit) = self.limits().daily_loss_limit {
+            if -state.day_pnl >= limit as i64 {
+                error!(loss = -state.day_pnl, limit, "达到当天亏损上限，停止买入");
+            }
+        }
+    }
+
+    /// 定时检查 kill switch 文件，收到 SIGUSR1 时触发；推送 kill switch 是否触发
+    pub fn spawn_kill_switch(self: &Arc<Self>, interval: Duration) -> watch::Receiver<bool> {
+        let (tx, rx) = watch::channel(self.killed().is_some());
+        let guard = self.clone();
+        tokio::spawn(async move {
+            let mut signal = match signal(SignalKind::user_defined1()) {
+                Ok(signal) => Some(signal),
+                Err(e) => {
+                    warn!("无法监听 SIGUSR1: {:?}", e);
+                    None
+                }
+            };
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    Some(()) = async {
+                        match signal.as_mut() {
+                            Some(signal) => signal.recv().await,
+                            None => std::future::pending().await,
+                        }
+                    } => guard.kill("SIGUSR1"),
+                    _ = tx.closed() => break,
+                }
+                let killed = guard.killed().is_some();
+                tx.send_if_modified(|current| std::mem::replace(current, killed) != killed);
+            }
+        });
+        rx
+    }
+}
+
+/// 跨过 UTC 零点时清零当天盈亏
+fn roll_day(state: &mut RiskState, unix_time: i64) {
+    let day = unix_time / SECONDS_PER_DAY;
+    if state.day != day {
+        state.day = day;
+        state.day_pnl = 0;
+    }
+}
+
+#[test]
+fn test_risk_guard_limits() {
+    use crate::monitor::{create_buy_transaction, create_sell_transaction, find_bonding_curve, transfer_tx, ComputeBudget, SellParams, TradeEvent};
+    use solana_sdk::{hash::Hash, signature::Keypair, signature::Signature, signer::Signer};
+
+    let guard = RiskGuard::new(RiskLimits {
+        max_sol_per_trade: 1_000_000_000,
+        max_trades_per_minute: 3,
+        max_slippage_bps: 2000.0,
+        daily_loss_limit: Some(100_000_000),
+        kill_switch_file: None,
+    });
+    let keypair = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let curve = find_bonding_curve(&mint);
+    let budget = ComputeBudget::default();
+    let blockhash = Hash::new_unique();
+    // 1_000_000 个 token 按价格 0.1 计算为 100_000_000 lamports
+    let price = 0.1;
+    let buy = |tokens, max_cost| create_buy_transaction(&curve, &mint, &keypair, tokens, max_cost, &budget, blockhash).unwrap();
+    let sell = |slippage_bps| {
+        let params = SellParams { bonding_curve: &curve, mint: &mint, price, amount_in_token: 1_000_000, slippage_bps, budget: &budget };
+        create_sell_transaction(&params, &keypair, blockhash).unwrap()
+    };
+    let now = Instant::now();
+    let day = 20_000 * SECONDS_PER_DAY;
+
+    assert_eq!(guard.check_at(&buy(1_000_000, 110_000_000), price, now, day), Ok(()));
+    assert!(matches!(
+        guard.check_at(&buy(20_000_000, 2_000_000_000), price, now, day),
+        Err(RiskError::TradeTooLarge { .. })
+    ));
+    assert!(matches!(
+        guard.check_at(&buy(1_000_000, 500_000_000), price, now, day),
+        Err(RiskError::Slippage { side: "buy", .. })
+    ));
+    assert!(matches!(guard.check_at(&sell(9000.0), price, now, day), Err(RiskError::Slippage { side: "sell", .. })));
+    assert_eq!(guard.check_at(&sell(1500.0), price, now, day), Ok(()));
+    let tip = transfer_tx(&Pubkey::new_unique(), &keypair, 2_000_000_000, blockhash).unwrap();
+    assert!(matches!(guard.check_at(&tip, price, now, day), Err(RiskError::TradeTooLarge { .. })));
+
+    // 每分钟 3 笔，超过后拒绝买入，只减仓的卖出不受限制；一分钟后恢复
+    assert_eq!(guard.check_at(&sell(1500.0), price, now, day), Ok(()));
+    assert_eq!(
+        guard.check_at(&buy(1_000_000, 110_000_000), price, now, day),
+        Err(RiskError::RateLimited { count: 3, limit: 3 })
+    );
+    assert_eq!(guard.check_at(&sell(1500.0), price, now, day), Ok(()));
+    let later = now + RATE_WINDOW;
+    assert_eq!(guard.check_at(&buy(1_000_000, 110_000_000), price, later, day), Ok(()));
+
+    // 买入 100_000_000 后以 40_000_000 卖出，亏损 60_000_000；再加上失败交易的 50_000_000 达到限制
+    let fill = |is_buy, sol_change, success| Fill {
+        signature: Signature::default(),
+        slot: 1,
+        success,
+        mint: Some(mint),
+        trades: vec![TradeEvent {
+            mint,
+            sol_amount: 0,
+            token_amount: 1_000_000,
+            is_buy,
+            user: keypair.pubkey(),
+            timestamp: 0,
+            virtual_sol_reserves: 0,
+            virtual_token_reserves: 0,
+            real_sol_reserves: 0,
+            real_token_reserves: 0,
+        }],
+        sol_change,
+        base_fee: 5000,
+        priority_fee: 0,
+        jito_tip: 0,
+        rent_reclaimed: 0,
+        sol_usd: None,
+    };
+    guard.record_fill_at(&fill(true, -100_000_000, true), day);
+    guard.record_fill_at(&fill(false, 40_000_000, true), day);
+    assert_eq!(guard.state.lock().unwrap().day_pnl, -60_000_000);
+    let later = later + RATE_WINDOW;
+    assert_eq!(guard.check_at(&buy(1_000_000, 110_000_000), price, later, day), Ok(()));
+    guard.record_fill_at(&fill(true, -50_000_000, false), day);
+    assert_eq!(
+        guard.check_at(&buy(1_000_000, 110_000_000), price, later, day),
+        Err(RiskError::DailyLoss { loss: 110_000_000, limit: 100_000_000 })
+    );
+    // 卖出不受限制，第二天恢复买入
+    assert_eq!(guard.check_at(&sell(1500.0), price, later, day), Ok(()));
+    assert_eq!(guard.check_at(&buy(1_000_000, 110_000_000), price, later, day + SECONDS_PER_DAY), Ok(()));
+
+    // kill switch 只阻止买入
+    guard.kill("test");
+    let later = later + RATE_WINDOW;
+    assert!(matches!(
+        guard.check_at(&buy(1_000_000, 110_000_000), price, later, day + SECONDS_PER_DAY),
+        Err(RiskError::KillSwitch(_))
+    ));
+    assert_eq!(guard.check_at(&sell(1500.0), price, later, day + SECONDS_PER_DAY), Ok(()));
+}
@@ -0,0 +1,573 @@
+use std::{
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use solana_sdk::pubkey::Pubkey;
+use tracing::error;
+
+use crate::{
+    monitor::{CompleteEvent, CreateEvent, TargetEvent, TradeEvent},
+    pnl::Fill,
+};
+
+/// 每次事务最多写入的记录数
+const MAX_BATCH: usize = 1000;
+
+/// 按顺序执行的 schema 迁移，版本号记录在 `PRAGMA user_version`
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE create_events (
+        id INTEGER PRIMARY KEY,
+        slot INTEGER NOT NULL,
+        signature TEXT NOT NULL,
+        mint TEXT NOT NULL,
+        bonding_curve TEXT NOT NULL,
+        user TEXT NOT NULL,
+        name TEXT NOT NULL,
+        symbol TEXT NOT NULL,
+        uri TEXT NOT NULL,
+        recorded_at INTEGER NOT NULL
+    );
+    CREATE INDEX create_events_mint ON create_events (mint);
+
+    CREATE TABLE trade_events (
+        id INTEGER PRIMARY KEY,
+        slot INTEGER NOT NULL,
+        signature TEXT NOT NULL,
+        mint TEXT NOT NULL,
+        user TEXT NOT NULL,
+        is_buy INTEGER NOT NULL,
+        sol_amount INTEGER NOT NULL,
+        token_amount INTEGER NOT NULL,
+        timestamp INTEGER NOT NULL,
+        virtual_sol_reserves INTEGER NOT NULL,
+        virtual_token_reserves INTEGER NOT NULL,
+        real_sol_reserves INTEGER NOT NULL,
+        real_token_reserves INTEGER NOT NULL,
+        recorded_at INTEGER NOT NULL
+    );
+    CREATE INDEX trade_events_mint ON trade_events (mint, slot);
+
+    CREATE TABLE complete_events (
+        id INTEGER PRIMARY KEY,
+        slot INTEGER NOT NULL,
+        signature TEXT NOT NULL,
+        mint TEXT NOT NULL,
+        user TEXT NOT NULL,
+        bonding_curve TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        recorded_at INTEGER NOT NULL
+    );
+    CREATE INDEX complete_events_mint ON complete_events (mint);
+
+    CREATE TABLE orders (
+        id INTEGER PRIMARY KEY,
+        signature TEXT NOT NULL UNIQUE,
+        mint TEXT NOT NULL,
+        side TEXT NOT NULL,
+        token_amount INTEGER NOT NULL,
+        price REAL NOT NULL,
+        slippage_bps REAL NOT NULL,
+        tip INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX orders_mint ON orders (mint);
+
+    CREATE TABLE fills (
+        id INTEGER PRIMARY KEY,
+        signature TEXT NOT NULL UNIQUE,
+        slot INTEGER NOT NULL,
+        mint TEXT,
+        success INTEGER NOT NULL,
+        sol_change INTEGER NOT NULL,
+        base_fee INTEGER NOT NULL,
+        priority_fee INTEGER NOT NULL,
+        jito_tip INTEGER NOT NULL,
+        rent_reclaimed INTEGER NOT NULL,
+        sol_usd REAL,
+        recorded_at INTEGER NOT NULL
+    );
+    CREATE INDEX fills_mint ON fills (mint);",
+];
+
+/// 链上事件及其所在交易
+#[derive(Debug, Clone)]
+pub struct Stored<T> {
+    pub slot: u64,
+    pub signature: String,
+    pub event: T,
+}
+
+/// 自己发出的订单
+#[derive(Debug, Clone)]
+pub struct OrderRecord {
+    pub signature: String,
+    pub mint: Pubkey,
+    pub side: String,
+    pub token_amount: u64,
+    pub price: f32,
+    pub slippage_bps: f64,
+    pub tip: u64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FillRecord {
+    pub signature: String,
+    pub slot: u64,
+    pub mint: Option<Pubkey>,
+    pub success: bool,
+    pub sol_change: i64,
+    pub base_fee: u64,
+    pub priority_fee: u64,
+    pub jito_tip: u64,
+    pub rent_reclaimed: u64,
+    pub sol_usd: Option<f64>,
+}
+
+impl From<&Fill> for FillRecord {
+    fn from(fill: &Fill) -> Self {
+        Self {
+            signature: fill.signature.to_string(),
+            slot: fill.slot,
+            mint: fill.mint,
+            success: fill.success,
+            sol_change: fill.sol_change,
+            base_fee: fill.base_fee,
+            priority_fee: fill.priority_fee,
+            jito_tip: fill.jito_tip,
+            rent_reclaimed: fill.rent_reclaimed,
+            sol_usd: fill.sol_usd,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum StoreRecord {
+    Create(Stored<CreateEvent>),
+    Trade(Stored<TradeEvent>),
+    Complete(Stored<CompleteEvent>),
+    Order(OrderRecord),
+    Fill(FillRecord),
+}
+
+impl StoreRecord {
+    pub fn from_event(slot: u64, signature: &str, event: TargetEvent) -> Self {
+        let signature = signature.to_string();
+        match event {
+            TargetEvent::PumpfunBuy(event) | TargetEvent::PumpfunSell(event) => {
+                Self::Trade(Stored { slot, signature, event })
+            }
+            TargetEvent::PumpfunCreate(event) => Self::Create(Stored { slot, signature, event }),
+            TargetEvent::PumpfunComplete(event) => Self::Complete(Stored { slot, signature, event }),
+        }
+    }
+}
+
+enum Message {
+    Record(StoreRecord),
+    Flush(Sender<()>),
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn write(tx: &Connection, record: &StoreRecord, now: i64) -> rusqlite::Result<()> {
+    match record {
+        StoreRecord::Create(Stored { slot, signature, event }) => {
+            tx.prepare_cached(
+                "INSERT INTO create_events
+                 (slot, signature, mint, bonding_curve, user, name, symbol, uri, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?
+            .execute(params![
+                *slot as i64,
+                signature,
+                event.mint.to_string(),
+                event.bonding_curve.to_string(),
+                event.user.to_string(),
+                event.name,
+                event.symbol,
+                event.uri,
+                now,
+            ])?;
+        }
+        StoreRecord::Trade(Stored { slot, signature, event }) => {
+            tx.prepare_cached(
+                "INSERT INTO trade_events
+                 (slot, signature, mint, user, is_buy, sol_amount, token_amount, timestamp,
+                  virtual_sol_reserves, virtual_token_reserves, real_sol_reserves,
+                  real_token_reserves, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?
+            .execute(params![
+                *slot as i64,
+                signature,
+                event.mint.to_string(),
+                event.user.to_string(),
+                event.is_buy,
+                event.sol_amount as i64,
+                event.token_amount as i64,
+                event.timestamp,
+                event.virtual_sol_reserves as i64,
+                event.virtual_token_reserves as i64,
+                event.real_sol_reserves as i64,
+                event.real_token_reserves as i64,
+                now,
+            ])?;
+        }
+        StoreRecord::Complete(Stored { slot, signature, event }) => {
+            tx.prepare_cached(
+                "INSERT INTO complete_events
+                 (slot, signature, mint, user, bonding_curve, timestamp, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?
+            .execute(params![
+                *slot as i64,
+                signature,
+                event.mint.to_string(),
+                event.user.to_string(),
+                event.bonding_curve.to_string(),
+                event.timestamp,
+                now,
+            ])?;
+        }
+        StoreRecord::Order(order) => {
+            tx.prepare_cached(
+                "INSERT OR REPLACE INTO orders
+                 (signature, mint, side, token_amount, price, slippage_bps, tip, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?
+            .execute(params![
+                order.signature,
+                order.mint.to_string(),
+                order.side,
+                order.token_amount as i64,
+                order.price as f64,
+                order.slippage_bps,
+                order.tip as i64,
+                order.created_at,
+            ])?;
+        }
+        StoreRecord::Fill(fill) => {
+            tx.prepare_cached(
+                "INSERT OR REPLACE INTO fills
+                 (signature, slot, mint, success, sol_change, base_fee, priority_fee, jito_tip,
+                  rent_reclaimed, sol_usd, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?
+            .execute(params![
+                fill.signature,
+                fill.slot as i64,
+                fill.mint.map(|mint| mint.to_string()),
+                fill.success,
+                fill.sol_change,
+                fill.base_fee as i64,
+                fill.priority_fee as i64,
+                fill.jito_tip as i64,
+                fill.rent_reclaimed as i64,
+                fill.sol_usd,
+                now,
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+/// 后台写线程：阻塞等待第一条记录，再把队列里已有的记录合并到同一个事务。
+/// 每条记录在自己的 savepoint 中写入，写入失败只丢弃这一条
+fn run_writer(mut conn: Connection, receiver: Receiver<Message>) {
+    while let Ok(first) = receiver.recv() {
+        let mut batch = vec![first];
+        while batch.len() < MAX_BATCH {
+            match receiver.try_recv() {
+                Ok(message) => batch.push(message),
+                Err(_) => break,
+            }
+        }
+
+        let now = unix_now();
+        let mut flushes = vec![];
+        let result = conn.transaction().and_then(|mut tx| {
+            for message in &batch {
+                match message {
+                    Message::Record(record) => {
+                        let savepoint = tx.savepoint()?;
+                        match write(&savepoint, record, now) {
+                            Ok(()) => savepoint.commit()?,
+                            // savepoint 释放时回滚这一条
+                            Err(e) => error!(?record, "写入数据库失败，丢弃记录: {:?}", e),
+                        }
+                    }
+                    Message::Flush(done) => flushes.push(done.clone()),
+                }
+            }
+            tx.commit()
+        });
+        if let Err(e) = result {
+            error!(records = batch.len(), "写入数据库失败: {:?}", e);
+        }
+        for done in flushes {
+            done.send(()).ok();
+        }
+    }
+}
+
+fn parse_pubkey(s: String) -> rusqlite::Result<Pubkey> {
+    s.parse()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+fn trade_from_row(row: &Row) -> rusqlite::Result<Stored<TradeEvent>> {
+    Ok(Stored {
+        slot: row.get::<_, i64>(0)? as u64,
+        signature: row.get(1)?,
+        event: TradeEvent {
+            mint: parse_pubkey(row.get(2)?)?,
+            user: parse_pubkey(row.get(3)?)?,
+            is_buy: row.get(4)?,
+            sol_amount: row.get::<_, i64>(5)? as u64,
+            token_amount: row.get::<_, i64>(6)? as u64,
+            timestamp: row.get(7)?,
+            virtual_sol_reserves: row.get::<_, i64>(8)? as u64,
+            virtual_token_reserves: row.get::<_, i64>(9)? as u64,
+            real_sol_reserves: row.get::<_, i64>(10)? as u64,
+            real_token_reserves: row.get::<_, i64>(11)? as u64,
+        },
+    })
+}
+
+/// SQLite 存储：写入排队后由后台线程批量落盘，读取使用单独的连接
+#[derive(Clone)]
+pub struct Store {
+    sender: Sender<Message>,
+    reader: Arc<Mutex<Connection>>,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        migrate(&mut conn)?;
+
+        let reader = Connection::open(path)?;
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("store-writer".to_string())
+            .spawn(move || run_writer(conn, receiver))?;
+
+        Ok(Self {
+            sender,
+            reader: Arc::new(Mutex::new(reader)),
+        })
+    }
+
+    /// 放入写队列，不等待落盘
+    pub fn record(&self, record: StoreRecord) {
+        if self.sender.send(Message::Record(record)).is_err() {
+            error!("数据库写线程已停止，丢弃记录");
+        }
+    }
+
+    /// 阻塞直到之前放入队列的记录都已写入
+    pub fn flush(&self) -> Result<()> {
+        let (done, wait) = mpsc::channel();
+        self.sender
+            .send(Message::Flush(done))
+            .map_err(|_| anyhow!("store: writer thread stopped"))?;
+        wait.recv()?;
+        Ok(())
+    }
+
+    pub fn schema_version(&self) -> Result<usize> {
+        let conn = self.reader.lock().unwrap();
+        Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+
+    pub fn create_event(&self, mint: &Pubkey) -> Result<Option<Stored<CreateEvent>>> {
+        let conn = self.reader.lock().unwrap();
+        let create = conn
+            .query_row(
+                "SELECT slot, signature, mint, bonding_curve, user, name, symbol, uri
+                 FROM create_events WHERE mint = ?1 ORDER BY slot LIMIT 1",
+                params![mint.to_string()],
+                |row| {
+                    Ok(Stored {
+                        slot: row.get::<_, i64>(0)? as u64,
+                        signature: row.get(1)?,
+                        event: CreateEvent {
+                            mint: parse_pubkey(row.get(2)?)?,
+                            bonding_curve: parse_pubkey(row.get(3)?)?,
+                            user: parse_pubkey(row.get(4)?)?,
+                            name: row.get(5)?,
+                            symbol: row.get(6)?,
+                            uri: row.get(7)?,
+                        },
+                    })
+                },
+            )
+            .optional()?;
+        Ok(create)
+    }
+
+    /// 某个 mint 按 slot 排序的成交记录
+    pub fn trades(&self, mint: &Pubkey) -> Result<Vec<Stored<TradeEvent>>> {
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT slot, signature, mint, user, is_buy, sol_amount, token_amount, timestamp,
+                    virtual_sol_reserves, virtual_token_reserves, real_sol_reserves,
+                    real_token_reserves
+             FROM trade_events WHERE mint = ?1 ORDER BY slot, id",
+        )?;
+        let trades = stmt
+            .query_map(params![mint.to_string()], trade_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(trades)
+    }
+
+    /// 有成交记录的所有 mint
+    pub fn traded_mints(&self) -> Result<Vec<Pubkey>> {
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare_cached("SELECT DISTINCT mint FROM trade_events")?;
+        let mints = stmt
+            .query_map([], |row| parse_pubkey(row.get(0)?))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(mints)
+    }
+
+    pub fn is_complete(&self, mint: &Pubkey) -> Result<bool> {
+        let conn = self.reader.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM complete_events WHERE mint = ?1",
+            params![mint.to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    pub fn orders(&self, mint: &Pubkey) -> Result<Vec<OrderRecord>> {
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT signature, mint, side, token_amount, price, slippage_bps, tip, created_at
+             FROM orders WHERE mint = ?1 ORDER BY created_at, id",
+        )?;
+        let orders = stmt
+            .query_map(params![mint.to_string()], |row| {
+                Ok(OrderRecord {
+                    signature: row.get(0)?,
+                    mint: parse_pubkey(row.get(1)?)?,
+                    side: row.get(2)?,
+                    token_amount: row.get::<_, i64>(3)? as u64,
+                    price: row.get::<_, f64>(4)? as f32,
+                    slippage_bps: row.get(5)?,
+                    tip: row.get::<_, i64>(6)? as u64,
+                    created_at: row.get(7)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(orders)
+    }
+
+    pub fn fills(&self, mint: &Pubkey) -> Result<Vec<FillRecord>> {
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT signature, slot, mint, success, sol_change, base_fee, priority_fee, jito_tip,
+                    rent_reclaimed, sol_usd
+             FROM fills WHERE mint = ?1 ORDER BY slot, id",
+        )?;
+        let fills = stmt
+            .query_map(params![mint.to_string()], |row| {
+                Ok(FillRecord {
+                    signature: row.get(0)?,
+                    slot: row.get::<_, i64>(1)? as u64,
+                    mint: row.get::<_, Option<String>>(2)?.map(parse_pubkey).transpose()?,
+                    success: row.get(3)?,
+                    sol_change: row.get(4)?,
+                    base_fee: row.get::<_, i64>(5)? as u64,
+                    priority_fee: row.get::<_, i64>(6)? as u64,
+                    jito_tip: row.get::<_, i64>(7)? as u64,
+                    rent_reclaimed: row.get::<_, i64>(8)? as u64,
+                    sol_usd: row.get(9)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(fills)
+    }
+}
+
+#[test]
+fn test_store_trade_history() {
+    let path = std::env::temp_dir().join(format!("solana_pump_store_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let store = Store::open(&path).unwrap();
+    assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+
+    let mint = Pubkey::new_unique();
+    let trade = TradeEvent {
+        mint,
+        sol_amount: 1_000_000,
+        token_amount: 35_000_000_000,
+        is_buy: true,
+        user: Pubkey::new_unique(),
+        timestamp: 1_700_000_000,
+        virtual_sol_reserves: 30_001_000_000,
+        virtual_token_reserves: 1_072_965_191_000_000,
+        real_sol_reserves: 1_000_000,
+        real_token_reserves: 793_065_191_000_000,
+    };
+    for slot in [2, 1] {
+        store.record(StoreRecord::from_event(slot, "sig", TargetEvent::PumpfunBuy(trade)));
+    }
+    store.flush().unwrap();
+
+    // 写入失败的记录（NaN 存成 NULL，违反 NOT NULL）不影响同一批的其他记录
+    let order = OrderRecord {
+        signature: "order".to_string(),
+        mint,
+        side: "buy".to_string(),
+        token_amount: 1,
+        price: 0.1,
+        slippage_bps: 100.0,
+        tip: 0,
+        created_at: 1,
+    };
+    store.record(StoreRecord::Order(order.clone()));
+    store.record(StoreRecord::Order(OrderRecord { signature: "bad".to_string(), price: f32::NAN, ..order.clone() }));
+    store.record(StoreRecord::Order(OrderRecord { signature: "order2".to_string(), ..order }));
+    store.flush().unwrap();
+    let orders = store.orders(&mint).unwrap();
+    assert_eq!(orders.iter().map(|o| o.signature.as_str()).collect::<Vec<_>>(), ["order", "order2"]);
+
+    let trades = store.trades(&mint).unwrap();
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0].slot, 1);
+    assert_eq!(trades[1].event.virtual_token_reserves, trade.virtual_token_reserves);
+    assert!(store.trades(&Pubkey::new_unique()).unwrap().is_empty());
+
+    drop(store);
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,296 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use dashmap::{DashMap, DashSet};
+use futures::future::join_all;
+use solana_client::{
+    nonblocking::{rpc_client::RpcClient, tpu_client::TpuClient},
+    rpc_config::RpcSendTransactionConfig,
+    tpu_client::TpuClientConfig,
+};
+use solana_quic_client::{QuicConfig, QuicConnectionManager, QuicPool};
+use solana_sdk::{signature::Signature, transaction::Transaction};
+
+use crate::{jito::JitoPool, metrics};
+
+pub type QuicTpuClient = TpuClient<QuicPool, QuicConnectionManager, QuicConfig>;
+
+/// 交易广播通道
+pub enum SubmitPath {
+    /// RPC 节点，跳过 preflight 直接转发
+    Rpc { name: String, client: Arc<RpcClient> },
+    /// Jito block engine 区域池，交易和 tip 交易一起打包成 bundle
+    Jito { name: String, client: Arc<JitoPool> },
+    /// 通过 QUIC 直接发送给当前及后续 leader 的 TPU
+    Tpu { name: String, client: Arc<QuicTpuClient> },
+}
+
+impl SubmitPath {
+    pub fn rpc(url: &str) -> Self {
+        Self::Rpc {
+            name: format!("rpc:{}", url),
+            client: Arc::new(RpcClient::new(url.to_string())),
+        }
+    }
+
+    pub fn jito(pool: Arc<JitoPool>) -> Self {
+        Self::Jito {
+            name: "jito".to_string(),
+            client: pool,
+        }
+    }
+
+    pub async fn tpu(rpc: Arc<RpcClient>, ws_url: &str) -> Result<Self> {
+        let client = TpuClient::new("solana_pump", rpc, ws_url, TpuClientConfig::default())
+            .await
+            .map_err(|e| anyhow!("tpu client: {}", e))?;
+        Ok(Self::Tpu {
+            name: "tpu".to_string(),
+            client: Arc::new(client),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Rpc { name, .. } | Self::Jito { name, .. } | Self::Tpu { name, .. } => name,
+        }
+    }
+
+    async fn send(&self, tx: &Transaction, tip_tx: Option<&Transaction>) -> Result<String> {
+        match self {
+            Self::Rpc { client, .. } => {
+                let config = RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    max_retries: Some(0),
+                    ..Default::default()
+                };
+                let sig = client.send_transaction_with_config(tx, config).await?;
+                Ok(sig.to_string())
+            }
+            Self::Jito { client, .. } => {
+                let tip_tx = tip_tx.ok_or_else(|| anyhow!("jito: bundle requires a tip transaction"))?;
+                client.send_bundle(vec![tx.clone(), tip_tx.clone()]).await
+            }
+            Self::Tpu { client, .. } => {
+                client.try_send_transaction(tx).await?;
+                Ok(tx.signatures[0].to_string())
+            }
+        }
+    }
+}
+
+/// 单个通道的一次发送结果
+#[derive(Debug, Clone)]
+pub struct PathResult {
+    pub path: String,
+    pub latency: Duration,
+    /// 成功时为签名或 bundle id
+    pub result: Result<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubmitReport {
+    pub signature: Signature,
+    pub results: Vec<PathResult>,
+    /// 最先成功返回的通道
+    pub first_ack: Option<String>,
+}
+
+impl SubmitReport {
+    pub fn is_accepted(&self) -> bool {
+        self.first_ack.is_some()
+    }
+
+    /// 最先成功返回的通道的耗时
+    pub fn ack_latency(&self) -> Option<Duration> {
+        let first_ack = self.first_ack.as_ref()?;
+        self.results.iter().find(|r| &r.path == first_ack).map(|r| r.latency)
+    }
+}
+
+/// 每个通道的累计统计
+#[derive(Debug, Clone, Default)]
+pub struct PathStats {
+    pub sent: u64,
+    pub failed: u64,
+    /// 该通道最先返回成功的次数
+    pub first_ack: u64,
+    /// 交易上链时该通道是最先返回成功的次数。所有通道发送同一笔交易，无法区分实际由哪个通道送达
+    pub first_ack_landed: u64,
+    pub total_latency: Duration,
+    pub max_latency: Duration,
+}
+
+impl PathStats {
+    pub fn avg_latency(&self) -> Duration {
+        if self.sent == 0 {
+            Duration::ZERO
+        } else {
+            self.total_latency / self.sent as u32
+        }
+    }
+}
+
+/// 同一笔签名交易并发发往所有配置的通道
+pub struct Submitter {
+    paths: Vec<SubmitPath>,
+    stats: DashMap<String, PathStats>,
+    pending: DashMap<Signature, String>,
+    /// Jito 接受了 bundle 的待确认交易
+    bundled: DashSet<Signature>,
+}
+
+impl Submitter {
+    pub fn new(paths: Vec<SubmitPath>) -> Self {
+        let stats = DashMap::new();
+        for path in &paths {
+            stats.insert(path.name().to_string(), PathStats::default());
+        }
+        Self {
+            paths,
+            stats,
+            pending: DashMap::new(),
+            bundled: DashSet::new(),
+        }
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.paths.iter().map(|p| p.name())
+    }
+
+    /// 并发发送到所有通道，返回每个通道的结果。
+    /// Jito 通道只有在提供 `tip_tx` 时才会发送。
+    pub async fn submit(&self, tx: &Transaction, tip_tx: Option<&Transaction>) -> Result<SubmitReport> {
+        let signature = *tx
+            .signatures
+            .first()
+            .ok_or_else(|| anyhow!("submit: transaction is not signed"))?;
+
+        let sends = self
+            .paths
+            .iter()
+            .filter(|path| tip_tx.is_some() || !matches!(path, SubmitPath::Jito { .. }))
+            .map(|path| async move {
+                let start = Instant::now();
+                let result = path.send(tx, tip_tx).await.map_err(|e| e.to_string());
+                PathResult {
+                    path: path.name().to_string(),
+                    latency: start.elapsed(),
+                    result,
+                }
+            });
+        let results = join_all(sends).await;
+
+        let first_ack = results
+            .iter()
+            .filter(|r| r.result.is_ok())
+            .min_by_key(|r| r.latency)
+            .map(|r| r.path.clone());
+
+        for r in &results {
+            let mut stats = self.stats.entry(r.path.clone()).or_default();
+            stats.sent += 1;
+            stats.total_latency += r.latency;
+            stats.max_latency = stats.max_latency.max(r.latency);
+            if r.result.is_err() {
+                stats.failed += 1;
+            }
+            if first_ack.as_ref() == Some(&r.path) {
+                stats.first_ack += 1;
+            }
+            let result = if r.result.is_ok() { "ok" } else { "error" };
+            metrics::SUBMISSIONS.with_label_values(&[&r.path, result]).inc();
+        }
+        // Jito 通道返回成功说明 bundle 被接受
+        let bundle_accepted = results.iter().any(|r| {
+            r.result.is_ok() && self.paths.iter().any(|p| p.name() == r.path && matches!(p, SubmitPath::Jito { .. }))
+        });
+        if bundle_accepted {
+            metrics::BUNDLES_SENT.inc();
+            self.bundled.insert(signature);
+        }
+        if let Some(path) = &first_ack {
+            self.pending.insert(signature, path.clone());
+        }
+
+        Ok(SubmitReport {
+            signature,
+            results,
+            first_ack,
+        })
+    }
+
+    /// 交易上链后调用，把落地计入最先返回成功的通道（不是实际送达的通道）
+    pub fn mark_landed(&self, signature: &Signature) -> Option<String> {
+        if self.bundled.remove(signature).is_some() {
+            metrics::BUNDLES_LANDED.inc();
+        }
+        let (_, path) = self.pending.remove(signature)?;
+        if let Some(mut stats) = self.stats.get_mut(&path) {
+            stats.first_ack_landed += 1;
+        }
+        Some(path)
+    }
+
+    /// 交易过期或失败后调用，清理待确认记录
+    pub fn forget(&self, signature: &Signature) {
+        self.pending.remove(signature);
+        self.bundled.remove(signature);
+    }
+
+    pub fn stats(&self) -> Vec<(String, PathStats)> {
+        let mut stats: Vec<_> = self
+            .stats
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone()))
+            .collect();
+        stats.sort_by(|a, b| a.0.cmp(&b.0));
+        stats
+    }
+}
+
+#[tokio::test]
+async fn test_submit_stats() {
+    use solana_sdk::{hash::Hash, signature::Keypair, signer::Signer, system_instruction};
+
+    // mock RPC："succeeds" 返回交易签名，"fails" 返回空结果
+    let path = |name: &str, url: &str| SubmitPath::Rpc {
+        name: name.to_string(),
+        client: Arc::new(RpcClient::new_mock(url.to_string())),
+    };
+    let submitter = Submitter::new(vec![path("rpc:a", "succeeds"), path("rpc:b", "fails")]);
+    assert_eq!(submitter.paths().collect::<Vec<_>>(), ["rpc:a", "rpc:b"]);
+
+    let payer = Keypair::new();
+    let ix = system_instruction::transfer(&payer.pubkey(), &solana_sdk::pubkey::Pubkey::new_unique(), 1);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], Hash::new_unique());
+    let report = submitter.submit(&tx, None).await.unwrap();
+    assert_eq!(report.signature, tx.signatures[0]);
+    assert!(report.is_accepted());
+    assert_eq!(report.first_ack.as_deref(), Some("rpc:a"));
+    assert!(report.ack_latency().is_some());
+    assert_eq!(report.results.len(), 2);
+
+    // 落地计入最先返回成功的通道，只计一次
+    assert_eq!(submitter.mark_landed(&tx.signatures[0]).as_deref(), Some("rpc:a"));
+    assert_eq!(submitter.mark_landed(&tx.signatures[0]), None);
+    let stats = submitter.stats();
+    let (a, b) = (&stats[0].1, &stats[1].1);
+    assert_eq!((a.sent, a.failed, a.first_ack, a.first_ack_landed), (1, 0, 1, 1));
+    assert_eq!((b.sent, b.failed, b.first_ack, b.first_ack_landed), (1, 1, 0, 0));
+
+    // 过期或失败的交易清理后不再计入落地
+    let report = submitter.submit(&tx, None).await.unwrap();
+    submitter.forget(&report.signature);
+    assert_eq!(submitter.mark_landed(&report.signature), None);
+    assert_eq!(submitter.stats()[0].1.first_ack_landed, 1);
+
+    // 所有通道都失败时不算接受
+    let submitter = Submitter::new(vec![path("rpc:b", "fails")]);
+    let report = submitter.submit(&tx, None).await.unwrap();
+    assert!(!report.is_accepted());
+    assert_eq!(report.ack_latency(), None);
+}
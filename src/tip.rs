@@ -0,0 +1,174 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tracing::warn;
+
+pub const JITO_TIP_FLOOR_URL: &str = "https://bundles.jito.wtf/api/v1/bundles/tip_floor";
+
+/// Jito tip floor 统计，单位为 SOL
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TipFloor {
+    pub landed_tips_25th_percentile: f64,
+    pub landed_tips_50th_percentile: f64,
+    pub landed_tips_75th_percentile: f64,
+    pub landed_tips_95th_percentile: f64,
+    pub landed_tips_99th_percentile: f64,
+    #[serde(default)]
+    pub ema_landed_tips_50th_percentile: f64,
+}
+
+impl TipFloor {
+    /// 在已知分位点之间线性插值，返回 lamports
+    pub fn percentile_lamports(&self, percentile: f64) -> u64 {
+        let points = [
+            (25.0, self.landed_tips_25th_percentile),
+            (50.0, self.landed_tips_50th_percentile),
+            (75.0, self.landed_tips_75th_percentile),
+            (95.0, self.landed_tips_95th_percentile),
+            (99.0, self.landed_tips_99th_percentile),
+        ];
+        let p = percentile.clamp(points[0].0, points[points.len() - 1].0);
+        let mut sol = points[points.len() - 1].1;
+        for w in points.windows(2) {
+            let ((p0, v0), (p1, v1)) = (w[0], w[1]);
+            if p <= p1 {
+                sol = v0 + (v1 - v0) * (p - p0) / (p1 - p0);
+                break;
+            }
+        }
+        (sol * 1e9) as u64
+    }
+}
+
+/// tip floor 统计的来源，`File` 用于本地测试
+#[derive(Debug, Clone)]
+pub enum TipFloorSource {
+    Http(String),
+    File(PathBuf),
+}
+
+impl TipFloorSource {
+    /// `file://` 前缀读取本地 JSON，其余按 HTTP 地址处理
+    pub fn from_url(url: &str) -> Self {
+        match url.strip_prefix("file://") {
+            Some(path) => Self::File(PathBuf::from(path)),
+            None => Self::Http(url.to_string()),
+        }
+    }
+
+    pub async fn fetch(&self) -> Result<TipFloor> {
+        let body = match self {
+            Self::Http(url) => reqwest::get(url).await?.error_for_status()?.text().await?,
+            Self::File(path) => tokio::fs::read_to_string(path).await?,
+        };
+        // 接口返回数组，取最新的一条
+        let floors: Vec<TipFloor> = serde_json::from_str(&body)?;
+        floors
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("tip floor: empty response"))
+    }
+}
+
+/// 按 tip floor 和预期利润选择 Jito tip
+#[derive(Debug, Clone)]
+pub struct TipPolicy {
+    /// 利润为 0 时使用的分位
+    pub min_percentile: f64,
+    /// 利润达到 `profit_scale` 时使用的分位
+    pub max_percentile: f64,
+    /// 预期利润 (lamports)，达到后使用 `max_percentile`
+    pub profit_scale: u64,
+    /// tip 的下限，利润比例截断后仍不低于该值
+    pub min_tip: u64,
+    pub max_tip: u64,
+    /// tip 不超过预期利润的比例，低于 `min_tip` 时使用 `min_tip`
+    pub max_profit_fraction: f64,
+}
+
+impl Default for TipPolicy {
+    fn default() -> Self {
+        Self {
+            min_percentile: 50.0,
+            max_percentile: 95.0,
+            profit_scale: 1_000_000_000,
+            min_tip: 1_000,
+            max_tip: 10_000_000,
+            max_profit_fraction: 0.5,
+        }
+    }
+}
+
+impl TipPolicy {
+    /// `expected_profit` 为 `check_sol_change` 的结果（不含 tip），单位 lamports
+    pub fn choose(&self, floor: &TipFloor, expected_profit: f32) -> u64 {
+        let profit = expected_profit.max(0.0) as f64;
+        let scale = (profit / self.profit_scale.max(1) as f64).min(1.0);
+        let percentile = self.min_percentile + (self.max_percentile - self.min_percentile) * scale;
+
+        let cap = (profit * self.max_profit_fraction) as u64;
+        floor
+            .percentile_lamports(percentile)
+            .min(cap)
+            .clamp(self.min_tip, self.max_tip.max(self.min_tip))
+    }
+}
+
+/// 后台定时刷新的 tip floor
+#[derive(Clone, Default)]
+pub struct TipFloorCache {
+    inner: Arc<RwLock<Option<TipFloor>>>,
+}
+
+impl TipFloorCache {
+    pub fn get(&self) -> Option<TipFloor> {
+        self.inner.read().unwrap().clone()
+    }
+
+    pub fn set(&self, floor: TipFloor) {
+        *self.inner.write().unwrap() = Some(floor);
+    }
+
+    pub fn spawn_refresh(source: TipFloorSource, interval: Duration) -> Self {
+        let cache = Self::default();
+        let handle = cache.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match source.fetch().await {
+                    Ok(floor) => handle.set(floor),
+                    Err(e) => warn!("刷新 tip floor 失败: {:?}", e),
+                }
+            }
+        });
+        cache
+    }
+}
+
+#[test]
+fn test_tip_policy_caps_at_profit_fraction() {
+    let floor = TipFloor {
+        landed_tips_25th_percentile: 0.00001,
+        landed_tips_50th_percentile: 0.00002,
+        landed_tips_75th_percentile: 0.0001,
+        landed_tips_95th_percentile: 0.001,
+        landed_tips_99th_percentile: 0.01,
+        ema_landed_tips_50th_percentile: 0.00002,
+    };
+    let policy = TipPolicy::default();
+
+    assert_eq!(floor.percentile_lamports(50.0), 20_000);
+    // 利润很小时 tip 被利润比例截断
+    assert_eq!(policy.choose(&floor, 10_000.0), 5_000);
+    // 利润比例低于 min_tip 时使用 min_tip，亏本交易也一样，是否卖出由利润扣除 tip 后决定
+    assert_eq!(policy.choose(&floor, 1_000.0), 1_000);
+    assert_eq!(policy.choose(&floor, -1.0), 1_000);
+    // 利润足够大时使用最高分位
+    assert_eq!(policy.choose(&floor, 2_000_000_000.0), 1_000_000);
+}
@@ -1,8 +1,8 @@
 
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{account::Account, pubkey::Pubkey};
 use anyhow::Result;
-use pyth_sdk_solana::{state::SolanaPriceAccount, Price}; 
+
+use crate::price::PriceOracle;
  
 pub fn check_sol_change(
     price: f32,
@@ -15,18 +15,22 @@ pub fn check_sol_change(
     Ok(sol_lamports_cost)
 }
 
+/// 自动卖出的退出规则，实盘和回测共用：扣除成本和 tip 后的利润达到 `min_profit` 时卖出
+pub fn should_sell(profit: f32, min_profit: u64) -> bool {
+    profit >= 0.0 && profit >= min_profit as f32
+}
+
+/// Pyth SOL/USD 价格账户
+pub const PYTH_SOL_USD: &str = "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG";
+
+/// 读取 SOL/USD 价格，默认检查时效和置信区间
 pub async fn get_sol_price(rpc: &RpcClient) -> Result<f64> {
-    let price_key: Pubkey = "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG".parse().unwrap();
-    let mut price_account: Account = rpc.get_account(&price_key).await?;
-    match SolanaPriceAccount::account_to_feed(&price_key, &mut price_account) {
-        Ok(feed) => {
-            let current_price: Price = feed.get_price_unchecked();
-            Ok(current_price.price as f64 * 10.0f64.powi(current_price.expo))
-        },
-        Err(e) => {
-            Err(e.into())
-        }
-    }
+    Ok(PriceOracle::default().fetch(rpc).await?.price)
+}
+
+/// lamports 换算成美元
+pub fn lamports_to_usd(lamports: i64, sol_price_usd: f64) -> f64 {
+    lamports as f64 / 1e9 * sol_price_usd
 }
 
 
@@ -0,0 +1,298 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{bail, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signer::Signer};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+use crate::pnl::{Fill, LAMPORTS_PER_SIGNATURE};
+
+/// 每个钱包保留的 SOL（lamports），用于交易费和 token 账户租金，不参与买入和转出
+pub const WALLET_RESERVE: u64 = 10_000_000;
+
+/// 一笔买入在买入金额之外的花费：签名费、优先费和 tip（tip 是单独的一笔交易）
+pub fn buy_fees(priority_fee: u64, tip: u64) -> u64 {
+    LAMPORTS_PER_SIGNATURE * (1 + u64::from(tip > 0)) + priority_fee + tip
+}
+
+/// 钱包池中的一个钱包，余额和持仓由 RPC 刷新并随成交更新
+pub struct Wallet {
+    pub signer: Arc<dyn Signer + Send + Sync>,
+    pub sol: u64,
+    pub positions: HashMap<Pubkey, u64>,
+}
+
+impl Wallet {
+    pub fn pubkey(&self) -> Pubkey {
+        self.signer.pubkey()
+    }
+
+    pub fn position(&self, mint: &Pubkey) -> u64 {
+        self.positions.get(mint).copied().unwrap_or_default()
+    }
+
+    /// 扣除保留金额后可以花费的 SOL
+    pub fn available(&self) -> u64 {
+        self.sol.saturating_sub(WALLET_RESERVE)
+    }
+}
+
+/// 钱包之间的 SOL 转账
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transfer {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub lamports: u64,
+}
+
+/// 多个钱包：买入轮流拆分到各个钱包，卖出由持有 token 的钱包发出
+pub struct WalletPool {
+    wallets: Vec<Wallet>,
+    /// 下次拆分买入时第一个使用的钱包
+    next: usize,
+}
+
+impl WalletPool {
+    pub fn new(signers: Vec<Arc<dyn Signer + Send + Sync>>) -> Result<Self> {
+        if signers.is_empty() {
+            bail!("wallet pool needs at least one wallet");
+        }
+        let mut wallets: Vec<Wallet> = Vec::with_capacity(signers.len());
+        for signer in signers {
+            let pubkey = signer.try_pubkey()?;
+            if wallets.iter().any(|w| w.pubkey() == pubkey) {
+                bail!("wallet {} is listed twice", pubkey);
+            }
+            wallets.push(Wallet {
+                signer,
+                sol: 0,
+                positions: HashMap::new(),
+            });
+        }
+        Ok(Self { wallets, next: 0 })
+    }
+
+    pub fn wallets(&self) -> &[Wallet] {
+        &self.wallets
+    }
+
+    pub fn get(&self, wallet: &Pubkey) -> Option<&Wallet> {
+        self.wallets.iter().find(|w| w.pubkey() == *wallet)
+    }
+
+    /// 第一个钱包，单钱包操作（创建代币、paper 模式）使用
+    pub fn primary(&self) -> &Wallet {
+        &self.wallets[0]
+    }
+
+    /// 从链上读取每个钱包的 SOL 余额和 `mints` 的持仓
+    pub async fn refresh(&mut self, rpc: &RpcClient, mints: &[Pubkey]) -> Result<()> {
+        for wallet in &mut self.wallets {
+            let pubkey = wallet.pubkey();
+            wallet.sol = rpc.get_balance(&pubkey).await?;
+            for mint in mints {
+                let tokens = get_token_balance(rpc, &pubkey, mint).await?;
+                wallet.positions.insert(*mint, tokens);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn total_sol(&self) -> u64 {
+        self.wallets.iter().map(|w| w.sol).sum()
+    }
+
+    pub fn total_position(&self, mint: &Pubkey) -> u64 {
+        self.wallets.iter().map(|w| w.position(mint)).sum()
+    }
+
+    /// 持有 `mint` 的钱包和数量，卖出时每个钱包各发一笔
+    pub fn holders(&self, mint: &Pubkey) -> Vec<(Pubkey, u64)> {
+        self.wallets
+            .iter()
+            .filter(|w| w.position(mint) > 0)
+            .map(|w| (w.pubkey(), w.position(mint)))
+            .collect()
+    }
+
+    /// 持有 `mint` 的钱包的签名者和持仓，拿到后释放钱包池的锁再签名
+    pub fn holder_signers(&self, mint: &Pubkey) -> Vec<(Arc<dyn Signer + Send + Sync>, u64)> {
+        self.wallets
+            .iter()
+            .filter(|w| w.position(mint) > 0)
+            .map(|w| (w.signer.clone(), w.position(mint)))
+            .collect()
+    }
+
+    /// 把花费 `lamports` 的买入拆分到多个钱包：从上次之后的钱包开始轮流分配，
+    /// 每个钱包不超过 `max_per_wallet`，加上 `slippage_bps` 的滑点和 `fees` 后不超过可用 SOL
+    pub fn split_buy(
+        &mut self,
+        lamports: u64,
+        max_per_wallet: Option<u64>,
+        slippage_bps: f64,
+        fees: u64,
+    ) -> Result<Vec<(Pubkey, u64)>> {
+        let n = self.wallets.len();
+        let mut remaining = lamports;
+        let mut parts = Vec::new();
+        for i in 0..n {
+            if remaining == 0 {
+                break;
+            }
+            let wallet = &self.wallets[(self.next + i) % n];
+            // 交易的最多花费是金额加上滑点
+            let affordable = (wallet.available().saturating_sub(fees) as f64 * 10_000.0 / (10_000.0 + slippage_bps)) as u64;
+            let amount = remaining
+                .min(affordable)
+                .min(max_per_wallet.unwrap_or(u64::MAX));
+            if amount > 0 {
+                parts.push((wallet.pubkey(), amount));
+                remaining -= amount;
+            }
+        }
+        if remaining > 0 {
+            bail!(
+                "wallet pool can spend only {} of {} lamports",
+                lamports - remaining,
+                lamports
+            );
+        }
+        self.next = (self.next + 1) % n;
+        Ok(parts)
+    }
+
+    /// 平衡 SOL：高于平均余额的钱包转给低于平均余额的钱包，少于 `min_transfer` 的差额不转
+    pub fn rebalance(&self, min_transfer: u64) -> Vec<Transfer> {
+        let average = self.total_sol() / self.wallets.len() as u64;
+        let mut donors: Vec<(Pubkey, u64)> = self
+            .wallets
+            .iter()
+            .filter(|w| w.sol > average)
+            .map(|w| (w.pubkey(), w.sol - average))
+            .collect();
+        let mut transfers = Vec::new();
+        for wallet in self.wallets.iter().filter(|w| w.sol < average) {
+            let mut need = average - wallet.sol;
+            for (from, surplus) in donors.iter_mut() {
+                let lamports = need.min(*surplus);
+                if lamports < min_transfer {
+                    continue;
+                }
+                transfers.push(Transfer {
+                    from: *from,
+                    to: wallet.pubkey(),
+                    lamports,
+                });
+                *surplus -= lamports;
+                need -= lamports;
+            }
+        }
+        transfers
+    }
+
+    /// 按落地的成交更新钱包余额和持仓
+    pub fn apply_fill(&mut self, wallet: &Pubkey, fill: &Fill) {
+        let Some(wallet) = self.wallets.iter_mut().find(|w| w.pubkey() == *wallet) else {
+            return;
+        };
+        wallet.sol = wallet.sol.saturating_add_signed(fill.sol_change);
+        let owner = wallet.pubkey();
+        for trade in fill.trades.iter().filter(|t| t.user == owner) {
+            let position = wallet.positions.entry(trade.mint).or_default();
+            *position = match trade.is_buy {
+                true => position.saturating_add(trade.token_amount),
+                false => position.saturating_sub(trade.token_amount),
+            };
+        }
+    }
+}
+
+/// 钱包在 `mint` 上的持仓，token 账户不存在时为 0
+pub async fn get_token_balance(rpc: &RpcClient, wallet: &Pubkey, mint: &Pubkey) -> Result<u64> {
+    let token_ata = get_associated_token_address_with_program_id(wallet, mint, &spl_token::id());
+    let account = rpc
+        .get_account_with_commitment(&token_ata, CommitmentConfig::confirmed())
+        .await?;
+    if account.value.is_none() {
+        return Ok(0);
+    }
+    let balance = rpc
+        .get_token_account_balance_with_commitment(&token_ata, CommitmentConfig::confirmed())
+        .await?;
+    Ok(balance.value.amount.parse()?)
+}
+
+#[test]
+fn test_wallet_pool_split_route_and_rebalance() {
+    use crate::monitor::TradeEvent;
+    use solana_sdk::{signature::Keypair, signature::Signature};
+
+    let signers: Vec<Arc<dyn Signer + Send + Sync>> = (0..3).map(|_| Arc::new(Keypair::new()) as _).collect();
+    let keys: Vec<Pubkey> = signers.iter().map(|s| s.pubkey()).collect();
+    assert!(WalletPool::new(vec![signers[0].clone(), signers[0].clone()]).is_err());
+
+    let mut pool = WalletPool::new(signers).unwrap();
+    let mint = Pubkey::new_unique();
+    for (wallet, sol) in pool.wallets.iter_mut().zip([1_010_000_000, 210_000_000, 10_000_000]) {
+        wallet.sol = sol;
+    }
+
+    // 第三个钱包只有保留金额，不参与买入
+    let parts = pool.split_buy(1_100_000_000, None, 0.0, 0).unwrap();
+    assert_eq!(parts, vec![(keys[0], 1_000_000_000), (keys[1], 100_000_000)]);
+    // 轮到第二个钱包先买，每个钱包最多 150_000_000
+    let parts = pool.split_buy(300_000_000, Some(150_000_000), 0.0, 0).unwrap();
+    assert_eq!(parts, vec![(keys[1], 150_000_000), (keys[0], 150_000_000)]);
+    assert!(pool.split_buy(2_000_000_000, None, 0.0, 0).is_err());
+    // 加上 10% 滑点和手续费后，不够 1.1 SOL
+    let fees = buy_fees(0, 0);
+    assert_eq!(fees, 5000);
+    assert!(pool.split_buy(1_100_000_000, None, 1000.0, fees).is_err());
+    let parts = pool.split_buy(1_000_000_000, None, 1000.0, fees).unwrap();
+    assert_eq!(parts, vec![(keys[0], 909_086_363), (keys[1], 90_913_637)]);
+
+    // 成交后更新持仓，卖出路由到持有 token 的钱包
+    let fill = Fill {
+        signature: Signature::default(),
+        slot: 1,
+        success: true,
+        mint: Some(mint),
+        trades: vec![TradeEvent {
+            mint,
+            sol_amount: 100_000_000,
+            token_amount: 3_000_000,
+            is_buy: true,
+            user: keys[1],
+            timestamp: 0,
+            virtual_sol_reserves: 0,
+            virtual_token_reserves: 0,
+            real_sol_reserves: 0,
+            real_token_reserves: 0,
+        }],
+        sol_change: -101_000_000,
+        base_fee: 5000,
+        priority_fee: 0,
+        jito_tip: 0,
+        rent_reclaimed: 0,
+        sol_usd: None,
+    };
+    pool.apply_fill(&keys[1], &fill);
+    assert_eq!(pool.get(&keys[1]).unwrap().sol, 109_000_000);
+    assert_eq!(pool.holders(&mint), vec![(keys[1], 3_000_000)]);
+    let signers = pool.holder_signers(&mint);
+    assert_eq!(signers.len(), 1);
+    assert_eq!((signers[0].0.pubkey(), signers[0].1), (keys[1], 3_000_000));
+    assert_eq!(pool.total_position(&mint), 3_000_000);
+
+    // 平均 376_333_333，第一个钱包转给另外两个
+    let transfers = pool.rebalance(1_000_000);
+    assert_eq!(
+        transfers,
+        vec![
+            Transfer { from: keys[0], to: keys[1], lamports: 267_333_333 },
+            Transfer { from: keys[0], to: keys[2], lamports: 366_333_333 },
+        ]
+    );
+    assert!(pool.rebalance(400_000_000).is_empty());
+}
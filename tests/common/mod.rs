@@ -0,0 +1,211 @@
+//! 基于 LiteSVM 的进程内 pump.fun 环境。
+//!
+//! 程序文件由 `tests/fixtures/fetch.sh` 从 mainnet 下载并提交到仓库，
+//! 缺少程序文件时测试打印提示并跳过。
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use litesvm::{
+    types::{FailedTransactionMetadata, TransactionMetadata},
+    LiteSVM,
+};
+use solana_program::{program_option::COption, program_pack::Pack};
+use solana_pump::monitor::{
+    find_bonding_curve, BondingCurveAccount, GlobalAccount, BONDING_CURVE_DISCRIMINATOR,
+    GLOBAL_DISCRIMINATOR, INIT_SOL_REVERSES, INIT_TOKEN_REVERSES, MPL_TOKEN_METADATA_PROGRAM_ID,
+    PUMPFUN_FEE_RECIPIENT, PUMPFUN_GLOBAL, PUMPFUN_PROGRAM_ID, PUMPFUN_TOTAL_SUPPLY,
+};
+use solana_sdk::{
+    account::Account, instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
+    signature::Keypair, signer::Signer, transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+/// 新 bonding curve 的可买数量
+pub const INIT_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000;
+
+/// 测试使用的程序文件
+const FIXTURES: [&str; 2] = ["pump.so", "mpl_token_metadata.so"];
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+pub struct PumpEnv {
+    pub svm: LiteSVM,
+    /// global 的 authority，已有 100 SOL
+    pub payer: Keypair,
+}
+
+impl PumpEnv {
+    /// 加载 pump.fun 程序并写入 global 和手续费账户，缺少程序文件时返回 None
+    pub fn load() -> Option<Self> {
+        if let Some(missing) = FIXTURES.iter().map(|name| fixture(name)).find(|path| !path.exists()) {
+            eprintln!("skipping LiteSVM test: {} not found, run tests/fixtures/fetch.sh", missing.display());
+            return None;
+        }
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(PUMPFUN_PROGRAM_ID, fixture("pump.so"))
+            .expect("load pump.so");
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 100 * LAMPORTS_PER_SOL).unwrap();
+
+        let global = GlobalAccount {
+            discriminator: GLOBAL_DISCRIMINATOR,
+            initialized: true,
+            authority: payer.pubkey(),
+            fee_recipient: PUMPFUN_FEE_RECIPIENT,
+            initial_virtual_token_reserves: INIT_TOKEN_REVERSES,
+            initial_virtual_sol_reserves: INIT_SOL_REVERSES,
+            initial_real_token_reserves: INIT_REAL_TOKEN_RESERVES,
+            token_total_supply: PUMPFUN_TOTAL_SUPPLY,
+            fee_basis_points: 100,
+        };
+        let mut env = Self { svm, payer };
+        env.set_program_account(PUMPFUN_GLOBAL, borsh::to_vec(&global).unwrap(), 0);
+        // 手续费账户需要先满足租金要求
+        env.svm
+            .set_account(
+                PUMPFUN_FEE_RECIPIENT,
+                Account {
+                    lamports: LAMPORTS_PER_SOL,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        Some(env)
+    }
+
+    /// 额外加载 token metadata 程序，`create` 需要
+    pub fn with_metadata_program(mut self) -> Self {
+        self.svm
+            .add_program_from_file(MPL_TOKEN_METADATA_PROGRAM_ID, fixture("mpl_token_metadata.so"))
+            .expect("load mpl_token_metadata.so");
+        self
+    }
+
+    fn set_program_account(&mut self, address: Pubkey, data: Vec<u8>, extra_lamports: u64) {
+        let lamports = self.svm.minimum_balance_for_rent_exemption(data.len()) + extra_lamports;
+        self.svm
+            .set_account(
+                address,
+                Account {
+                    lamports,
+                    data,
+                    owner: PUMPFUN_PROGRAM_ID,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+    }
+
+    fn set_token_state<T: Pack>(&mut self, address: Pubkey, state: T) {
+        let mut data = vec![0; T::LEN];
+        T::pack(state, &mut data).unwrap();
+        self.svm
+            .set_account(
+                address,
+                Account {
+                    lamports: self.svm.minimum_balance_for_rent_exemption(T::LEN),
+                    data,
+                    owner: spl_token::id(),
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+    }
+
+    /// 写入一个已创建的代币：mint、bonding curve 和持有剩余 token 的关联账户
+    pub fn add_curve(&mut self, mint: &Pubkey, curve: &BondingCurveAccount) {
+        self.set_token_state(
+            *mint,
+            Mint {
+                mint_authority: COption::None,
+                supply: curve.token_total_supply,
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            },
+        );
+        let bonding_curve = find_bonding_curve(mint);
+        let account = BondingCurveAccount {
+            discriminator: u64::from_le_bytes(BONDING_CURVE_DISCRIMINATOR),
+            ..curve.clone()
+        };
+        self.set_program_account(bonding_curve, borsh::to_vec(&account).unwrap(), curve.real_sol_reserves);
+        self.set_token_state(
+            get_associated_token_address(&bonding_curve, mint),
+            TokenAccount {
+                mint: *mint,
+                owner: bonding_curve,
+                amount: curve.real_token_reserves,
+                delegate: COption::None,
+                state: AccountState::Initialized,
+                is_native: COption::None,
+                delegated_amount: 0,
+                close_authority: COption::None,
+            },
+        );
+    }
+
+    /// 新用户，带 SOL 和该 mint 的 token 账户
+    pub fn user(&mut self, mint: &Pubkey, lamports: u64) -> Keypair {
+        let user = Keypair::new();
+        self.svm.airdrop(&user.pubkey(), lamports).unwrap();
+        let ix = create_associated_token_account_idempotent(&user.pubkey(), &user.pubkey(), mint, &spl_token::id());
+        self.send(&[ix], &[&user]).unwrap();
+        user
+    }
+
+    pub fn send(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<TransactionMetadata, Box<FailedTransactionMetadata>> {
+        let tx = Transaction::new_signed_with_payer(ixs, Some(&signers[0].pubkey()), signers, self.svm.latest_blockhash());
+        self.send_tx(tx)
+    }
+
+    pub fn send_tx(&mut self, tx: Transaction) -> Result<TransactionMetadata, Box<FailedTransactionMetadata>> {
+        let result = self.svm.send_transaction(tx).map_err(Box::new);
+        self.svm.expire_blockhash();
+        result
+    }
+
+    pub fn bonding_curve(&self, mint: &Pubkey) -> BondingCurveAccount {
+        let account = self.svm.get_account(&find_bonding_curve(mint)).expect("bonding curve");
+        borsh::BorshDeserialize::deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub fn lamports(&self, address: &Pubkey) -> u64 {
+        self.svm.get_balance(address).unwrap_or_default()
+    }
+
+    /// token 账户余额，账户不存在时为 None
+    pub fn token_balance(&self, owner: &Pubkey, mint: &Pubkey) -> Option<u64> {
+        let account = self.svm.get_account(&get_associated_token_address(owner, mint))?;
+        if account.data.is_empty() {
+            return None;
+        }
+        Some(TokenAccount::unpack(&account.data).ok()?.amount)
+    }
+}
+
+/// 新发行、还没有成交的曲线
+pub fn fresh_curve() -> BondingCurveAccount {
+    BondingCurveAccount {
+        discriminator: 0,
+        virtual_token_reserves: INIT_TOKEN_REVERSES,
+        virtual_sol_reserves: INIT_SOL_REVERSES,
+        real_token_reserves: INIT_REAL_TOKEN_RESERVES,
+        real_sol_reserves: 0,
+        token_total_supply: PUMPFUN_TOTAL_SUPPLY,
+        complete: false,
+    }
+}
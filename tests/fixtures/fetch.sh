@@ -0,0 +1,7 @@
+#!/usr/bin/env sh
+# 下载 LiteSVM 测试使用的程序文件，下载后提交到仓库
+set -e
+cd "$(dirname "$0")"
+RPC_URL="${RPC_URL:-https://api.mainnet-beta.solana.com}"
+solana program dump -u "$RPC_URL" 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P pump.so
+solana program dump -u "$RPC_URL" metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s mpl_token_metadata.so
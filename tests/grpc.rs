@@ -0,0 +1,107 @@
+mod mock_geyser;
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use mock_geyser::{ping, trade_update, MockGeyser, Step};
+use solana_pump::{
+    grpc::{pumpfun_request, GrpcClient, Reconnect, PUMPFUN_PROGRAM_ID},
+    monitor::{convert_to_encoded_tx, parse_events, TargetEvent, TradeEvent},
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use yellowstone_grpc_proto::{geyser::subscribe_update::UpdateOneof, tonic::Code};
+
+fn reconnect() -> Reconnect {
+    Reconnect {
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        max_attempts: Some(2),
+    }
+}
+
+fn trade(mint: Pubkey) -> TradeEvent {
+    TradeEvent {
+        mint,
+        sol_amount: 1_000_000_000,
+        token_amount: 34_612_903_225_806,
+        is_buy: true,
+        user: Pubkey::new_unique(),
+        timestamp: 1_700_000_000,
+        virtual_sol_reserves: 31_000_000_000,
+        virtual_token_reserves: 1_038_387_287_774_194,
+        real_sol_reserves: 1_000_000_000,
+        real_token_reserves: 758_487_287_774_194,
+    }
+}
+
+#[tokio::test]
+async fn test_reconnects_after_disconnect_and_error() {
+    let mint = Pubkey::new_unique();
+    let (mock, endpoint) = MockGeyser::spawn(vec![
+        vec![trade_update(1, Signature::new_unique(), &trade(mint)), Step::Disconnect],
+        vec![Step::Sleep(Duration::from_millis(20)), Step::Error(Code::Internal)],
+        vec![ping(), trade_update(2, Signature::new_unique(), &trade(mint)), Step::Disconnect],
+    ])
+    .await;
+
+    let stream = GrpcClient::new(endpoint).subscribe_with_reconnect(pumpfun_request(), reconnect());
+    let updates: Vec<_> = tokio::time::timeout(Duration::from_secs(10), stream.collect())
+        .await
+        .expect("stream should end after the scripted sessions");
+
+    assert_eq!(updates.len(), 4);
+    let slot = |i: usize| match &updates[i].as_ref().unwrap().update_oneof {
+        Some(UpdateOneof::Transaction(tx)) => tx.slot,
+        other => panic!("unexpected update {:?}", other),
+    };
+    assert_eq!(slot(0), 1);
+    assert_eq!(updates[1].as_ref().unwrap_err().code(), Code::Internal);
+    assert!(matches!(
+        updates[2].as_ref().unwrap().update_oneof,
+        Some(UpdateOneof::Ping(_))
+    ));
+    assert_eq!(slot(3), 2);
+
+    // 三次成功的订阅，加上之后被拒绝的重试
+    assert_eq!(mock.subscriptions(), 3 + 2);
+    let requests = mock.requests();
+    let filter = &requests[0].transactions["client"];
+    assert_eq!(filter.account_required, vec![PUMPFUN_PROGRAM_ID.to_string()]);
+    assert!(requests.iter().any(|request| request.ping.is_some()));
+}
+
+#[tokio::test]
+async fn test_decodes_pump_trade_from_stream() {
+    let mint = Pubkey::new_unique();
+    let signature = Signature::new_unique();
+    let (_mock, endpoint) =
+        MockGeyser::spawn(vec![vec![trade_update(42, signature, &trade(mint)), Step::Disconnect]]).await;
+
+    let mut stream = GrpcClient::new(endpoint).subscribe_with_reconnect(pumpfun_request(), reconnect());
+    let update = stream.next().await.unwrap().unwrap();
+    let Some(UpdateOneof::Transaction(sub_tx)) = update.update_oneof else {
+        panic!("expected a transaction update");
+    };
+    assert_eq!(sub_tx.slot, 42);
+    let tx_info = sub_tx.transaction.unwrap();
+    assert_eq!(tx_info.signature, signature.as_ref());
+
+    let events = parse_events(&convert_to_encoded_tx(tx_info).unwrap());
+    assert_eq!(events.len(), 1);
+    let TargetEvent::PumpfunBuy(event) = &events[0] else {
+        panic!("expected a buy event");
+    };
+    assert_eq!(event.mint, mint);
+    assert_eq!(event.virtual_sol_reserves, 31_000_000_000);
+}
+
+#[tokio::test]
+async fn test_gives_up_when_server_is_unavailable() {
+    let (mock, endpoint) = MockGeyser::spawn(vec![]).await;
+    let stream = GrpcClient::new(endpoint).subscribe_with_reconnect(pumpfun_request(), reconnect());
+    let updates: Vec<_> = tokio::time::timeout(Duration::from_secs(10), stream.collect())
+        .await
+        .unwrap();
+    assert!(updates.is_empty());
+    assert_eq!(mock.subscriptions(), 3);
+}
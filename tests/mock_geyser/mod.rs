@@ -0,0 +1,242 @@
+//! 进程内的 Yellowstone `Geyser` 服务，按脚本推送更新。
+#![allow(dead_code)]
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures_util::{stream, Stream, StreamExt};
+use solana_pump::monitor::{TradeEvent, PUMPFUN_PROGRAM_ID};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tokio::net::TcpListener;
+use yellowstone_grpc_proto::{
+    geyser::{
+        geyser_server::{Geyser, GeyserServer},
+        subscribe_update::UpdateOneof,
+        GetBlockHeightRequest, GetBlockHeightResponse, GetLatestBlockhashRequest,
+        GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse, GetVersionRequest,
+        GetVersionResponse, IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest,
+        PongResponse, SubscribeRequest, SubscribeUpdate, SubscribeUpdatePing,
+        SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+    },
+    prelude::{
+        CompiledInstruction, InnerInstruction, InnerInstructions, Message, MessageHeader,
+        Transaction, TransactionStatusMeta,
+    },
+    tonic::{self, transport::Server, Request, Response, Status, Streaming},
+};
+
+/// 一次连接里服务端依次执行的动作
+#[derive(Debug, Clone)]
+pub enum Step {
+    Update(Box<SubscribeUpdate>),
+    /// 返回错误并结束这次连接
+    Error(tonic::Code),
+    /// 正常关闭这次连接
+    Disconnect,
+    Sleep(Duration),
+}
+
+#[derive(Default)]
+struct Shared {
+    /// 每次订阅取一个脚本，用完后拒绝订阅
+    sessions: VecDeque<Vec<Step>>,
+    /// 客户端发来的所有请求，包括 ping 回复
+    requests: Vec<SubscribeRequest>,
+    subscriptions: usize,
+}
+
+#[derive(Clone)]
+pub struct MockGeyser {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl MockGeyser {
+    /// 在随机端口启动服务，返回 `http://` 地址
+    pub async fn spawn(sessions: Vec<Vec<Step>>) -> (Self, String) {
+        let mock = Self {
+            shared: Arc::new(Mutex::new(Shared {
+                sessions: sessions.into(),
+                ..Default::default()
+            })),
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let incoming = stream::unfold(listener, |listener| async move {
+            let conn = listener.accept().await.map(|(socket, _)| socket);
+            Some((conn, listener))
+        });
+        let service = GeyserServer::new(mock.clone());
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(service)
+                .serve_with_incoming(incoming)
+                .await
+                .unwrap();
+        });
+        (mock, endpoint)
+    }
+
+    pub fn requests(&self) -> Vec<SubscribeRequest> {
+        self.shared.lock().unwrap().requests.clone()
+    }
+
+    pub fn subscriptions(&self) -> usize {
+        self.shared.lock().unwrap().subscriptions
+    }
+}
+
+type UpdateStream = Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Geyser for MockGeyser {
+    type SubscribeStream = UpdateStream;
+
+    async fn subscribe(
+        &self,
+        request: Request<Streaming<SubscribeRequest>>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let steps = {
+            let mut shared = self.shared.lock().unwrap();
+            shared.subscriptions += 1;
+            shared.sessions.pop_front()
+        };
+        let Some(steps) = steps else {
+            return Err(Status::unavailable("no scripted session left"));
+        };
+
+        let shared = self.shared.clone();
+        let mut inbound = request.into_inner();
+        tokio::spawn(async move {
+            while let Some(Ok(request)) = inbound.next().await {
+                shared.lock().unwrap().requests.push(request);
+            }
+        });
+
+        let updates = stream::unfold(VecDeque::from(steps), |mut steps| async move {
+            loop {
+                match steps.pop_front()? {
+                    Step::Update(update) => return Some((Ok(*update), steps)),
+                    Step::Error(code) => {
+                        steps.clear();
+                        return Some((Err(Status::new(code, "scripted error")), steps));
+                    }
+                    Step::Disconnect => return None,
+                    Step::Sleep(duration) => tokio::time::sleep(duration).await,
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(updates)))
+    }
+
+    async fn ping(&self, request: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+        Ok(Response::new(PongResponse {
+            count: request.into_inner().count,
+        }))
+    }
+
+    async fn get_latest_blockhash(
+        &self,
+        _: Request<GetLatestBlockhashRequest>,
+    ) -> Result<Response<GetLatestBlockhashResponse>, Status> {
+        Err(Status::unimplemented("mock"))
+    }
+
+    async fn get_block_height(
+        &self,
+        _: Request<GetBlockHeightRequest>,
+    ) -> Result<Response<GetBlockHeightResponse>, Status> {
+        Err(Status::unimplemented("mock"))
+    }
+
+    async fn get_slot(&self, _: Request<GetSlotRequest>) -> Result<Response<GetSlotResponse>, Status> {
+        Err(Status::unimplemented("mock"))
+    }
+
+    async fn is_blockhash_valid(
+        &self,
+        _: Request<IsBlockhashValidRequest>,
+    ) -> Result<Response<IsBlockhashValidResponse>, Status> {
+        Err(Status::unimplemented("mock"))
+    }
+
+    async fn get_version(
+        &self,
+        _: Request<GetVersionRequest>,
+    ) -> Result<Response<GetVersionResponse>, Status> {
+        Err(Status::unimplemented("mock"))
+    }
+}
+
+pub fn ping() -> Step {
+    Step::Update(Box::new(SubscribeUpdate {
+        filters: vec!["client".to_string()],
+        update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+        ..Default::default()
+    }))
+}
+
+/// pump.fun 交易：一条外层指令，内层指令里带有 `trade` 的 CPI 事件
+pub fn trade_update(slot: u64, signature: Signature, trade: &TradeEvent) -> Step {
+    // anchor emit_cpi: 8 字节指令鉴别符 + 8 字节事件鉴别符 + borsh 数据
+    let mut data = vec![0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d];
+    data.extend_from_slice(&[189, 219, 127, 211, 78, 230, 97, 238]);
+    data.extend_from_slice(&borsh::to_vec(trade).unwrap());
+
+    let account_keys = [trade.user, trade.mint, PUMPFUN_PROGRAM_ID]
+        .iter()
+        .map(|key: &Pubkey| key.to_bytes().to_vec())
+        .collect();
+    let transaction = Transaction {
+        signatures: vec![signature.as_ref().to_vec()],
+        message: Some(Message {
+            header: Some(MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            }),
+            account_keys,
+            recent_blockhash: vec![7; 32],
+            instructions: vec![CompiledInstruction {
+                program_id_index: 2,
+                accounts: vec![0, 1],
+                data: vec![],
+            }],
+            versioned: false,
+            address_table_lookups: vec![],
+        }),
+    };
+    let meta = TransactionStatusMeta {
+        fee: 5000,
+        pre_balances: vec![10_000_000_000, 0, 1],
+        post_balances: vec![10_000_000_000 - 5000, 0, 1],
+        inner_instructions: vec![InnerInstructions {
+            index: 0,
+            instructions: vec![InnerInstruction {
+                program_id_index: 2,
+                accounts: vec![2],
+                data,
+                stack_height: Some(2),
+            }],
+        }],
+        return_data_none: true,
+        ..Default::default()
+    };
+    Step::Update(Box::new(SubscribeUpdate {
+        filters: vec!["client".to_string()],
+        update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: signature.as_ref().to_vec(),
+                is_vote: false,
+                transaction: Some(transaction),
+                meta: Some(meta),
+                index: 0,
+            }),
+            slot,
+        })),
+        ..Default::default()
+    }))
+}
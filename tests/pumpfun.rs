@@ -0,0 +1,136 @@
+mod common;
+
+use common::{fresh_curve, PumpEnv, INIT_REAL_TOKEN_RESERVES};
+use solana_pump::{
+    curve::Curve,
+    monitor::{
+        buy_amount_out_ix, create_ix, create_sell_transaction, find_bonding_curve, ComputeBudget,
+        CreateArgs, SellParams, INIT_TOKEN_REVERSES, PUMPFUN_FEE_RECIPIENT,
+    },
+};
+use solana_program::program_pack::Pack;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair, signer::Signer,
+};
+use spl_associated_token_account::get_associated_token_address;
+
+#[test]
+fn test_buy_and_sell_against_program() {
+    let Some(mut env) = PumpEnv::load() else {
+        return;
+    };
+    let mint = Pubkey::new_unique();
+    env.add_curve(&mint, &fresh_curve());
+    let bonding_curve = find_bonding_curve(&mint);
+    let user = env.user(&mint, 10 * LAMPORTS_PER_SOL);
+
+    // 买入 1 SOL 能买到的数量，程序按数量收取 SOL
+    let mut expected = Curve::from(&env.bonding_curve(&mint));
+    let buy = expected.buy_exact_tokens(expected.buy_with_sol(LAMPORTS_PER_SOL).tokens);
+    let fee_before = env.lamports(&PUMPFUN_FEE_RECIPIENT);
+    let ix = buy_amount_out_ix(
+        &mint,
+        &bonding_curve,
+        &get_associated_token_address(&bonding_curve, &mint),
+        &user.pubkey(),
+        &get_associated_token_address(&user.pubkey(), &mint),
+        buy.tokens,
+        buy.sol_in() * 101 / 100,
+    );
+    env.send(&[ix], &[&user]).unwrap();
+    expected.apply_buy(&buy);
+
+    assert_eq!(env.token_balance(&user.pubkey(), &mint), Some(buy.tokens));
+    assert_eq!(Curve::from(&env.bonding_curve(&mint)), expected);
+    assert_eq!(env.lamports(&PUMPFUN_FEE_RECIPIENT) - fee_before, buy.fee);
+
+    // 全部卖出并关闭 token 账户
+    let sell = expected.sell(buy.tokens);
+    let user_before = env.lamports(&user.pubkey());
+    let params = SellParams {
+        bonding_curve: &bonding_curve,
+        mint: &mint,
+        price: expected.price(),
+        amount_in_token: buy.tokens,
+        slippage_bps: 1500.0,
+        budget: &ComputeBudget::default(),
+    };
+    let tx = create_sell_transaction(&params, &user, env.svm.latest_blockhash()).unwrap();
+    env.send_tx(tx).unwrap();
+    expected.apply_sell(&sell);
+
+    assert_eq!(env.token_balance(&user.pubkey(), &mint), None);
+    let curve = env.bonding_curve(&mint);
+    assert_eq!(Curve::from(&curve), expected);
+    assert_eq!(curve.virtual_token_reserves, INIT_TOKEN_REVERSES);
+    // 收到卖出所得和退回的租金，扣除签名和优先费
+    let received = env.lamports(&user.pubkey()) - user_before;
+    let rent = env.svm.minimum_balance_for_rent_exemption(spl_token::state::Account::LEN);
+    assert!(received <= sell.sol_out() + rent);
+    assert!(received >= sell.sol_out() + rent - 20_000);
+}
+
+#[test]
+fn test_sell_below_min_sol_output_fails() {
+    let Some(mut env) = PumpEnv::load() else {
+        return;
+    };
+    let mint = Pubkey::new_unique();
+    env.add_curve(&mint, &fresh_curve());
+    let bonding_curve = find_bonding_curve(&mint);
+    let user = env.user(&mint, 10 * LAMPORTS_PER_SOL);
+    let curve = Curve::from(&env.bonding_curve(&mint));
+    let buy = curve.buy_exact_tokens(curve.buy_with_sol(LAMPORTS_PER_SOL).tokens);
+    let ix = buy_amount_out_ix(
+        &mint,
+        &bonding_curve,
+        &get_associated_token_address(&bonding_curve, &mint),
+        &user.pubkey(),
+        &get_associated_token_address(&user.pubkey(), &mint),
+        buy.tokens,
+        buy.sol_in(),
+    );
+    env.send(&[ix], &[&user]).unwrap();
+    let after_buy = env.bonding_curve(&mint);
+
+    // 按两倍价格、零滑点卖出，最少收到的 SOL 达不到
+    let params = SellParams {
+        bonding_curve: &bonding_curve,
+        mint: &mint,
+        price: Curve::from(&after_buy).price() * 2.0,
+        amount_in_token: buy.tokens,
+        slippage_bps: 0.0,
+        budget: &ComputeBudget::default(),
+    };
+    let tx = create_sell_transaction(&params, &user, env.svm.latest_blockhash()).unwrap();
+    assert!(env.send_tx(tx).is_err());
+    assert_eq!(env.token_balance(&user.pubkey(), &mint), Some(buy.tokens));
+    assert_eq!(Curve::from(&env.bonding_curve(&mint)), Curve::from(&after_buy));
+}
+
+#[test]
+fn test_create_initializes_curve_from_global() {
+    let Some(env) = PumpEnv::load() else {
+        return;
+    };
+    let mut env = env.with_metadata_program();
+    let mint = Keypair::new();
+    let creator = env.payer.insecure_clone();
+    let ix = create_ix(
+        &mint.pubkey(),
+        &creator.pubkey(),
+        &CreateArgs {
+            name: "Test".to_string(),
+            symbol: "TEST".to_string(),
+            uri: "https://example.com/test.json".to_string(),
+        },
+    )
+    .unwrap();
+    env.send(&[ix], &[&creator, &mint]).unwrap();
+
+    let curve = env.bonding_curve(&mint.pubkey());
+    assert_eq!(Curve::from(&curve), Curve::from(&fresh_curve()));
+    assert!(!curve.complete);
+    let bonding_curve = find_bonding_curve(&mint.pubkey());
+    assert_eq!(env.token_balance(&bonding_curve, &mint.pubkey()), Some(INIT_REAL_TOKEN_RESERVES));
+}